@@ -0,0 +1,133 @@
+//!
+//! Generates `include/rc-fshare/rtp.hpp`.
+//!
+//! GCC and Clang allocate bitfields starting from the least significant bit on the little
+//! endian targets we build for, so the sub-byte fields of each byte are emitted in the reverse
+//! order of the schema.
+//!
+
+use std::fmt::Write;
+
+use crate::rust::comment;
+use crate::schema::{Enum, FieldKind, Layout, PlacedField, Schema, Variant};
+use crate::HEADER;
+
+/// Generate the C++ header
+pub fn header(schema: &Schema, layouts: &[Layout]) -> String {
+    let mut out = String::new();
+    writeln!(out, "{}", comment(HEADER, "//")).unwrap();
+    out.push('\n');
+    writeln!(out, "#pragma once").unwrap();
+    out.push('\n');
+    writeln!(out, "#include <cstdint>").unwrap();
+    writeln!(out, "#include <string>").unwrap();
+    writeln!(out, "#include <vector>").unwrap();
+    out.push('\n');
+    writeln!(out, "namespace rtp {{").unwrap();
+
+    for ty in &schema.enums {
+        out.push('\n');
+        writeln!(out, "{}", doc(&ty.doc)).unwrap();
+        writeln!(out, "enum {} {{", ty.cpp_name()).unwrap();
+        for variant in &ty.variants {
+            writeln!(out, "    {} = {},", enumerator(ty, variant), variant.value).unwrap();
+        }
+        writeln!(out, "}};").unwrap();
+    }
+
+    for layout in layouts {
+        let message = layout.message;
+        out.push('\n');
+        writeln!(out, "{}", doc(&message.doc)).unwrap();
+        writeln!(out, "struct {} {{", message.name).unwrap();
+        for constant in &message.constants {
+            if let Some(note) = &constant.deprecated {
+                writeln!(out, "    [[deprecated({:?})]]", note).unwrap();
+            }
+            writeln!(
+                out,
+                "    static constexpr float {} = {:?}f;",
+                constant.name, constant.value
+            )
+            .unwrap();
+        }
+        if !message.constants.is_empty() {
+            out.push('\n');
+        }
+
+        let mut byte = 0;
+        while byte < layout.size {
+            let mut fields: Vec<&PlacedField> = layout.fields_in(byte).collect();
+            fields.reverse();
+            for placed in &fields {
+                writeln!(out, "    // {}", placed.field.doc).unwrap();
+                match placed.kind {
+                    FieldKind::Integer { cpp, .. } => {
+                        writeln!(out, "    {} {};", cpp, placed.field.name).unwrap()
+                    }
+                    _ => writeln!(
+                        out,
+                        "    unsigned {} : {};",
+                        placed.field.name, placed.field.bits
+                    )
+                    .unwrap(),
+                }
+            }
+            byte += match fields[..] {
+                [PlacedField {
+                    kind: FieldKind::Integer { bytes, .. },
+                    ..
+                }] => *bytes,
+                _ => 1,
+            };
+        }
+        writeln!(out, "}} __attribute__((packed));").unwrap();
+        out.push('\n');
+        writeln!(
+            out,
+            "static_assert(sizeof({}) == {}, \"{} does not match the packed layout\");",
+            message.name, layout.size, message.name
+        )
+        .unwrap();
+    }
+
+    out.push('\n');
+    writeln!(out, "}}  // namespace rtp").unwrap();
+    out
+}
+
+/// A C++ doc comment
+pub(crate) fn doc(text: &str) -> String {
+    let mut out = String::from("/**\n");
+    for line in text.trim().lines() {
+        if line.is_empty() {
+            out.push_str("*\n");
+        } else {
+            writeln!(out, "* {}", line).unwrap();
+        }
+    }
+    out.push_str("*/");
+    out
+}
+
+/// The name of a variant's C++ enumerator.  The enums are unscoped, so the enumerators are
+/// prefixed with the name of their enum unless the schema says otherwise
+fn enumerator(ty: &Enum, variant: &Variant) -> String {
+    let prefix = match &ty.cpp_prefix {
+        Some(prefix) => prefix.clone(),
+        None => format!("{}_", screaming_snake_case(ty.cpp_name())),
+    };
+    prefix + &screaming_snake_case(&variant.name)
+}
+
+/// Convert a CamelCase name to SCREAMING_SNAKE_CASE
+fn screaming_snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (index, c) in name.chars().enumerate() {
+        if c.is_uppercase() && index != 0 {
+            out.push('_');
+        }
+        out.push(c.to_ascii_uppercase());
+    }
+    out
+}
@@ -0,0 +1,480 @@
+//!
+//! Generates the C ABI of the rtp crate (`src/generated/ffi.rs`) and its C header
+//! (`include/rc-fshare/rtp_ffi.h`).
+//!
+
+use std::fmt::Write;
+
+use crate::cpp::doc;
+use crate::rust::comment;
+use crate::schema::{FieldKind, Layout, Schema};
+use crate::HEADER;
+
+/// The status codes returned by every C function as (name, value, doc)
+const STATUS_CODES: [(&str, i32, &str); 5] = [
+    ("RTP_OK", 0, "The operation succeeded"),
+    ("RTP_ERROR_NULL_POINTER", -1, "A pointer argument was null"),
+    (
+        "RTP_ERROR_BUFFER_TOO_SHORT",
+        -2,
+        "The buffer is too short for the message",
+    ),
+    (
+        "RTP_ERROR_INVALID_DISCRIMINANT",
+        -3,
+        "A field contains a value that does not correspond to any variant of its enum",
+    ),
+    ("RTP_ERROR_OTHER", -4, "Any other error"),
+];
+
+/// How a field of a mirror converts to and from the Rust message
+enum Conversion {
+    /// The field has the same type in the mirror and the message
+    Plain,
+    /// An enum passed as its raw value
+    Enum,
+}
+
+/// A field of a `#[repr(C)]` mirror
+struct MirrorField<'a> {
+    name: &'a str,
+    rust: &'static str,
+    c: &'static str,
+    count: Option<usize>,
+    conversion: Conversion,
+    doc: &'a str,
+}
+
+/// A `#[repr(C)]` mirror of a message
+struct Mirror<'a> {
+    name: &'a str,
+    module: &'a str,
+    size_constant: &'a str,
+    size: usize,
+    doc: &'a str,
+    fields: Vec<MirrorField<'a>>,
+}
+
+impl Mirror<'_> {
+    /// The name of the mirror struct
+    fn mirror_name(&self) -> String {
+        format!("Rtp{}", self.name)
+    }
+
+    /// The prefix of the C functions for the message
+    fn function_prefix(&self) -> String {
+        format!("rtp_{}", snake_case(self.name))
+    }
+
+    /// Whether converting the mirror into the message can fail
+    fn fallible(&self) -> bool {
+        self.fields
+            .iter()
+            .any(|field| matches!(field.conversion, Conversion::Enum))
+    }
+}
+
+/// Every message exposed through the C ABI
+fn mirrors<'a>(schema: &'a Schema, layouts: &'a [Layout]) -> Result<Vec<Mirror<'a>>, String> {
+    let mut mirrors = Vec::new();
+    for layout in layouts {
+        let message = layout.message;
+        let fields = layout
+            .fields
+            .iter()
+            .filter_map(|placed| {
+                let (rust, c, conversion) = match placed.kind {
+                    FieldKind::Reserved => return None,
+                    FieldKind::Bool => ("bool", "bool", Conversion::Plain),
+                    FieldKind::Unsigned | FieldKind::RobotId => {
+                        ("u8", "uint8_t", Conversion::Plain)
+                    }
+                    FieldKind::Enum(_) => ("u8", "uint8_t", Conversion::Enum),
+                    FieldKind::Integer { rust, cpp, .. } => (rust, cpp, Conversion::Plain),
+                };
+                Some(MirrorField {
+                    name: &placed.field.name,
+                    rust,
+                    c,
+                    count: None,
+                    conversion,
+                    doc: &placed.field.doc,
+                })
+            })
+            .collect();
+        mirrors.push(Mirror {
+            name: &message.name,
+            module: &message.module,
+            size_constant: &message.size_constant,
+            size: layout.size,
+            doc: &message.doc,
+            fields,
+        });
+    }
+
+    for message in &schema.ffi_messages {
+        let mut fields = Vec::new();
+        for field in &message.fields {
+            let (rust, c) = match field.ty.as_str() {
+                "bool" => ("bool", "bool"),
+                "u8" => ("u8", "uint8_t"),
+                "i8" => ("i8", "int8_t"),
+                "u16" => ("u16", "uint16_t"),
+                "i16" => ("i16", "int16_t"),
+                "u32" => ("u32", "uint32_t"),
+                "i32" => ("i32", "int32_t"),
+                "f32" => ("f32", "float"),
+                ty => {
+                    return Err(format!(
+                        "{}.{}: unsupported type {}",
+                        message.name, field.name, ty
+                    ))
+                }
+            };
+            fields.push(MirrorField {
+                name: &field.name,
+                rust,
+                c,
+                count: field.count,
+                conversion: Conversion::Plain,
+                doc: &field.doc,
+            });
+        }
+        mirrors.push(Mirror {
+            name: &message.name,
+            module: &message.module,
+            size_constant: &message.size_constant,
+            size: message.size,
+            doc: &message.doc,
+            fields,
+        });
+    }
+    Ok(mirrors)
+}
+
+/// Generate `src/generated/ffi.rs`
+pub fn rust(schema: &Schema, layouts: &[Layout]) -> Result<String, String> {
+    let mirrors = mirrors(schema, layouts)?;
+
+    let mut out = String::new();
+    writeln!(out, "{}", comment(HEADER, "//")).unwrap();
+    out.push('\n');
+    writeln!(out, "use ncomm_utils::packing::Packable;").unwrap();
+    out.push('\n');
+
+    let mut modules: Vec<&str> = mirrors.iter().map(|mirror| mirror.module).collect();
+    modules.dedup();
+    for module in modules {
+        let names: Vec<&str> = mirrors
+            .iter()
+            .filter(|mirror| mirror.module == module)
+            .flat_map(|mirror| [mirror.name, mirror.size_constant])
+            .collect();
+        writeln!(out, "use crate::{}::{{{}}};", module, names.join(", ")).unwrap();
+    }
+    writeln!(out, "use crate::RtpError;").unwrap();
+    out.push('\n');
+    writeln!(out, "use super::status;").unwrap();
+
+    for (name, value, doc) in STATUS_CODES {
+        out.push('\n');
+        writeln!(out, "/// {}", doc).unwrap();
+        writeln!(out, "pub const {}: i32 = {};", name, value).unwrap();
+    }
+
+    for mirror in &mirrors {
+        let name = mirror.name;
+        let mirror_name = mirror.mirror_name();
+        let prefix = mirror.function_prefix();
+
+        out.push('\n');
+        writeln!(
+            out,
+            "const _: () = assert!({} == {});",
+            mirror.size_constant, mirror.size
+        )
+        .unwrap();
+        out.push('\n');
+
+        // the mirror
+        writeln!(out, "/// C mirror of `{}`", name).unwrap();
+        if mirror.fallible() {
+            writeln!(out, "///").unwrap();
+            writeln!(out, "/// Note: enums are passed as their raw value").unwrap();
+        }
+        writeln!(out, "#[repr(C)]").unwrap();
+        writeln!(out, "#[derive(Clone, Copy, Debug, Default, PartialEq)]").unwrap();
+        writeln!(out, "pub struct {} {{", mirror_name).unwrap();
+        for field in &mirror.fields {
+            writeln!(out, "    /// {}", field.doc).unwrap();
+            match field.count {
+                Some(count) => {
+                    writeln!(out, "    pub {}: [{}; {}],", field.name, field.rust, count).unwrap()
+                }
+                None => writeln!(out, "    pub {}: {},", field.name, field.rust).unwrap(),
+            }
+        }
+        writeln!(out, "}}").unwrap();
+        out.push('\n');
+
+        // conversions
+        writeln!(out, "impl From<{}> for {} {{", name, mirror_name).unwrap();
+        writeln!(out, "    fn from(message: {}) -> Self {{", name).unwrap();
+        writeln!(out, "        Self {{").unwrap();
+        for field in &mirror.fields {
+            match field.conversion {
+                Conversion::Plain => {
+                    writeln!(out, "            {}: message.{},", field.name, field.name).unwrap()
+                }
+                Conversion::Enum => writeln!(
+                    out,
+                    "            {}: message.{} as u8,",
+                    field.name, field.name
+                )
+                .unwrap(),
+            }
+        }
+        writeln!(out, "        }}").unwrap();
+        writeln!(out, "    }}").unwrap();
+        writeln!(out, "}}").unwrap();
+        out.push('\n');
+
+        if mirror.fallible() {
+            writeln!(out, "impl TryFrom<{}> for {} {{", mirror_name, name).unwrap();
+            writeln!(out, "    type Error = RtpError;").unwrap();
+            out.push('\n');
+            writeln!(
+                out,
+                "    fn try_from(mirror: {}) -> Result<Self, Self::Error> {{",
+                mirror_name
+            )
+            .unwrap();
+            writeln!(out, "        Ok(Self {{").unwrap();
+            for field in &mirror.fields {
+                match field.conversion {
+                    Conversion::Plain => {
+                        writeln!(out, "            {}: mirror.{},", field.name, field.name).unwrap()
+                    }
+                    Conversion::Enum => writeln!(
+                        out,
+                        "            {}: mirror.{}.try_into()?,",
+                        field.name, field.name
+                    )
+                    .unwrap(),
+                }
+            }
+            writeln!(out, "        }})").unwrap();
+        } else {
+            writeln!(out, "impl From<{}> for {} {{", mirror_name, name).unwrap();
+            writeln!(out, "    fn from(mirror: {}) -> Self {{", mirror_name).unwrap();
+            writeln!(out, "        Self {{").unwrap();
+            for field in &mirror.fields {
+                writeln!(out, "            {}: mirror.{},", field.name, field.name).unwrap();
+            }
+            writeln!(out, "        }}").unwrap();
+        }
+        writeln!(out, "    }}").unwrap();
+        writeln!(out, "}}").unwrap();
+        out.push('\n');
+
+        // pack
+        writeln!(
+            out,
+            "/// Pack a `{}` into `buffer`, returning `RTP_OK` or a negative error code",
+            name
+        )
+        .unwrap();
+        writeln!(out, "///").unwrap();
+        writeln!(out, "/// # Safety").unwrap();
+        writeln!(
+            out,
+            "/// `message` must point to a valid `{}` and `buffer` to `buffer_len` writable Bytes",
+            mirror_name
+        )
+        .unwrap();
+        writeln!(out, "#[no_mangle]").unwrap();
+        writeln!(out, "pub unsafe extern \"C\" fn {}_pack(", prefix).unwrap();
+        writeln!(out, "    message: *const {},", mirror_name).unwrap();
+        writeln!(out, "    buffer: *mut u8,").unwrap();
+        writeln!(out, "    buffer_len: usize,").unwrap();
+        writeln!(out, ") -> i32 {{").unwrap();
+        writeln!(out, "    if message.is_null() || buffer.is_null() {{").unwrap();
+        writeln!(out, "        return RTP_ERROR_NULL_POINTER;").unwrap();
+        writeln!(out, "    }}").unwrap();
+        writeln!(
+            out,
+            "    let buffer = core::slice::from_raw_parts_mut(buffer, buffer_len);"
+        )
+        .unwrap();
+        if mirror.fallible() {
+            writeln!(out, "    status(").unwrap();
+            writeln!(out, "        {}::try_from(*message)", name).unwrap();
+            writeln!(
+                out,
+                "            .and_then(|message| message.pack(buffer).map_err(RtpError::from)),"
+            )
+            .unwrap();
+            writeln!(out, "    )").unwrap();
+        } else {
+            writeln!(
+                out,
+                "    status({}::from(*message).pack(buffer).map_err(RtpError::from))",
+                name
+            )
+            .unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+        out.push('\n');
+
+        // unpack
+        writeln!(
+            out,
+            "/// Unpack a `{}` from `data` into `message`, returning `RTP_OK` or a negative error code",
+            name
+        )
+        .unwrap();
+        writeln!(out, "///").unwrap();
+        writeln!(out, "/// # Safety").unwrap();
+        writeln!(
+            out,
+            "/// `data` must point to `data_len` readable Bytes and `message` to a writable `{}`",
+            mirror_name
+        )
+        .unwrap();
+        writeln!(out, "#[no_mangle]").unwrap();
+        writeln!(out, "pub unsafe extern \"C\" fn {}_unpack(", prefix).unwrap();
+        writeln!(out, "    data: *const u8,").unwrap();
+        writeln!(out, "    data_len: usize,").unwrap();
+        writeln!(out, "    message: *mut {},", mirror_name).unwrap();
+        writeln!(out, ") -> i32 {{").unwrap();
+        writeln!(out, "    if data.is_null() || message.is_null() {{").unwrap();
+        writeln!(out, "        return RTP_ERROR_NULL_POINTER;").unwrap();
+        writeln!(out, "    }}").unwrap();
+        writeln!(
+            out,
+            "    let data = core::slice::from_raw_parts(data, data_len);"
+        )
+        .unwrap();
+        writeln!(out, "    match {}::unpack(data) {{", name).unwrap();
+        writeln!(out, "        Ok(unpacked) => {{").unwrap();
+        writeln!(out, "            message.write(unpacked.into());").unwrap();
+        writeln!(out, "            RTP_OK").unwrap();
+        writeln!(out, "        }}").unwrap();
+        writeln!(out, "        Err(err) => status(Err(err.into())),").unwrap();
+        writeln!(out, "    }}").unwrap();
+        writeln!(out, "}}").unwrap();
+    }
+    Ok(out)
+}
+
+/// Generate `include/rc-fshare/rtp_ffi.h`
+pub fn header(schema: &Schema, layouts: &[Layout]) -> Result<String, String> {
+    let mirrors = mirrors(schema, layouts)?;
+
+    let mut out = String::new();
+    writeln!(out, "{}", comment(HEADER, "//")).unwrap();
+    writeln!(out, "//").unwrap();
+    writeln!(
+        out,
+        "// C ABI of the Rust rtp packers (fshare-rust/src/ffi.rs).  Link against the"
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "// robojackets-robocup-rtp-ffi static library (see RC_FSHARE_RUST_FFI in CMakeLists.txt)."
+    )
+    .unwrap();
+    out.push('\n');
+    writeln!(out, "#pragma once").unwrap();
+    out.push('\n');
+    writeln!(out, "#include <stdbool.h>").unwrap();
+    writeln!(out, "#include <stddef.h>").unwrap();
+    writeln!(out, "#include <stdint.h>").unwrap();
+    out.push('\n');
+    writeln!(out, "#ifdef __cplusplus").unwrap();
+    writeln!(out, "extern \"C\" {{").unwrap();
+    writeln!(out, "#endif").unwrap();
+    out.push('\n');
+
+    for (name, value, doc) in STATUS_CODES {
+        writeln!(out, "// {}", doc).unwrap();
+        if value < 0 {
+            writeln!(out, "#define {} ({})", name, value).unwrap();
+        } else {
+            writeln!(out, "#define {} {}", name, value).unwrap();
+        }
+    }
+
+    for mirror in &mirrors {
+        let mirror_name = mirror.mirror_name();
+        let prefix = mirror.function_prefix();
+
+        out.push('\n');
+        writeln!(out, "// The size of a packed {} in Bytes", mirror.name).unwrap();
+        writeln!(
+            out,
+            "#define {}_SIZE {}",
+            prefix.to_uppercase(),
+            mirror.size
+        )
+        .unwrap();
+        out.push('\n');
+        let mut text = mirror.doc.trim().to_owned();
+        if mirror.fallible() {
+            text.push_str("\n\nEnums are passed as their raw value (see rtp.hpp).");
+        }
+        writeln!(out, "{}", doc(&text)).unwrap();
+        writeln!(out, "typedef struct {} {{", mirror_name).unwrap();
+        for field in &mirror.fields {
+            writeln!(out, "    // {}", field.doc).unwrap();
+            match field.count {
+                Some(count) => writeln!(out, "    {} {}[{}];", field.c, field.name, count).unwrap(),
+                None => writeln!(out, "    {} {};", field.c, field.name).unwrap(),
+            }
+        }
+        writeln!(out, "}} {};", mirror_name).unwrap();
+        out.push('\n');
+        writeln!(
+            out,
+            "// Pack a {} into buffer, returning RTP_OK or a negative error code",
+            mirror.name
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "int32_t {}_pack(const {} *message, uint8_t *buffer, size_t buffer_len);",
+            prefix, mirror_name
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "// Unpack a {} from data, returning RTP_OK or a negative error code",
+            mirror.name
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "int32_t {}_unpack(const uint8_t *data, size_t data_len, {} *message);",
+            prefix, mirror_name
+        )
+        .unwrap();
+    }
+
+    out.push('\n');
+    writeln!(out, "#ifdef __cplusplus").unwrap();
+    writeln!(out, "}}  // extern \"C\"").unwrap();
+    writeln!(out, "#endif").unwrap();
+    Ok(out)
+}
+
+/// Convert a CamelCase name to snake_case
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (index, c) in name.chars().enumerate() {
+        if c.is_uppercase() && index != 0 {
+            out.push('_');
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    out
+}
@@ -0,0 +1,53 @@
+//!
+//! Generates the ASCII packed layout tables used in the documentation of each message.
+//!
+
+use crate::schema::{FieldKind, Layout};
+
+/// The border between two rows of the table
+const BORDER: &str = "+---------+---------+---------+---------+---------+---------+---------+---------+";
+
+/// Generate the layout table of a message
+pub fn table(layout: &Layout) -> String {
+    let mut lines = vec![BORDER.to_string()];
+    lines.push(row((0..8).rev().map(|bit| (format!("   {}", bit), 1))));
+    lines.push(BORDER.into());
+
+    for byte in 0..layout.size {
+        let cells = layout.fields_in(byte).map(|placed| match placed.kind {
+            FieldKind::Integer { bytes, .. } if bytes > 1 => {
+                let index = byte - placed.byte;
+                let part = if index == 0 {
+                    "(lsb)".into()
+                } else if index == bytes - 1 {
+                    "(msb)".into()
+                } else {
+                    format!("(byte {})", index)
+                };
+                (format!("{} {}", placed.field.label(), part), 8)
+            }
+            _ => (placed.field.label().to_string(), placed.field.bits),
+        });
+        lines.push(row(cells));
+        lines.push(BORDER.into());
+    }
+
+    lines.push(String::new());
+    lines.push(format!(
+        "Size = {} Bits = {} Bytes",
+        layout.size * 8,
+        layout.size
+    ));
+    lines.join("\n") + "\n"
+}
+
+/// A row of the table made of (label, bits) cells
+fn row(cells: impl Iterator<Item = (String, u32)>) -> String {
+    let mut row = String::from("|");
+    for (label, bits) in cells {
+        let width = 10 * bits as usize - 2;
+        let label: String = label.chars().take(width).collect();
+        row.push_str(&format!(" {:<width$}|", label, width = width));
+    }
+    row
+}
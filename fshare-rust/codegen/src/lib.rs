@@ -0,0 +1,177 @@
+//!
+//! rtp-codegen generates everything that depends on the packed layout of the rtp messages from
+//! a single schema (`fshare-rust/schema/messages.toml`):
+//!
+//! - the `Packable` implementations in `fshare-rust/src/generated`
+//! - the layout tables included in the documentation of each message
+//! - the C++ header `include/rc-fshare/rtp.hpp`
+//! - the C ABI in `fshare-rust/src/generated/ffi.rs` and its header `include/rc-fshare/rtp_ffi.h`
+//!
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+mod cpp;
+mod ffi;
+mod layout;
+mod rust;
+pub mod schema;
+mod source;
+
+use schema::Schema;
+
+/// The comment at the top of every generated file
+const HEADER: &str = "@generated by rtp-codegen from fshare-rust/schema/messages.toml.\n\
+                      Do not edit by hand, run `cargo run -p rtp-codegen` from fshare-rust instead.";
+
+/// The path of the schema relative to the repository root
+pub const SCHEMA_PATH: &str = "fshare-rust/schema/messages.toml";
+
+/// A generated file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GeneratedFile {
+    /// The path of the file relative to the repository root
+    pub path: PathBuf,
+    /// The contents of the file
+    pub contents: String,
+}
+
+/// The root of the repository this crate lives in
+pub fn repository_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../..")
+}
+
+/// Generate every file from a schema, reading the fields of the `ffi_message`s from the rtp
+/// crate in the repository at `root`
+pub fn generate(source: &str, root: &Path) -> Result<Vec<GeneratedFile>, String> {
+    let mut schema = Schema::parse(source)?;
+    for message in &mut schema.ffi_messages {
+        let path = root
+            .join("fshare-rust/src")
+            .join(format!("{}.rs", message.module));
+        let module = fs::read_to_string(&path)
+            .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+        message.fields = source::struct_fields(&module, &message.name)?;
+    }
+
+    let layouts = schema
+        .messages
+        .iter()
+        .map(|message| schema.layout(message))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let generated = Path::new("fshare-rust/src/generated");
+    let mut files = vec![GeneratedFile {
+        path: generated.join("mod.rs"),
+        contents: rust::module(&schema),
+    }];
+    for layout in &layouts {
+        let module = &layout.message.module;
+        files.push(GeneratedFile {
+            path: generated.join(format!("{}.rs", module)),
+            contents: rust::message(layout),
+        });
+        files.push(GeneratedFile {
+            path: generated.join(format!("{}_layout.txt", module)),
+            contents: layout::table(layout),
+        });
+    }
+    files.push(GeneratedFile {
+        path: "include/rc-fshare/rtp.hpp".into(),
+        contents: cpp::header(&schema, &layouts),
+    });
+    files.push(GeneratedFile {
+        path: generated.join("ffi.rs"),
+        contents: ffi::rust(&schema, &layouts)?,
+    });
+    files.push(GeneratedFile {
+        path: "include/rc-fshare/rtp_ffi.h".into(),
+        contents: ffi::header(&schema, &layouts)?,
+    });
+    Ok(files)
+}
+
+/// Generate every file from the schema in the repository
+pub fn generate_from_repository(root: &Path) -> Result<Vec<GeneratedFile>, String> {
+    let path = root.join(SCHEMA_PATH);
+    let source = fs::read_to_string(&path)
+        .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+    generate(&source, root)
+}
+
+/// The generated files whose contents differ from the files in the repository
+pub fn stale_files(root: &Path, files: &[GeneratedFile]) -> Vec<PathBuf> {
+    files
+        .iter()
+        .filter(|file| {
+            fs::read_to_string(root.join(&file.path)).ok().as_deref() != Some(&file.contents)
+        })
+        .map(|file| file.path.clone())
+        .collect()
+}
+
+/// Write the generated files into the repository
+pub fn write(root: &Path, files: &[GeneratedFile]) -> io::Result<()> {
+    for file in files {
+        let path = root.join(&file.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, &file.contents)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that the checked in generated files match the schema.  If this fails, run
+    /// `cargo run -p rtp-codegen` and commit the result
+    #[test]
+    fn test_generated_files_are_up_to_date() {
+        let root = repository_root();
+        let files = generate_from_repository(&root).unwrap();
+        assert_eq!(stale_files(&root, &files), Vec::<PathBuf>::new());
+    }
+
+    /// Test that fields which do not fit in the packed layout are rejected
+    #[test]
+    fn test_invalid_layouts_are_rejected() {
+        let root = repository_root();
+        let generate_message = |fields: &str| {
+            let source = format!(
+                "[[message]]\nname = \"Test\"\nmodule = \"test\"\nsize_constant = \"TEST_SIZE\"\n\
+                 doc = \"\"\nfields = [{}]",
+                fields
+            );
+            generate(&source, &root)
+        };
+
+        assert!(generate_message(
+            r#"{ name = "a", type = "u8", bits = 4, doc = "" },
+               { name = "b", type = "u8", bits = 4, doc = "" }"#
+        )
+        .is_ok());
+        assert_eq!(
+            generate_message(
+                r#"{ name = "a", type = "u8", bits = 6, doc = "" },
+                   { name = "b", type = "u8", bits = 4, doc = "" },
+                   { name = "c", type = "reserved", bits = 6, doc = "" }"#
+            ),
+            Err("Test.b: field crosses a byte boundary".into())
+        );
+        assert_eq!(
+            generate_message(
+                r#"{ name = "a", type = "bool", bits = 1, doc = "" },
+                   { name = "b", type = "i16", bits = 16, doc = "" }"#
+            ),
+            Err("Test.b: multi-bit integers must be byte aligned".into())
+        );
+        assert_eq!(
+            generate_message(r#"{ name = "a", type = "u8", bits = 4, doc = "" }"#),
+            Err("Test: 4 bits is not a whole number of bytes".into())
+        );
+    }
+}
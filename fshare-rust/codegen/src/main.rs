@@ -0,0 +1,45 @@
+//!
+//! Regenerate the files derived from `fshare-rust/schema/messages.toml`.
+//!
+//! Usage: `cargo run -p rtp-codegen [-- --check]`
+//!
+//! With `--check` nothing is written and the process exits with an error if any generated file
+//! is out of date.
+//!
+
+use std::env;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let check = env::args().skip(1).any(|arg| arg == "--check");
+
+    let root = rtp_codegen::repository_root();
+    let files = match rtp_codegen::generate_from_repository(&root) {
+        Ok(files) => files,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if check {
+        let stale = rtp_codegen::stale_files(&root, &files);
+        for path in &stale {
+            eprintln!("out of date: {}", path.display());
+        }
+        return if stale.is_empty() {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        };
+    }
+
+    if let Err(err) = rtp_codegen::write(&root, &files) {
+        eprintln!("error: {}", err);
+        return ExitCode::FAILURE;
+    }
+    for file in &files {
+        println!("wrote {}", file.path.display());
+    }
+    ExitCode::SUCCESS
+}
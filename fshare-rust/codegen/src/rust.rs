@@ -0,0 +1,347 @@
+//!
+//! Generates the `Packable` implementations of the rtp crate.
+//!
+
+use std::fmt::Write;
+
+use crate::schema::{FieldKind, Layout, PlacedField, Schema};
+use crate::HEADER;
+
+/// Generate `src/generated/mod.rs`, which declares the generated modules and checks that the
+/// hand-written enums match the schema
+pub fn module(schema: &Schema) -> String {
+    let mut out = String::new();
+    writeln!(out, "{}", comment(HEADER, "//")).unwrap();
+    out.push('\n');
+    for message in &schema.messages {
+        writeln!(out, "mod {};", message.module).unwrap();
+    }
+    out.push('\n');
+
+    let mut modules: Vec<&str> = schema.enums.iter().map(|ty| ty.module.as_str()).collect();
+    modules.sort();
+    modules.dedup();
+    for module in modules {
+        let mut names: Vec<&str> = schema
+            .enums
+            .iter()
+            .filter(|ty| ty.module == module)
+            .map(|ty| ty.name.as_str())
+            .collect();
+        names.sort();
+        writeln!(out, "{}", use_item(module, &names)).unwrap();
+    }
+
+    for ty in &schema.enums {
+        out.push('\n');
+        for variant in &ty.variants {
+            writeln!(
+                out,
+                "const _: () = assert!({}::{} as u8 == {});",
+                ty.name, variant.name, variant.value
+            )
+            .unwrap();
+        }
+    }
+    out
+}
+
+/// Generate `src/generated/<module>.rs` for a message
+pub fn message(layout: &Layout) -> String {
+    let message = layout.message;
+    let size = &message.size_constant;
+    let robot_id = layout.robot_id();
+
+    let mut out = String::new();
+    writeln!(out, "{}", comment(HEADER, "//")).unwrap();
+    out.push('\n');
+    writeln!(out, "use ncomm_utils::packing::{{Packable, PackingError}};").unwrap();
+    out.push('\n');
+
+    // imports
+    let enums = layout.enums();
+    let mut local: Vec<&str> = vec![message.name.as_str()];
+    let mut root = vec!["RtpError"];
+    for ty in &enums {
+        if ty.module == message.module {
+            local.push(&ty.name);
+        } else if ty.module == "crate" {
+            root.push(&ty.name);
+        }
+    }
+    local[1..].sort();
+    local.push(size);
+    local.extend(
+        message
+            .constants
+            .iter()
+            .filter(|constant| constant.deprecated.is_none())
+            .map(|constant| constant.name.as_str()),
+    );
+    if robot_id.is_some() {
+        root.push("ROBOTS_PER_TEAM");
+    }
+    writeln!(out, "{}", use_item(&message.module, &local)).unwrap();
+    for ty in &enums {
+        if ty.module != message.module && ty.module != "crate" {
+            writeln!(out, "{}", use_item(&ty.module, &[&ty.name])).unwrap();
+        }
+    }
+    writeln!(out, "use crate::error::check_len;").unwrap();
+    writeln!(out, "{}", use_item("crate", &root)).unwrap();
+    out.push('\n');
+
+    // the hand-written constants must match the schema
+    writeln!(out, "const _: () = assert!({} == {});", size, layout.size).unwrap();
+    for constant in &message.constants {
+        if constant.deprecated.is_some() {
+            // deprecated constants are not imported, so using them does not warn
+            writeln!(out, "#[allow(deprecated)]").unwrap();
+            writeln!(
+                out,
+                "const _: () = assert!(crate::{}::{} == {:?});",
+                message.module, constant.name, constant.value
+            )
+            .unwrap();
+        } else {
+            writeln!(out, "const _: () = assert!({} == {:?});", constant.name, constant.value)
+                .unwrap();
+        }
+    }
+    out.push('\n');
+
+    // unpack_strict
+    writeln!(out, "impl {} {{", message.name).unwrap();
+    writeln!(
+        out,
+        "    /// Unpack a {}, rejecting unknown enum values and robot ids that are out of range",
+        message.name
+    )
+    .unwrap();
+    writeln!(out, "    /// instead of coercing them.").unwrap();
+    writeln!(out, "    ///").unwrap();
+    writeln!(
+        out,
+        "    /// Note: `Packable::unpack` remains lenient for backwards compatibility"
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "    pub fn unpack_strict(data: &[u8]) -> Result<Self, RtpError> {{"
+    )
+    .unwrap();
+    writeln!(out, "        check_len(data, {})?;", size).unwrap();
+    out.push('\n');
+    if let Some(placed) = robot_id {
+        writeln!(out, "        let {} = {};", placed.field.name, raw(placed)).unwrap();
+        writeln!(
+            out,
+            "        if {} as usize >= ROBOTS_PER_TEAM {{",
+            placed.field.name
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "            return Err(RtpError::InvalidRobotId({}));",
+            placed.field.name
+        )
+        .unwrap();
+        writeln!(out, "        }}").unwrap();
+        out.push('\n');
+    }
+    writeln!(out, "        Ok(Self {{").unwrap();
+    for placed in &layout.fields {
+        let value = match placed.kind {
+            FieldKind::Reserved => continue,
+            FieldKind::RobotId => {
+                writeln!(out, "            {},", placed.field.name).unwrap();
+                continue;
+            }
+            FieldKind::Enum(_) => format!("{}.try_into()?", parenthesize(&raw(placed))),
+            _ => unpack_value(placed),
+        };
+        writeln!(out, "            {}: {},", placed.field.name, value).unwrap();
+    }
+    writeln!(out, "        }})").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+    out.push('\n');
+
+    // Packable
+    writeln!(out, "impl Packable for {} {{", message.name).unwrap();
+    writeln!(out, "    fn len() -> usize {{").unwrap();
+    writeln!(out, "        {}", size).unwrap();
+    writeln!(out, "    }}").unwrap();
+    out.push('\n');
+    writeln!(
+        out,
+        "    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {{"
+    )
+    .unwrap();
+    writeln!(out, "        check_len(buffer, {})?;", size).unwrap();
+    out.push('\n');
+    let mut byte = 0;
+    while byte < layout.size {
+        let fields: Vec<&PlacedField> = layout.fields_in(byte).collect();
+        if let [placed @ PlacedField {
+            kind: FieldKind::Integer { bytes, .. },
+            ..
+        }] = fields[..]
+        {
+            if *bytes == 1 {
+                writeln!(out, "        buffer[{}] = {};", byte, pack_value(placed)).unwrap();
+            } else {
+                writeln!(
+                    out,
+                    "        buffer[{}..{}].copy_from_slice(&self.{}.to_le_bytes());",
+                    byte,
+                    byte + *bytes,
+                    placed.field.name
+                )
+                .unwrap();
+            }
+            byte += *bytes;
+            continue;
+        }
+
+        let values: Vec<String> = fields
+            .iter()
+            .filter(|placed| !matches!(placed.kind, FieldKind::Reserved))
+            .map(|placed| pack_value(placed))
+            .collect();
+        if values.is_empty() {
+            writeln!(out, "        buffer[{}] = 0;", byte).unwrap();
+        } else {
+            writeln!(
+                out,
+                "        buffer[{}] = {};",
+                byte,
+                values.join("\n            | ")
+            )
+            .unwrap();
+        }
+        byte += 1;
+    }
+    writeln!(out, "        Ok(())").unwrap();
+    writeln!(out, "    }}").unwrap();
+    out.push('\n');
+    writeln!(
+        out,
+        "    fn unpack(data: &[u8]) -> Result<Self, PackingError> {{"
+    )
+    .unwrap();
+    writeln!(out, "        check_len(data, {})?;", size).unwrap();
+    out.push('\n');
+    writeln!(out, "        Ok(Self {{").unwrap();
+    for placed in &layout.fields {
+        let value = match placed.kind {
+            FieldKind::Reserved => continue,
+            FieldKind::Enum(ty) => format!(
+                "{}::try_from({}).unwrap_or({}::{})",
+                ty.name,
+                raw(placed),
+                ty.name,
+                ty.fallback
+            ),
+            _ => unpack_value(placed),
+        };
+        writeln!(out, "            {}: {},", placed.field.name, value).unwrap();
+    }
+    writeln!(out, "        }})").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+/// The expression that packs a field into its byte
+fn pack_value(placed: &PlacedField) -> String {
+    let name = &placed.field.name;
+    let value = match placed.kind {
+        FieldKind::Integer { bytes: 1, rust: "u8", .. } => return format!("self.{}", name),
+        FieldKind::Integer { .. } => return format!("self.{}.to_le_bytes()[0]", name),
+        FieldKind::Bool => format!("self.{} as u8", name),
+        FieldKind::Enum(_) if placed.field.bits == 8 => format!("self.{} as u8", name),
+        FieldKind::Enum(_) => format!("((self.{} as u8) & {:#b})", name, placed.mask()),
+        FieldKind::Unsigned | FieldKind::RobotId if placed.field.bits == 8 => {
+            format!("self.{}", name)
+        }
+        FieldKind::Unsigned | FieldKind::RobotId | FieldKind::Reserved => {
+            format!("(self.{} & {:#b})", name, placed.mask())
+        }
+    };
+    if placed.shift == 0 {
+        value
+    } else if value.starts_with('(') {
+        format!("{} << {}", value, placed.shift)
+    } else {
+        format!("({}) << {}", value, placed.shift)
+    }
+}
+
+/// The expression that unpacks the raw bits of a sub-byte field
+fn raw(placed: &PlacedField) -> String {
+    let byte = placed.byte;
+    match (placed.field.bits, placed.shift) {
+        (8, _) => format!("data[{}]", byte),
+        (_, 0) => format!("data[{}] & {:#b}", byte, placed.mask()),
+        (_, shift) => format!(
+            "(data[{}] & ({:#b} << {})) >> {}",
+            byte,
+            placed.mask(),
+            shift,
+            shift
+        ),
+    }
+}
+
+/// The expression that unpacks a (non-enum) field
+fn unpack_value(placed: &PlacedField) -> String {
+    match placed.kind {
+        FieldKind::Bool if placed.shift == 0 => format!("data[{}] & 0b1 != 0", placed.byte),
+        FieldKind::Bool => format!("data[{}] & (0b1 << {}) != 0", placed.byte, placed.shift),
+        FieldKind::Integer { rust: "u8", .. } => format!("data[{}]", placed.byte),
+        FieldKind::Integer { rust, bytes, .. } => {
+            let bytes: Vec<String> = (placed.byte..placed.byte + bytes)
+                .map(|byte| format!("data[{}]", byte))
+                .collect();
+            format!("{}::from_le_bytes([{}])", rust, bytes.join(", "))
+        }
+        _ => raw(placed),
+    }
+}
+
+/// Wrap an expression in parentheses unless it is already a single term
+fn parenthesize(expression: &str) -> String {
+    if expression.contains(' ') {
+        format!("({})", expression)
+    } else {
+        expression.into()
+    }
+}
+
+/// A `use` item importing `names` from `module`
+fn use_item(module: &str, names: &[&str]) -> String {
+    let path = if module == "crate" {
+        "crate".into()
+    } else {
+        format!("crate::{}", module)
+    };
+    match names {
+        [name] => format!("use {}::{};", path, name),
+        names => format!("use {}::{{{}}};", path, names.join(", ")),
+    }
+}
+
+/// Prefix every line of some text with a comment marker
+pub fn comment(text: &str, marker: &str) -> String {
+    text.lines()
+        .map(|line| {
+            if line.is_empty() {
+                marker.into()
+            } else {
+                format!("{} {}", marker, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,341 @@
+//!
+//! The message schema (`schema/messages.toml`) and the bit placement of every field.
+//!
+
+use serde::Deserialize;
+
+/// Every enum and message described by the schema
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Schema {
+    /// Enums that can be used as field types
+    #[serde(rename = "enum", default)]
+    pub enums: Vec<Enum>,
+    /// The messages to generate code for
+    #[serde(rename = "message", default)]
+    pub messages: Vec<Message>,
+    /// Messages packed by derive(Packable) that only need C ABI mirrors
+    #[serde(rename = "ffi_message", default)]
+    pub ffi_messages: Vec<FfiMessage>,
+}
+
+/// An enum packed as its discriminant
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Enum {
+    /// The name of the Rust enum
+    pub name: String,
+    /// The name of the C++ enum (defaults to `name`)
+    pub cpp_name: Option<String>,
+    /// The prefix of the C++ enumerators (defaults to the C++ name in SCREAMING_SNAKE_CASE
+    /// followed by `_`), because the C++ enums are unscoped
+    pub cpp_prefix: Option<String>,
+    /// The module of the rtp crate the Rust enum lives in (`crate` for the crate root)
+    pub module: String,
+    /// Documentation for the C++ enum
+    pub doc: String,
+    /// The variant used when unpacking leniently and the raw value is unknown
+    pub fallback: String,
+    /// The variants of the enum
+    pub variants: Vec<Variant>,
+}
+
+impl Enum {
+    /// The name of the C++ enum
+    pub fn cpp_name(&self) -> &str {
+        self.cpp_name.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// A variant of an enum
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Variant {
+    /// The name of the Rust variant
+    pub name: String,
+    /// The discriminant of the variant
+    pub value: u8,
+}
+
+/// A packed message
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Message {
+    /// The name of the message struct
+    pub name: String,
+    /// The module of the rtp crate the message lives in
+    pub module: String,
+    /// The name of the constant holding the packed size of the message
+    pub size_constant: String,
+    /// Documentation for the C++ struct
+    pub doc: String,
+    /// Scale factors that belong to the message
+    #[serde(default)]
+    pub constants: Vec<Constant>,
+    /// The fields of the message, starting at the most significant bit of the first byte
+    pub fields: Vec<Field>,
+}
+
+/// A floating point constant that belongs to a message
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Constant {
+    /// The name of the constant
+    pub name: String,
+    /// The value of the constant
+    pub value: f64,
+    /// Why the constant is deprecated and what to use instead
+    pub deprecated: Option<String>,
+}
+
+/// A field of a message
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Field {
+    /// The name of the field
+    pub name: String,
+    /// The type of the field
+    #[serde(rename = "type")]
+    pub ty: String,
+    /// The number of bits the field occupies
+    pub bits: u32,
+    /// The text used for the field in the layout table (defaults to `name`)
+    pub label: Option<String>,
+    /// Documentation for the C++ field
+    pub doc: String,
+}
+
+impl Field {
+    /// The text used for the field in the layout table
+    pub fn label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// A message packed by derive(Packable) that is exposed through the C ABI
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FfiMessage {
+    /// The name of the message struct
+    pub name: String,
+    /// The module of the rtp crate the message lives in
+    pub module: String,
+    /// The name of the constant holding the packed size of the message
+    pub size_constant: String,
+    /// The packed size of the message in bytes
+    pub size: usize,
+    /// Documentation for the C struct
+    pub doc: String,
+    /// The fields of the message (read from the Rust struct, not the schema)
+    #[serde(skip)]
+    pub fields: Vec<FfiField>,
+}
+
+/// A field of a message exposed through the C ABI
+#[derive(Debug)]
+pub struct FfiField {
+    /// The name of the field
+    pub name: String,
+    /// The type of the field
+    pub ty: String,
+    /// The length of the field if it is an array
+    pub count: Option<usize>,
+    /// Documentation for the C field
+    pub doc: String,
+}
+
+/// How a field is packed
+#[derive(Clone, Copy, Debug)]
+pub enum FieldKind<'a> {
+    /// A single bit
+    Bool,
+    /// An unsigned value smaller than a byte
+    Unsigned,
+    /// A robot id (rejected by strict unpacking when out of range)
+    RobotId,
+    /// Unused bits that are always packed as 0
+    Reserved,
+    /// An enum packed as its discriminant
+    Enum(&'a Enum),
+    /// One or more whole bytes packed little endian
+    Integer {
+        /// The Rust type of the field
+        rust: &'static str,
+        /// The C++ type of the field
+        cpp: &'static str,
+        /// The number of bytes the field occupies
+        bytes: usize,
+    },
+}
+
+/// A field and its position in the packed message
+#[derive(Clone, Copy, Debug)]
+pub struct PlacedField<'a> {
+    /// The field from the schema
+    pub field: &'a Field,
+    /// How the field is packed
+    pub kind: FieldKind<'a>,
+    /// The (first) byte the field is packed into
+    pub byte: usize,
+    /// The position of the least significant bit of the field within its byte
+    pub shift: u32,
+}
+
+impl<'a> PlacedField<'a> {
+    /// The mask of a sub-byte field before it is shifted into place
+    pub fn mask(&self) -> u32 {
+        (1 << self.field.bits) - 1
+    }
+}
+
+/// The packed layout of a message
+#[derive(Debug)]
+pub struct Layout<'a> {
+    /// The message from the schema
+    pub message: &'a Message,
+    /// The fields in the order they are packed
+    pub fields: Vec<PlacedField<'a>>,
+    /// The size of the packed message in bytes
+    pub size: usize,
+}
+
+impl<'a> Layout<'a> {
+    /// The fields packed into a given byte
+    pub fn fields_in(&self, byte: usize) -> impl Iterator<Item = &PlacedField<'a>> {
+        self.fields.iter().filter(move |placed| match placed.kind {
+            FieldKind::Integer { bytes, .. } => (placed.byte..placed.byte + bytes).contains(&byte),
+            _ => placed.byte == byte,
+        })
+    }
+
+    /// The enums used by the message (in the order they are first used)
+    pub fn enums(&self) -> Vec<&'a Enum> {
+        let mut enums: Vec<&'a Enum> = Vec::new();
+        for placed in &self.fields {
+            if let FieldKind::Enum(ty) = placed.kind {
+                if !enums.iter().any(|other| other.name == ty.name) {
+                    enums.push(ty);
+                }
+            }
+        }
+        enums
+    }
+
+    /// The robot id field of the message (if there is one)
+    pub fn robot_id(&self) -> Option<&PlacedField<'a>> {
+        self.fields
+            .iter()
+            .find(|placed| matches!(placed.kind, FieldKind::RobotId))
+    }
+}
+
+impl Schema {
+    /// Parse a schema
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let schema: Self = toml::from_str(source).map_err(|err| err.to_string())?;
+        for ty in &schema.enums {
+            if !ty.variants.iter().any(|variant| variant.name == ty.fallback) {
+                return Err(format!(
+                    "enum {}: fallback {} is not a variant",
+                    ty.name, ty.fallback
+                ));
+            }
+        }
+        Ok(schema)
+    }
+
+    /// Find an enum by name
+    pub fn find_enum(&self, name: &str) -> Option<&Enum> {
+        self.enums.iter().find(|ty| ty.name == name)
+    }
+
+    /// Place every field of a message, checking that the fields fit together
+    pub fn layout<'a>(&'a self, message: &'a Message) -> Result<Layout<'a>, String> {
+        let error = |field: &Field, reason: &str| {
+            Err(format!("{}.{}: {}", message.name, field.name, reason))
+        };
+
+        let mut fields = Vec::new();
+        let mut cursor = 0;
+        for field in &message.fields {
+            if fields
+                .iter()
+                .any(|placed: &PlacedField| placed.field.name == field.name)
+            {
+                return error(field, "duplicate field name");
+            }
+
+            let kind = match (field.ty.as_str(), field.bits) {
+                ("bool", 1) => FieldKind::Bool,
+                ("bool", _) => return error(field, "bool fields must be 1 bit"),
+                ("u8", 8) => FieldKind::Integer {
+                    rust: "u8",
+                    cpp: "uint8_t",
+                    bytes: 1,
+                },
+                ("u8", 1..=7) => FieldKind::Unsigned,
+                ("robot_id", 1..=8) => FieldKind::RobotId,
+                ("reserved", 1..=8) => FieldKind::Reserved,
+                ("i8", 8) => FieldKind::Integer {
+                    rust: "i8",
+                    cpp: "int8_t",
+                    bytes: 1,
+                },
+                ("u16", 16) => FieldKind::Integer {
+                    rust: "u16",
+                    cpp: "uint16_t",
+                    bytes: 2,
+                },
+                ("i16", 16) => FieldKind::Integer {
+                    rust: "i16",
+                    cpp: "int16_t",
+                    bytes: 2,
+                },
+                ("u32", 32) => FieldKind::Integer {
+                    rust: "u32",
+                    cpp: "uint32_t",
+                    bytes: 4,
+                },
+                (ty, bits) => match self.find_enum(ty) {
+                    Some(ty) if (1..=8).contains(&bits) => {
+                        if ty.variants.iter().any(|variant| variant.value as u32 >> bits != 0) {
+                            return error(field, "enum does not fit in the field");
+                        }
+                        FieldKind::Enum(ty)
+                    }
+                    _ => return error(field, "unsupported type or width"),
+                },
+            };
+
+            let offset = cursor % 8;
+            if let FieldKind::Integer { .. } = kind {
+                if offset != 0 {
+                    return error(field, "multi-bit integers must be byte aligned");
+                }
+            } else if offset + field.bits > 8 {
+                return error(field, "field crosses a byte boundary");
+            }
+
+            fields.push(PlacedField {
+                field,
+                kind,
+                byte: (cursor / 8) as usize,
+                shift: 8 - offset - field.bits.min(8),
+            });
+            cursor += field.bits;
+        }
+
+        if cursor % 8 != 0 {
+            return Err(format!(
+                "{}: {} bits is not a whole number of bytes",
+                message.name, cursor
+            ));
+        }
+
+        Ok(Layout {
+            message,
+            fields,
+            size: (cursor / 8) as usize,
+        })
+    }
+}
@@ -0,0 +1,119 @@
+//!
+//! Reads the fields of the derive(Packable) messages from their Rust source so the C ABI
+//! mirrors in the schema do not have to repeat them.
+//!
+
+use syn::{Attribute, Expr, Fields, Item, Lit, Meta, Type};
+
+use crate::schema::FfiField;
+
+/// The fields of the struct `name` defined at the top level of `source`
+pub fn struct_fields(source: &str, name: &str) -> Result<Vec<FfiField>, String> {
+    let file = syn::parse_file(source).map_err(|err| err.to_string())?;
+    let fields = file
+        .items
+        .iter()
+        .find_map(|item| match item {
+            Item::Struct(item) if item.ident == name => Some(&item.fields),
+            _ => None,
+        })
+        .ok_or_else(|| format!("{}: struct not found", name))?;
+    let Fields::Named(fields) = fields else {
+        return Err(format!(
+            "{}: only structs with named fields are supported",
+            name
+        ));
+    };
+
+    fields
+        .named
+        .iter()
+        .map(|field| {
+            let field_name = field.ident.as_ref().unwrap().to_string();
+            let (ty, count) = match &field.ty {
+                Type::Array(array) => match &array.len {
+                    Expr::Lit(expr) => match &expr.lit {
+                        Lit::Int(len) => (&*array.elem, Some(len.base10_parse::<usize>())),
+                        _ => (&field.ty, None),
+                    },
+                    _ => (&field.ty, None),
+                },
+                ty => (ty, None),
+            };
+            let count = count
+                .transpose()
+                .map_err(|err| format!("{}.{}: {}", name, field_name, err))?;
+            let ty = match ty {
+                Type::Path(path) if path.path.get_ident().is_some() => {
+                    path.path.get_ident().unwrap().to_string()
+                }
+                _ => return Err(format!("{}.{}: unsupported type", name, field_name)),
+            };
+            Ok(FfiField {
+                name: field_name,
+                ty,
+                count,
+                doc: doc(&field.attrs),
+            })
+        })
+        .collect()
+}
+
+/// The doc comment of an item joined into a single line
+fn doc(attributes: &[Attribute]) -> String {
+    attributes
+        .iter()
+        .filter_map(|attribute| match &attribute.meta {
+            Meta::NameValue(meta) if meta.path.is_ident("doc") => match &meta.value {
+                Expr::Lit(expr) => match &expr.lit {
+                    Lit::Str(doc) => Some(doc.value().trim().to_owned()),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that field names, types, array lengths and docs are read from a struct
+    #[test]
+    fn test_struct_fields() {
+        let source = r#"
+            /// Not this one
+            pub struct Other {
+                pub ignored: u8,
+            }
+
+            /// A message
+            pub struct Message {
+                /// Is the message
+                /// healthy
+                #[packable(offset = 7)]
+                pub healthy: bool,
+                pub samples: [f32; 4],
+            }
+        "#;
+
+        let fields = struct_fields(source, "Message").unwrap();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].name, "healthy");
+        assert_eq!(fields[0].ty, "bool");
+        assert_eq!(fields[0].count, None);
+        assert_eq!(fields[0].doc, "Is the message healthy");
+        assert_eq!(fields[1].name, "samples");
+        assert_eq!(fields[1].ty, "f32");
+        assert_eq!(fields[1].count, Some(4));
+        assert_eq!(fields[1].doc, "");
+
+        assert_eq!(
+            struct_fields(source, "Missing").err(),
+            Some("Missing: struct not found".into())
+        );
+    }
+}
@@ -0,0 +1,589 @@
+//!
+//! `#[derive(Packable)]` for bit-packed rtp messages.
+//!
+//! The derive implements `ncomm_utils::packing::Packable` for a struct with named fields from
+//! `#[packable(...)]` attributes instead of hand-written masks and shifts.
+//!
+//! Bits are numbered starting from the most significant bit of the first byte (like the fields
+//! of `schema/messages.toml` and the packed layout tables), so bit 8 is the most significant
+//! bit of the second byte.  Every field starts right after the previous field unless it is
+//! given an explicit `offset`.  Overlapping fields fail to compile.
+//!
+//! Struct attributes:
+//! - `size = NAME` - also generate `const NAME: usize` holding the packed size in Bytes
+//!
+//! Field attributes:
+//! - `bits = N` - the number of bits the field occupies (defaults to 1 for `bool` and the
+//!   width of the packed type otherwise)
+//! - `offset = N` - the bit the field starts at
+//! - `endian = "little" | "big"` - the byte order of multi-byte fields (defaults to little)
+//! - `repr = u8` - the integer type an enum (or scaled float) is packed as.  Enums are packed
+//!   with `as` and unpacked with `TryFrom<repr>`
+//! - `fallback = Path::To::Variant` - the value used when an enum is unpacked from an unknown
+//!   value (defaults to `Default::default()`)
+//! - `scale = 1000.0` - pack a float as `(value * scale) as repr` and unpack it as
+//!   `raw / scale`.  Like `as`, packing rounds toward zero, saturates values outside of `repr`
+//!   and turns NaN into 0 without an error, so range checks (e.g. `FixedPoint::encode`) have to
+//!   happen before packing
+//!
+//! Fields of up to 8 bits must fit in a single byte (and be unsigned) while wider fields must
+//! be byte aligned and use the full width of their type.  Arrays of any of the above are packed
+//! element by element.
+//!
+//! ```ignore
+//! #[derive(Packable)]
+//! #[packable(size = KICKER_TESTING_SIZE)]
+//! pub struct KickerTestingMessage {
+//!     #[packable(offset = 7)]
+//!     pub healthy: bool,
+//!     #[packable(offset = 6)]
+//!     pub ball_sense: bool,
+//!     #[packable(offset = 8)]
+//!     pub voltage: u8,
+//! }
+//! ```
+//!
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, ToTokens};
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, Expr, Fields, Ident, LitInt, LitStr, Type,
+};
+
+/// Derive `ncomm_utils::packing::Packable` for a bit-packed message
+#[proc_macro_derive(Packable, attributes(packable))]
+pub fn derive_packable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// A primitive type a field can be packed as
+#[derive(Clone, Debug)]
+struct Primitive {
+    ident: Ident,
+    bytes: u32,
+    signed: bool,
+    float: bool,
+}
+
+impl Primitive {
+    fn from_type(ty: &Type) -> Option<Self> {
+        let Type::Path(path) = ty else {
+            return None;
+        };
+        let ident = path.path.get_ident()?;
+        let (bytes, signed, float) = match ident.to_string().as_str() {
+            "u8" => (1, false, false),
+            "i8" => (1, true, false),
+            "u16" => (2, false, false),
+            "i16" => (2, true, false),
+            "u32" => (4, false, false),
+            "i32" => (4, true, false),
+            "u64" => (8, false, false),
+            "i64" => (8, true, false),
+            "f32" => (4, true, true),
+            "f64" => (8, true, true),
+            _ => return None,
+        };
+        Some(Self {
+            ident: ident.clone(),
+            bytes,
+            signed,
+            float,
+        })
+    }
+
+    fn is_u8(&self) -> bool {
+        self.ident == "u8"
+    }
+}
+
+/// The byte order of a multi-byte field
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Endian {
+    Little,
+    Big,
+}
+
+/// How a field is converted to the primitive it is packed as
+#[derive(Clone)]
+enum Conversion {
+    /// A `bool` packed as a `u8`
+    Bool,
+    /// A primitive packed as itself
+    Direct,
+    /// An enum packed as its discriminant
+    Enum { ty: Type, fallback: Option<Expr> },
+    /// A float multiplied by a scale factor and packed as an integer
+    Scaled { float: Ident, scale: Expr },
+}
+
+/// The attributes of a single field
+#[derive(Default)]
+struct FieldAttributes {
+    bits: Option<u32>,
+    offset: Option<u32>,
+    endian: Option<Endian>,
+    repr: Option<Type>,
+    scale: Option<Expr>,
+    fallback: Option<Expr>,
+}
+
+impl FieldAttributes {
+    fn parse(field: &syn::Field) -> syn::Result<Self> {
+        let mut attributes = Self::default();
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("packable")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("bits") {
+                    attributes.bits = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+                } else if meta.path.is_ident("offset") {
+                    attributes.offset = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+                } else if meta.path.is_ident("endian") {
+                    let endian: LitStr = meta.value()?.parse()?;
+                    attributes.endian = Some(match endian.value().as_str() {
+                        "little" => Endian::Little,
+                        "big" => Endian::Big,
+                        _ => return Err(meta.error("expected \"little\" or \"big\"")),
+                    });
+                } else if meta.path.is_ident("repr") {
+                    attributes.repr = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("scale") {
+                    attributes.scale = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("fallback") {
+                    attributes.fallback = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("unknown packable attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(attributes)
+    }
+}
+
+/// A field (or array element) placed in the packed message
+struct Placement {
+    /// `self.field` or `self.field[index]`
+    access: TokenStream2,
+    offset: u32,
+}
+
+/// A field of the message and where it is packed
+struct FieldLayout {
+    ident: Ident,
+    conversion: Conversion,
+    wire: Primitive,
+    bits: u32,
+    endian: Endian,
+    array: bool,
+    placements: Vec<Placement>,
+}
+
+/// Compute the layout of every field of a struct
+fn layout(input: &DeriveInput) -> syn::Result<(Vec<FieldLayout>, u32)> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            "derive(Packable) does not support generics",
+        ));
+    }
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new(
+            input.span(),
+            "derive(Packable) only supports structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new(
+            data.fields.span(),
+            "derive(Packable) only supports structs with named fields",
+        ));
+    };
+
+    let mut layouts = Vec::new();
+    let mut occupied: Vec<(u32, u32, String)> = Vec::new();
+    let mut cursor = 0;
+    for field in &fields.named {
+        let ident = field.ident.clone().unwrap();
+        let attributes = FieldAttributes::parse(field)?;
+
+        let (element, count, array) = match &field.ty {
+            Type::Array(array) => {
+                let Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Int(len),
+                    ..
+                }) = &array.len
+                else {
+                    return Err(Error::new(
+                        array.len.span(),
+                        "array lengths must be integer literals",
+                    ));
+                };
+                (&*array.elem, len.base10_parse::<u32>()?, true)
+            }
+            ty => (ty, 1, false),
+        };
+
+        let repr = match &attributes.repr {
+            Some(repr) => match Primitive::from_type(repr) {
+                Some(primitive) if !primitive.float => Some(primitive),
+                _ => return Err(Error::new(repr.span(), "repr must be an integer type")),
+            },
+            None => None,
+        };
+
+        let is_bool = matches!(element, Type::Path(path) if path.path.is_ident("bool"));
+        let (conversion, wire) = if let Some(scale) = attributes.scale.clone() {
+            let float = match Primitive::from_type(element) {
+                Some(primitive) if primitive.float => primitive.ident,
+                _ => {
+                    return Err(Error::new(
+                        element.span(),
+                        "scale can only be used on f32 and f64 fields",
+                    ))
+                }
+            };
+            let Some(repr) = repr else {
+                return Err(Error::new(ident.span(), "scaled fields need a repr"));
+            };
+            (Conversion::Scaled { float, scale }, repr)
+        } else if is_bool {
+            (Conversion::Bool, Primitive::from_type(&syn::parse_quote!(u8)).unwrap())
+        } else if let Some(primitive) = Primitive::from_type(element) {
+            if repr.is_some() {
+                return Err(Error::new(
+                    ident.span(),
+                    "repr can only be used on enums and scaled fields",
+                ));
+            }
+            (Conversion::Direct, primitive)
+        } else {
+            let Some(repr) = repr else {
+                return Err(Error::new(
+                    element.span(),
+                    "enum fields need a repr (e.g. #[packable(repr = u8)])",
+                ));
+            };
+            (
+                Conversion::Enum {
+                    ty: element.clone(),
+                    fallback: attributes.fallback.clone(),
+                },
+                repr,
+            )
+        };
+        if attributes.fallback.is_some() && !matches!(conversion, Conversion::Enum { .. }) {
+            return Err(Error::new(ident.span(), "fallback can only be used on enums"));
+        }
+
+        let full_width = wire.bytes * 8;
+        let bits = attributes
+            .bits
+            .unwrap_or(if is_bool { 1 } else { full_width });
+        if bits == 0 || bits > full_width || (is_bool && bits != 1) {
+            return Err(Error::new(ident.span(), "invalid number of bits for this type"));
+        }
+
+        let endian = attributes.endian.unwrap_or(Endian::Little);
+        let mut placements = Vec::new();
+        let mut offset = attributes.offset.unwrap_or(cursor);
+        for index in 0..count {
+            let full = offset % 8 == 0 && bits == full_width;
+            let sub_byte = offset % 8 + bits <= 8 && bits < 8;
+            if !full && !sub_byte {
+                return Err(Error::new(
+                    ident.span(),
+                    "fields wider than a byte must be byte aligned and use the full width of \
+                     their type, narrower fields must fit in a single byte",
+                ));
+            }
+            if sub_byte && wire.signed {
+                return Err(Error::new(
+                    ident.span(),
+                    "fields narrower than a byte must be unsigned",
+                ));
+            }
+            if sub_byte && attributes.endian.is_some() {
+                return Err(Error::new(
+                    ident.span(),
+                    "endian can only be used on fields of a whole number of bytes",
+                ));
+            }
+
+            let end = offset + bits;
+            if let Some((_, _, other)) = occupied
+                .iter()
+                .find(|(start, stop, _)| offset < *stop && *start < end)
+            {
+                return Err(Error::new(
+                    ident.span(),
+                    format!("`{}` overlaps `{}`", ident, other),
+                ));
+            }
+            occupied.push((offset, end, ident.to_string()));
+
+            let access = if array {
+                let index = syn::Index::from(index as usize);
+                quote!(self.#ident[#index])
+            } else {
+                quote!(self.#ident)
+            };
+            placements.push(Placement { access, offset });
+            offset = end;
+        }
+        cursor = offset;
+
+        layouts.push(FieldLayout {
+            ident,
+            conversion,
+            wire,
+            bits,
+            endian,
+            array,
+            placements,
+        });
+    }
+
+    let bits = occupied.iter().map(|(_, end, _)| *end).max().unwrap_or(0);
+    Ok((layouts, bits.div_ceil(8)))
+}
+
+/// Generate the `Packable` implementation (and size constant) for a struct
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let mut size_constant = None;
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("packable")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("size") {
+                size_constant = Some(meta.value()?.parse::<Ident>()?);
+                Ok(())
+            } else {
+                Err(meta.error("unknown packable attribute"))
+            }
+        })?;
+    }
+
+    let (fields, size) = layout(input)?;
+    let name = &input.ident;
+    let size = size as usize;
+
+    let (size_item, size) = match size_constant {
+        Some(constant) => {
+            let vis = &input.vis;
+            let doc = format!("The size of a packed {} in Bytes", name);
+            (
+                quote! {
+                    #[doc = #doc]
+                    #vis const #constant: usize = #size;
+                },
+                constant.into_token_stream(),
+            )
+        }
+        None => (TokenStream2::new(), quote!(#size)),
+    };
+
+    let mut pack = Vec::new();
+    let mut unpack = Vec::new();
+    for field in &fields {
+        let mut values = Vec::new();
+        for placement in &field.placements {
+            pack.push(pack_placement(field, placement));
+            values.push(unpack_placement(field, placement));
+        }
+        let ident = &field.ident;
+        if field.array {
+            unpack.push(quote!(#ident: [#(#values),*]));
+        } else {
+            unpack.push(quote!(#ident: #(#values)*));
+        }
+    }
+
+    Ok(quote! {
+        #size_item
+
+        impl ::ncomm_utils::packing::Packable for #name {
+            fn len() -> usize {
+                #size
+            }
+
+            fn pack(
+                self,
+                buffer: &mut [u8],
+            ) -> ::core::result::Result<(), ::ncomm_utils::packing::PackingError> {
+                if buffer.len() < #size {
+                    return ::core::result::Result::Err(
+                        ::ncomm_utils::packing::PackingError::InvalidBufferSize,
+                    );
+                }
+
+                buffer[..#size].fill(0);
+                #(#pack)*
+
+                ::core::result::Result::Ok(())
+            }
+
+            fn unpack(
+                data: &[u8],
+            ) -> ::core::result::Result<Self, ::ncomm_utils::packing::PackingError> {
+                if data.len() < #size {
+                    return ::core::result::Result::Err(
+                        ::ncomm_utils::packing::PackingError::InvalidBufferSize,
+                    );
+                }
+
+                ::core::result::Result::Ok(Self {
+                    #(#unpack,)*
+                })
+            }
+        }
+    })
+}
+
+/// The statement that packs a field (or array element) into the buffer
+fn pack_placement(field: &FieldLayout, placement: &Placement) -> TokenStream2 {
+    let access = &placement.access;
+    let wire = &field.wire.ident;
+    let value = match &field.conversion {
+        Conversion::Bool => quote!((#access as u8)),
+        Conversion::Direct => quote!(#access),
+        Conversion::Enum { .. } => quote!((#access as #wire)),
+        // `as` saturates (see the module docs)
+        Conversion::Scaled { scale, .. } => quote!(((#access * (#scale)) as #wire)),
+    };
+
+    let byte = (placement.offset / 8) as usize;
+    if field.bits == field.wire.bytes * 8 {
+        let end = byte + field.wire.bytes as usize;
+        return match field.endian {
+            Endian::Little => quote!(buffer[#byte..#end].copy_from_slice(&#value.to_le_bytes());),
+            Endian::Big => quote!(buffer[#byte..#end].copy_from_slice(&#value.to_be_bytes());),
+        };
+    }
+
+    let value = if field.wire.is_u8() {
+        value
+    } else {
+        quote!((#value as u8))
+    };
+    let masked = if matches!(field.conversion, Conversion::Bool) {
+        value
+    } else {
+        let mask = LitInt::new(&format!("{:#b}", (1u32 << field.bits) - 1), Span::call_site());
+        quote!((#value & #mask))
+    };
+    let shift = 8 - placement.offset % 8 - field.bits;
+    if shift == 0 {
+        quote!(buffer[#byte] |= #masked;)
+    } else {
+        let shift = LitInt::new(&shift.to_string(), Span::call_site());
+        quote!(buffer[#byte] |= #masked << #shift;)
+    }
+}
+
+/// The expression that unpacks a field (or array element) from the data
+fn unpack_placement(field: &FieldLayout, placement: &Placement) -> TokenStream2 {
+    let wire = &field.wire.ident;
+    let byte = (placement.offset / 8) as usize;
+    let raw = if field.bits == field.wire.bytes * 8 {
+        let bytes = (byte..byte + field.wire.bytes as usize).map(|index| quote!(data[#index]));
+        match field.endian {
+            Endian::Little => quote!(#wire::from_le_bytes([#(#bytes),*])),
+            Endian::Big => quote!(#wire::from_be_bytes([#(#bytes),*])),
+        }
+    } else {
+        let mask = LitInt::new(&format!("{:#b}", (1u32 << field.bits) - 1), Span::call_site());
+        let shift = 8 - placement.offset % 8 - field.bits;
+        let shifted = if shift == 0 {
+            quote!(data[#byte])
+        } else {
+            let shift = LitInt::new(&shift.to_string(), Span::call_site());
+            quote!((data[#byte] >> #shift))
+        };
+        if field.wire.is_u8() {
+            quote!((#shifted & #mask))
+        } else {
+            quote!(((#shifted & #mask) as #wire))
+        }
+    };
+
+    match &field.conversion {
+        Conversion::Bool => quote!(#raw != 0),
+        Conversion::Direct => raw,
+        Conversion::Enum { ty, fallback } => {
+            let fallback = match fallback {
+                Some(fallback) => quote!(#fallback),
+                None => quote!(::core::default::Default::default()),
+            };
+            quote! {
+                match <#ty as ::core::convert::TryFrom<#wire>>::try_from(#raw) {
+                    ::core::result::Result::Ok(value) => value,
+                    ::core::result::Result::Err(_) => #fallback,
+                }
+            }
+        }
+        Conversion::Scaled { float, scale } => quote!((#raw as #float) / (#scale)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that fields are placed one after another unless they are given an offset
+    #[test]
+    fn test_layout_offsets() {
+        let input: DeriveInput = syn::parse_quote! {
+            struct Message {
+                a: bool,
+                #[packable(bits = 3)]
+                b: u8,
+                #[packable(offset = 8)]
+                c: [u16; 2],
+                d: bool,
+            }
+        };
+
+        let (fields, size) = layout(&input).unwrap();
+        let offsets: Vec<Vec<u32>> = fields
+            .iter()
+            .map(|field| field.placements.iter().map(|placement| placement.offset).collect())
+            .collect();
+        assert_eq!(offsets, [vec![0], vec![1], vec![8, 24], vec![40]]);
+        assert_eq!(size, 6);
+    }
+
+    /// Test that overlapping and misaligned fields are rejected
+    #[test]
+    fn test_invalid_layouts_are_rejected() {
+        let overlapping: DeriveInput = syn::parse_quote! {
+            struct Message {
+                healthy: bool,
+                #[packable(offset = 0)]
+                ball_sense: bool,
+            }
+        };
+        assert_eq!(
+            layout(&overlapping).err().unwrap().to_string(),
+            "`ball_sense` overlaps `healthy`"
+        );
+
+        let misaligned: DeriveInput = syn::parse_quote! {
+            struct Message {
+                healthy: bool,
+                voltage: u16,
+            }
+        };
+        assert!(layout(&misaligned).is_err());
+
+        let crossing: DeriveInput = syn::parse_quote! {
+            struct Message {
+                #[packable(offset = 6, bits = 4)]
+                motor_errors: u8,
+            }
+        };
+        assert!(layout(&crossing).is_err());
+    }
+}
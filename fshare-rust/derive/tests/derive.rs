@@ -0,0 +1,75 @@
+//!
+//! derive(Packable) has to be tested from outside of the proc-macro crate.
+//!
+
+use ncomm_utils::packing::Packable;
+use robojackets_robocup_rtp_derive::Packable;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Kind {
+    #[default]
+    First = 0,
+    Second = 1,
+    Third = 2,
+}
+
+impl TryFrom<u8> for Kind {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::First),
+            1 => Ok(Self::Second),
+            2 => Ok(Self::Third),
+            _ => Err(value),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Packable)]
+#[packable(size = TEST_MESSAGE_SIZE)]
+struct TestMessage {
+    flag: bool,
+    #[packable(offset = 6, bits = 2, repr = u8)]
+    kind: Kind,
+    #[packable(offset = 3, bits = 3, repr = u8, fallback = Kind::Second)]
+    other_kind: Kind,
+    #[packable(offset = 8, repr = i16, scale = 1000.0)]
+    velocity: f32,
+    #[packable(endian = "big")]
+    counter: u32,
+    samples: [i8; 2],
+}
+
+/// Test that every attribute is packed into the expected bits and can be unpacked
+#[test]
+fn test_derive_packable_attributes() {
+    assert_eq!(TEST_MESSAGE_SIZE, 9);
+    assert_eq!(TestMessage::len(), TEST_MESSAGE_SIZE);
+
+    let message = TestMessage {
+        flag: true,
+        kind: Kind::Third,
+        other_kind: Kind::Second,
+        velocity: -1.5,
+        counter: 0x0102_0304,
+        samples: [-1, 5],
+    };
+
+    let mut buffer = [0xFFu8; TEST_MESSAGE_SIZE];
+    message.pack(&mut buffer).unwrap();
+    assert_eq!(
+        buffer,
+        [0b1000_0110, 0x24, 0xFA, 0x01, 0x02, 0x03, 0x04, 0xFF, 0x05]
+    );
+    assert_eq!(TestMessage::unpack(&buffer).unwrap(), message);
+
+    // unknown enum values fall back to the default (or the given fallback)
+    buffer[0] = 0b0001_1111;
+    let unpacked = TestMessage::unpack(&buffer).unwrap();
+    assert_eq!(unpacked.kind, Kind::First);
+    assert_eq!(unpacked.other_kind, Kind::Second);
+    assert!(!unpacked.flag);
+
+    assert!(TestMessage::unpack(&buffer[..TEST_MESSAGE_SIZE - 1]).is_err());
+}
@@ -0,0 +1,6 @@
+//!
+//! Builds the C ABI of rtp (`robojackets_robocup_rtp::ffi`) into a static library that can be
+//! linked into robocup-software.  The header is `include/rc-fshare/rtp_ffi.h`.
+//!
+
+pub use robojackets_robocup_rtp::ffi::*;
@@ -0,0 +1,374 @@
+//!
+//! Decode rtp messages from hex strings, raw binary files and radio logs, or encode a message
+//! from its fields into hex.
+//!
+//! Usage:
+//!     rtp-decode hex <HEX> [--as <FORMAT>]
+//!     rtp-decode file <PATH> [--as <FORMAT>]
+//!     rtp-decode log <PATH>
+//!     rtp-decode encode <KIND> [--envelope] [<FIELD>=<VALUE>...]
+//!
+//! FORMAT is either a message KIND, `envelope` or `broadcast`.  Without `--as` the format is
+//! guessed from the length of the data.  Messages are printed as JSON with the velocities in
+//! m/s and rad/s.  Fields that are not given to `encode` are 0 (or the first variant of an enum)
+//! and values that do not fit into their fields are rejected.
+//!
+//! Build with `cargo run --features cli --bin rtp-decode -- <ARGS>`.
+//!
+
+use std::env;
+use std::fs;
+use std::process::ExitCode;
+
+use robojackets_robocup_rtp::control_message::Rounding;
+use robojackets_robocup_rtp::envelope::{
+    Header, MessageKind, RtpMessage, HEADER_SIZE, MAX_ENVELOPE_SIZE,
+};
+use robojackets_robocup_rtp::radio_log::{Direction, Frame, FrameFormat, RadioLogReader};
+use robojackets_robocup_rtp::ControlMessageBuilder;
+use serde_json::{Map, Value};
+
+const USAGE: &str = "usage:
+    rtp-decode hex <HEX> [--as <FORMAT>]
+    rtp-decode file <PATH> [--as <FORMAT>]
+    rtp-decode log <PATH>
+    rtp-decode encode <KIND> [--envelope] [<FIELD>=<VALUE>...]
+
+KIND:   control, robot-status, imu-test, kicker-testing, kicker-program,
+        radio-receive-benchmark, radio-send-benchmark, control-test, halt,
+        game-state
+FORMAT: a KIND, envelope or broadcast";
+
+/// The name of every message kind on the command line
+const KINDS: [(&str, MessageKind); 10] = [
+    ("control", MessageKind::Control),
+    ("robot-status", MessageKind::RobotStatus),
+    ("imu-test", MessageKind::ImuTest),
+    ("kicker-testing", MessageKind::KickerTesting),
+    ("kicker-program", MessageKind::KickerProgram),
+    (
+        "radio-receive-benchmark",
+        MessageKind::RadioReceiveBenchmark,
+    ),
+    ("radio-send-benchmark", MessageKind::RadioSendBenchmark),
+    ("control-test", MessageKind::ControlTest),
+    ("halt", MessageKind::Halt),
+    ("game-state", MessageKind::GameState),
+];
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match run(&args) {
+        Ok(output) => {
+            println!("{}", output);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Run a command, returning what should be printed
+fn run(args: &[String]) -> Result<String, String> {
+    match args {
+        [command, input, options @ ..] if command == "hex" || command == "file" => {
+            let data = if command == "hex" {
+                parse_hex(input)?
+            } else {
+                fs::read(input).map_err(|err| format!("failed to read {}: {}", input, err))?
+            };
+            let format = match options {
+                [] => guess_format(&data)?,
+                [option, format] if option == "--as" => parse_format(format)?,
+                _ => return Err(format!("unexpected arguments {:?}", options)),
+            };
+            let frame = format.decode(&data).map_err(|err| err.to_string())?;
+            Ok(frame_to_json(&frame, true))
+        }
+        [command, path] if command == "log" => decode_log(path),
+        [command, kind, fields @ ..] if command == "encode" => {
+            let envelope = fields.iter().any(|field| field == "--envelope");
+            let fields: Vec<&str> = fields
+                .iter()
+                .map(String::as_str)
+                .filter(|field| *field != "--envelope")
+                .collect();
+            encode(parse_kind(kind)?, envelope, &fields)
+        }
+        _ => Err("invalid arguments".into()),
+    }
+}
+
+/// Parse hex (optionally prefixed with 0x and separated by whitespace, `:`, `,` or `_`)
+fn parse_hex(text: &str) -> Result<Vec<u8>, String> {
+    let text = text.trim();
+    let text = text.strip_prefix("0x").unwrap_or(text);
+    let digits: Vec<char> = text
+        .chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, ':' | ',' | '_'))
+        .collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err("hex must have an even number of digits".into());
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let pair: String = pair.iter().collect();
+            u8::from_str_radix(&pair, 16).map_err(|_| format!("invalid hex byte {}", pair))
+        })
+        .collect()
+}
+
+/// Format bytes as space separated hex
+fn to_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parse the name of a message kind
+fn parse_kind(name: &str) -> Result<MessageKind, String> {
+    KINDS
+        .iter()
+        .find(|(kind_name, _)| *kind_name == name)
+        .map(|(_, kind)| *kind)
+        .ok_or_else(|| format!("unknown message kind {}", name))
+}
+
+/// The name of a message kind on the command line
+fn kind_name(kind: MessageKind) -> &'static str {
+    KINDS.iter().find(|(_, other)| *other == kind).unwrap().0
+}
+
+/// Parse the name of a frame format
+fn parse_format(name: &str) -> Result<FrameFormat, String> {
+    match name {
+        "envelope" => Ok(FrameFormat::Envelope),
+        "broadcast" => Ok(FrameFormat::Broadcast),
+        kind => parse_kind(kind).map(FrameFormat::Bare),
+    }
+}
+
+/// The name of a frame format on the command line
+fn format_name(format: FrameFormat) -> &'static str {
+    match format {
+        FrameFormat::Bare(kind) => kind_name(kind),
+        FrameFormat::Envelope => "envelope",
+        FrameFormat::Broadcast => "broadcast",
+    }
+}
+
+/// Guess the format of a frame from its length (and envelope header)
+fn guess_format(data: &[u8]) -> Result<FrameFormat, String> {
+    if let Some(header) = data.first().and_then(|byte| Header::from_byte(*byte).ok()) {
+        if data.len() == HEADER_SIZE + header.kind.payload_size() {
+            return Ok(FrameFormat::Envelope);
+        }
+    }
+
+    let mut candidates = KINDS
+        .iter()
+        .filter(|(_, kind)| kind.payload_size() == data.len());
+    match (candidates.next(), candidates.next()) {
+        (Some((_, kind)), None) => Ok(FrameFormat::Bare(*kind)),
+        _ => Err(format!(
+            "cannot guess the format of {} Bytes, use --as <FORMAT>",
+            data.len()
+        )),
+    }
+}
+
+/// Convert a decoded frame into JSON (keeping the fields in the order they are packed)
+fn frame_to_json(frame: &Frame, pretty: bool) -> String {
+    let json = match (frame, pretty) {
+        (Frame::Message(message), true) => serde_json::to_string_pretty(message),
+        (Frame::Message(message), false) => serde_json::to_string(message),
+        (Frame::Broadcast(commands), true) => serde_json::to_string_pretty(commands),
+        (Frame::Broadcast(commands), false) => serde_json::to_string(commands),
+    };
+    json.unwrap()
+}
+
+/// Decode every record of a radio log (one per line)
+fn decode_log(path: &str) -> Result<String, String> {
+    let reader = RadioLogReader::open(path).map_err(|err| format!("{}: {}", path, err))?;
+    let mut lines = Vec::new();
+    for record in reader {
+        let record = record.map_err(|err| format!("{}: {}", path, err))?;
+        let direction = match record.direction {
+            Direction::ToRobot => "->",
+            Direction::FromRobot => "<-",
+        };
+        let robot = match record.robot_id {
+            Some(robot_id) => format!("{:?} {}", record.team, robot_id),
+            None => format!("{:?} *", record.team),
+        };
+        let decoded = match record.decode() {
+            Ok(frame) => frame_to_json(&frame, false),
+            Err(err) => format!("{} ({})", to_hex(&record.payload), err),
+        };
+        lines.push(format!(
+            "{:>12.6}s {} {} {}: {}",
+            record.timestamp.as_secs_f64(),
+            direction,
+            robot,
+            format_name(record.format),
+            decoded
+        ));
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Encode a message from `<FIELD>=<VALUE>` pairs into hex
+fn encode(kind: MessageKind, envelope: bool, fields: &[&str]) -> Result<String, String> {
+    // every kind of message decodes from zeros, which gives the defaults for missing fields
+    let defaults = RtpMessage::decode_payload(kind, &[0u8; MAX_ENVELOPE_SIZE]).unwrap();
+    let mut value = serde_json::to_value(defaults).unwrap();
+    let message = value
+        .as_object_mut()
+        .and_then(|variant| variant.values_mut().next())
+        .and_then(Value::as_object_mut)
+        .unwrap();
+
+    for field in fields {
+        let (name, text) = field
+            .split_once('=')
+            .ok_or_else(|| format!("expected <FIELD>=<VALUE> but found {}", field))?;
+        let slot = message
+            .get_mut(name)
+            .ok_or_else(|| format!("{} has no field {}", kind_name(kind), name))?;
+        // anything that is not valid JSON (e.g. an enum variant like Yellow) is a string
+        *slot = serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.into()));
+    }
+
+    let mut message: RtpMessage =
+        serde_json::from_value(value.clone()).map_err(|err| format!("invalid field: {}", err))?;
+    if let RtpMessage::Control(control) = message {
+        // deserializing saturates the velocities, so check the requested ones with the builder
+        let velocity = |name| fields_of(&value)[name].as_f64().unwrap_or_default() as f32;
+        message = ControlMessageBuilder::new()
+            .team(control.team)
+            .robot_id(control.robot_id)
+            .shoot_mode(control.shoot_mode)
+            .trigger_mode(control.trigger_mode)
+            .body_x(velocity("body_x"))
+            .body_y(velocity("body_y"))
+            .body_w(velocity("body_w"))
+            .dribbler_speed(control.dribbler_speed)
+            .kick_strength(control.kick_strength)
+            .role(control.role)
+            .mode(control.mode)
+            .sequence(control.sequence)
+            .rounding(Rounding::Nearest)
+            .try_build()
+            .map_err(|err| err.to_string())?
+            .into();
+    }
+
+    let mut buffer = [0u8; MAX_ENVELOPE_SIZE];
+    let written = message.encode(&mut buffer).map_err(|err| err.to_string())?;
+
+    // packing masks off the high bits of fields that are too large, so make sure every field
+    // survives the round trip
+    let packed = RtpMessage::decode_payload(kind, &buffer[HEADER_SIZE..written])
+        .map_err(|err| err.to_string())?;
+    if packed != message {
+        let (requested, packed) = (
+            serde_json::to_value(message).unwrap(),
+            serde_json::to_value(packed).unwrap(),
+        );
+        let (requested, packed) = (fields_of(&requested), fields_of(&packed));
+        let (name, value) = requested
+            .iter()
+            .find(|(name, value)| packed.get(*name) != Some(value))
+            .unwrap();
+        return Err(format!(
+            "{}={} does not fit into the packed message (it would be {})",
+            name, value, packed[name]
+        ));
+    }
+
+    Ok(if envelope {
+        to_hex(&buffer[..written])
+    } else {
+        to_hex(&buffer[HEADER_SIZE..written])
+    })
+}
+
+/// The fields of a serialized `RtpMessage`
+fn fields_of(value: &Value) -> &Map<String, Value> {
+    value
+        .as_object()
+        .and_then(|variant| variant.values().next())
+        .and_then(Value::as_object)
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that hex is accepted in the common formats and that the format of a frame is guessed
+    /// from its length
+    #[test]
+    fn test_parse_hex_and_guess_format() {
+        assert_eq!(parse_hex("0x9e204e").unwrap(), [0x9e, 0x20, 0x4e]);
+        assert_eq!(
+            parse_hex("9e 20:4E,10_27").unwrap(),
+            [0x9e, 0x20, 0x4e, 0x10, 0x27]
+        );
+        assert!(parse_hex("9e2").is_err());
+        assert!(parse_hex("zz").is_err());
+
+        assert_eq!(
+            guess_format(&[0; 11]).unwrap(),
+            FrameFormat::Bare(MessageKind::Control)
+        );
+        assert_eq!(
+            guess_format(&[0; 4]).unwrap(),
+            FrameFormat::Bare(MessageKind::RobotStatus)
+        );
+        assert!(guess_format(&[0; 8]).is_err());
+    }
+
+    /// Test that a message encoded from its fields decodes back into the same fields
+    #[test]
+    fn test_encode_and_decode() {
+        let hex = encode(
+            MessageKind::Control,
+            false,
+            &["team=Yellow", "robot_id=3", "body_x=1.5", "sequence=42"],
+        )
+        .unwrap();
+        assert_eq!(hex, "98 70 17 00 00 00 00 00 00 00 2a");
+
+        let args: Vec<String> = ["hex", &hex, "--as", "control"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let decoded: Value = serde_json::from_str(&run(&args).unwrap()).unwrap();
+        assert_eq!(decoded["Control"]["team"], "Yellow");
+        assert_eq!(decoded["Control"]["body_x"], 1.5);
+
+        let envelope = encode(MessageKind::Control, true, &["robot_id=3"]).unwrap();
+        assert_eq!(
+            guess_format(&parse_hex(&envelope).unwrap()).unwrap(),
+            FrameFormat::Envelope
+        );
+
+        assert!(encode(MessageKind::Control, false, &["speed=3"]).is_err());
+        assert!(encode(MessageKind::Control, false, &["team=Green"]).is_err());
+
+        // values that do not fit into their fields are rejected instead of masked or clamped
+        assert!(encode(MessageKind::Control, false, &["robot_id=99"]).is_err());
+        assert!(encode(MessageKind::Control, false, &["body_x=100"]).is_err());
+        assert_eq!(
+            encode(MessageKind::RobotStatus, false, &["robot_id=20"]),
+            Err("robot_id=20 does not fit into the packed message (it would be 4)".into())
+        );
+    }
+}
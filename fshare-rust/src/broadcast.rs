@@ -0,0 +1,307 @@
+//!
+//! The Broadcast Frame packs commands for several robots of one team into a single nRF24L01+
+//! payload so the base station does not need one radio transaction per robot each tick.
+//!
+//! The frame is sent to the team's `TEAM_BROADCAST_ADDRESSES` pipe and every robot listening on
+//! it extracts its own command by `robot_id` using `command_for`.
+//!
+//! A frame holds at most `MAX_BROADCAST_SLOTS` (3) commands, so a full team of six robots needs
+//! two broadcast frames, and two radio transactions, per tick.
+//!
+
+use ncomm_utils::packing::{Packable, PackingError};
+
+use crate::error::check_len;
+use crate::radio_link::MAX_PAYLOAD_SIZE;
+use crate::{ControlMessage, ControlMessageBuilder, RtpError, Team, CONTROL_MESSAGE_SIZE};
+
+/// The size of the header at the start of every broadcast frame in Bytes
+pub const BROADCAST_HEADER_SIZE: usize = 2;
+
+/// The size of each robot's slot in a broadcast frame in Bytes (a packed `ControlMessage`
+/// without its trailing sequence number, which is shared through the header)
+pub const BROADCAST_SLOT_SIZE: usize = CONTROL_MESSAGE_SIZE - 1;
+
+/// The largest number of robot slots that fit in a single radio payload.
+///
+/// Note: this is fewer than `ROBOTS_PER_TEAM`, so commanding a full team takes two broadcast
+/// frames (two radio transactions) per tick
+pub const MAX_BROADCAST_SLOTS: usize =
+    (MAX_PAYLOAD_SIZE - BROADCAST_HEADER_SIZE) / BROADCAST_SLOT_SIZE;
+
+/// A Broadcast Frame carrying commands for `N` robots of one team.
+///
+/// The Packed Format of this message is as follows:
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
+/// |    7    |    6    |    5    |    4    |    3    |    2    |    1    |    0    |
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
+/// | team    | count                       | unused                                |
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
+/// | sequence                                                                      |
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
+/// | slot 0 (10 Bytes)                                                             |
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
+/// | ...                                                                           |
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
+/// | slot N - 1 (10 Bytes)                                                         |
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
+///
+/// Each slot is the first 10 Bytes of a packed `ControlMessage` with the team bit cleared (the
+/// team and sequence number are shared by every slot and live in the header):
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
+/// | unused  | robot id                              | shoot_m | trigger_mode      |
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
+/// | body_x (lsb) ... kick_strength (8 Bytes)                                      |
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
+/// | role              | mode                                                      |
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
+///
+/// Size = 2 + 10 * N Bytes (at most MAX_PAYLOAD_SIZE, which is checked at compile time)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BroadcastFrame<const N: usize> {
+    /// The team every command in the frame is for
+    pub team: Team,
+    /// The sequence number shared by every command in the frame
+    pub sequence: u8,
+    /// The command for each robot.
+    ///
+    /// Note: the `team` and `sequence` of each command are replaced by those of the frame
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_impls::array"))]
+    pub commands: [ControlMessage; N],
+}
+
+impl<const N: usize> BroadcastFrame<N> {
+    /// The size of this frame in Bytes
+    pub const SIZE: usize = BROADCAST_HEADER_SIZE + N * BROADCAST_SLOT_SIZE;
+
+    /// Fails to compile when the frame is empty or does not fit in a radio payload
+    const FITS_IN_PAYLOAD: () = assert!(
+        N > 0 && Self::SIZE <= MAX_PAYLOAD_SIZE,
+        "a broadcast frame must hold between 1 and MAX_BROADCAST_SLOTS commands"
+    );
+
+    /// Create a new broadcast frame for a team
+    pub fn new(team: Team, sequence: u8, commands: [ControlMessage; N]) -> Self {
+        let () = Self::FITS_IN_PAYLOAD;
+        Self {
+            team,
+            sequence,
+            commands,
+        }
+    }
+
+    /// Unpack a broadcast frame, rejecting frames that hold a different number of commands
+    pub fn try_unpack(data: &[u8]) -> Result<Self, RtpError> {
+        let () = Self::FITS_IN_PAYLOAD;
+        check_len(data, BROADCAST_HEADER_SIZE)?;
+        let (team, count, sequence) = unpack_header(data)?;
+        if count != N {
+            return Err(RtpError::SlotCountMismatch {
+                expected: N,
+                found: count,
+            });
+        }
+        check_len(data, Self::SIZE)?;
+
+        let mut commands = [ControlMessageBuilder::new().build(); N];
+        for (slot, command) in commands.iter_mut().enumerate() {
+            *command = unpack_slot(data, slot, team, sequence)?;
+        }
+
+        Ok(Self {
+            team,
+            sequence,
+            commands,
+        })
+    }
+}
+
+impl<const N: usize> Packable for BroadcastFrame<N> {
+    fn len() -> usize {
+        Self::SIZE
+    }
+
+    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
+        let () = Self::FITS_IN_PAYLOAD;
+        check_len(buffer, Self::SIZE)?;
+
+        buffer[0] = (self.team as u8) << 7 | ((N as u8) & 0b111) << 4;
+        buffer[1] = self.sequence;
+
+        let mut packed = [0u8; CONTROL_MESSAGE_SIZE];
+        for (slot, command) in self.commands.into_iter().enumerate() {
+            command.pack(&mut packed)?;
+            let offset = slot_offset(slot);
+            buffer[offset] = packed[0] & 0b0111_1111;
+            buffer[offset + 1..offset + BROADCAST_SLOT_SIZE]
+                .copy_from_slice(&packed[1..BROADCAST_SLOT_SIZE]);
+        }
+
+        Ok(())
+    }
+
+    fn unpack(data: &[u8]) -> Result<Self, PackingError> {
+        Ok(Self::try_unpack(data)?)
+    }
+}
+
+/// Extract the command for a given robot from a packed broadcast frame of any size,
+/// returning `None` if the frame does not contain a command for the robot
+pub fn command_for(data: &[u8], robot_id: u8) -> Result<Option<ControlMessage>, RtpError> {
+    check_len(data, BROADCAST_HEADER_SIZE)?;
+    let (team, count, sequence) = unpack_header(data)?;
+    check_len(data, BROADCAST_HEADER_SIZE + count * BROADCAST_SLOT_SIZE)?;
+
+    for slot in 0..count {
+        let slot_robot_id = (data[slot_offset(slot)] & (0b1111 << 3)) >> 3;
+        if slot_robot_id == robot_id {
+            return unpack_slot(data, slot, team, sequence).map(Some);
+        }
+    }
+
+    Ok(None)
+}
+
+/// Unpack every command in a packed broadcast frame of any size (in slot order)
+pub fn for_each_command(data: &[u8], mut f: impl FnMut(ControlMessage)) -> Result<(), RtpError> {
+    check_len(data, BROADCAST_HEADER_SIZE)?;
+    let (team, count, sequence) = unpack_header(data)?;
+    check_len(data, BROADCAST_HEADER_SIZE + count * BROADCAST_SLOT_SIZE)?;
+
+    for slot in 0..count {
+        f(unpack_slot(data, slot, team, sequence)?);
+    }
+
+    Ok(())
+}
+
+/// Unpack the (team, count, sequence) from a broadcast frame header
+fn unpack_header(data: &[u8]) -> Result<(Team, usize, u8), RtpError> {
+    let team = Team::try_from((data[0] & (0b1 << 7)) >> 7)?;
+    let count = ((data[0] & (0b111 << 4)) >> 4) as usize;
+    Ok((team, count, data[1]))
+}
+
+/// The offset of a slot from the start of a broadcast frame
+fn slot_offset(slot: usize) -> usize {
+    BROADCAST_HEADER_SIZE + slot * BROADCAST_SLOT_SIZE
+}
+
+/// Rebuild the control message stored in a slot of a broadcast frame
+fn unpack_slot(
+    data: &[u8],
+    slot: usize,
+    team: Team,
+    sequence: u8,
+) -> Result<ControlMessage, RtpError> {
+    let offset = slot_offset(slot);
+    let mut packed = [0u8; CONTROL_MESSAGE_SIZE];
+    packed[..BROADCAST_SLOT_SIZE].copy_from_slice(&data[offset..offset + BROADCAST_SLOT_SIZE]);
+    packed[0] = (team as u8) << 7 | packed[0] & 0b0111_1111;
+    packed[BROADCAST_SLOT_SIZE] = sequence;
+    Ok(ControlMessage::unpack(&packed)?)
+}
+
+#[cfg(test)]
+#[allow(clippy::unusual_byte_groupings)]
+mod tests {
+    use super::*;
+
+    use crate::control_message::{Mode, ShootMode, TriggerMode};
+
+    fn commands() -> [ControlMessage; MAX_BROADCAST_SLOTS] {
+        [
+            ControlMessageBuilder::new()
+                .team(Team::Yellow)
+                .robot_id(0)
+                .body_x(1.0)
+                .sequence(9)
+                .build(),
+            ControlMessageBuilder::new()
+                .team(Team::Yellow)
+                .robot_id(5)
+                .shoot_mode(ShootMode::Chip)
+                .trigger_mode(TriggerMode::Immediate)
+                .body_w(-2.5)
+                .kick_strength(200)
+                .mode(Mode::KickerTest)
+                .sequence(9)
+                .build(),
+            ControlMessageBuilder::new()
+                .team(Team::Yellow)
+                .robot_id(2)
+                .dribbler_speed(-20)
+                .role(3)
+                .sequence(9)
+                .build(),
+        ]
+    }
+
+    /// Test that a full broadcast frame fits in an nRF24L01+ payload and can be packed and
+    /// unpacked
+    #[test]
+    fn test_broadcast_frame_pack_and_unpack() {
+        let frame = BroadcastFrame::new(Team::Yellow, 9, commands());
+        assert_eq!(
+            BroadcastFrame::<MAX_BROADCAST_SLOTS>::SIZE,
+            MAX_PAYLOAD_SIZE
+        );
+
+        let mut buffer = [0u8; MAX_PAYLOAD_SIZE];
+        frame.pack(&mut buffer).unwrap();
+        assert_eq!(buffer[0], 0b1_011_0000);
+        assert_eq!(buffer[1], 9);
+        assert_eq!(buffer[2], 0b0_0000_0_00);
+        assert_eq!(buffer[12], 0b0_0101_1_01);
+
+        assert_eq!(
+            BroadcastFrame::<MAX_BROADCAST_SLOTS>::unpack(&buffer).unwrap(),
+            frame
+        );
+        assert_eq!(
+            BroadcastFrame::<2>::try_unpack(&buffer),
+            Err(RtpError::SlotCountMismatch {
+                expected: 2,
+                found: MAX_BROADCAST_SLOTS
+            })
+        );
+    }
+
+    /// Test that each robot can extract its own command from a frame
+    #[test]
+    fn test_broadcast_frame_command_for() {
+        let commands = commands();
+        let mut buffer = [0u8; MAX_PAYLOAD_SIZE];
+        BroadcastFrame::new(Team::Yellow, 9, commands)
+            .pack(&mut buffer)
+            .unwrap();
+
+        for command in commands {
+            assert_eq!(command_for(&buffer, command.robot_id), Ok(Some(command)));
+        }
+        assert_eq!(command_for(&buffer, 4), Ok(None));
+        assert!(command_for(&buffer[..20], 2).is_err());
+    }
+
+    /// Test that a broadcast frame serializes its commands as a list and deserializes back into
+    /// the same frame
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_broadcast_frame_serde() {
+        let frame = BroadcastFrame::new(Team::Yellow, 9, commands());
+
+        let value = serde_json::to_value(frame).unwrap();
+        assert_eq!(
+            value["commands"].as_array().unwrap().len(),
+            MAX_BROADCAST_SLOTS
+        );
+        assert_eq!(value["commands"][0]["body_x"], 1.0);
+        assert_eq!(
+            serde_json::from_value::<BroadcastFrame<MAX_BROADCAST_SLOTS>>(value.clone()).unwrap(),
+            frame
+        );
+        assert!(serde_json::from_value::<BroadcastFrame<2>>(value).is_err());
+    }
+}
@@ -0,0 +1,211 @@
+//!
+//! Opt-in integrity checking for packed messages.
+//!
+//! Any `Packable` message can be wrapped in `Checked` to append a CRC trailer after the packed
+//! message.  Unpacking a `Checked` message recomputes the CRC and rejects the message if the
+//! trailer does not match, so a corrupted byte is dropped instead of turning into a wrong
+//! velocity or a spurious kick.
+//!
+
+use core::marker::PhantomData;
+
+use ncomm_utils::packing::{Packable, PackingError};
+
+use crate::error::check_len;
+use crate::RtpError;
+
+/// An algorithm used to compute the trailer of a `Checked` message
+pub trait Checksum {
+    /// The size of the trailer in Bytes (at most 2, which is checked at compile time by `Checked`)
+    const SIZE: usize;
+
+    /// Compute the checksum of a slice of Bytes
+    fn compute(data: &[u8]) -> u16;
+}
+
+/// CRC-8/SMBUS (polynomial 0x07, initial value 0x00)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Crc8;
+
+/// Lookup table for the Crc8 polynomial
+const CRC8_TABLE: [u8; 256] = {
+    let mut table = [0u8; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x80 != 0 { crc << 1 ^ 0x07 } else { crc << 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+impl Checksum for Crc8 {
+    const SIZE: usize = 1;
+
+    fn compute(data: &[u8]) -> u16 {
+        data.iter()
+            .fold(0u8, |crc, byte| CRC8_TABLE[(crc ^ byte) as usize]) as u16
+    }
+}
+
+/// CRC-16/CCITT-FALSE (polynomial 0x1021, initial value 0xFFFF)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Crc16;
+
+/// Lookup table for the Crc16 polynomial
+const CRC16_TABLE: [u16; 256] = {
+    let mut table = [0u16; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u16) << 8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000 != 0 { crc << 1 ^ 0x1021 } else { crc << 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+impl Checksum for Crc16 {
+    const SIZE: usize = 2;
+
+    fn compute(data: &[u8]) -> u16 {
+        data.iter().fold(0xFFFF, |crc, byte| {
+            crc << 8 ^ CRC16_TABLE[((crc >> 8) as u8 ^ byte) as usize]
+        })
+    }
+}
+
+/// A message followed by a checksum trailer.
+///
+/// The packed format of a checked message is as follows:
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
+/// |    7    |    6    |    5    |    4    |    3    |    2    |    1    |    0    |
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
+/// | message (T::len() Bytes)                                                      |
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
+/// | checksum (C::SIZE Bytes, lsb first)                                           |
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
+///
+/// Size = T::len() + C::SIZE Bytes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Checked<T, C = Crc8> {
+    /// The message protected by the checksum
+    pub message: T,
+    checksum: PhantomData<C>,
+}
+
+impl<T: Packable, C: Checksum> Checked<T, C> {
+    /// Fails to compile when the checksum does not fit into the `u16` it is computed as
+    const TRAILER_FITS: () = assert!(
+        C::SIZE <= 2,
+        "a checksum trailer must be at most 2 Bytes long"
+    );
+
+    /// Wrap a message so it is packed with a checksum trailer
+    pub fn new(message: T) -> Self {
+        Self {
+            message,
+            checksum: PhantomData,
+        }
+    }
+
+    /// Take the message out of the wrapper
+    pub fn into_inner(self) -> T {
+        self.message
+    }
+
+    /// Unpack a checked message, reporting checksum mismatches
+    pub fn try_unpack(data: &[u8]) -> Result<Self, RtpError> {
+        let () = Self::TRAILER_FITS;
+        check_len(data, Self::len())?;
+
+        let (message, trailer) = data.split_at(T::len());
+        let expected = C::compute(message);
+        let mut found = [0u8; 2];
+        found[..C::SIZE].copy_from_slice(&trailer[..C::SIZE]);
+        let found = u16::from_le_bytes(found);
+        if expected != found {
+            return Err(RtpError::ChecksumMismatch { expected, found });
+        }
+
+        Ok(Self::new(T::unpack(message)?))
+    }
+}
+
+impl<T: Packable, C: Checksum> Packable for Checked<T, C> {
+    fn len() -> usize {
+        T::len() + C::SIZE
+    }
+
+    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
+        let () = Self::TRAILER_FITS;
+        check_len(buffer, Self::len())?;
+
+        let (message, trailer) = buffer.split_at_mut(T::len());
+        self.message.pack(message)?;
+        let checksum = C::compute(message).to_le_bytes();
+        trailer[..C::SIZE].copy_from_slice(&checksum[..C::SIZE]);
+        Ok(())
+    }
+
+    fn unpack(data: &[u8]) -> Result<Self, PackingError> {
+        Ok(Self::try_unpack(data)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{ControlMessage, ControlMessageBuilder, Team, CONTROL_MESSAGE_SIZE};
+
+    /// Test the checksums against the standard check value for "123456789"
+    #[test]
+    fn test_checksum_check_values() {
+        assert_eq!(Crc8::compute(b"123456789"), 0xF4);
+        assert_eq!(Crc16::compute(b"123456789"), 0x29B1);
+    }
+
+    /// Test that a checked message can be packed and unpacked and that a single flipped bit
+    /// is detected
+    #[test]
+    fn test_checked_pack_and_unpack() {
+        let message = Checked::<ControlMessage, Crc16>::new(
+            ControlMessageBuilder::new()
+                .team(Team::Yellow)
+                .robot_id(4)
+                .body_y(-0.5)
+                .kick_strength(12)
+                .build(),
+        );
+
+        let mut buffer = [0u8; CONTROL_MESSAGE_SIZE + 2];
+        message.pack(&mut buffer).unwrap();
+        assert_eq!(
+            Checked::<ControlMessage, Crc16>::unpack(&buffer).unwrap(),
+            message
+        );
+
+        buffer[3] ^= 0b0000_0100;
+        assert!(matches!(
+            Checked::<ControlMessage, Crc16>::try_unpack(&buffer),
+            Err(RtpError::ChecksumMismatch { .. })
+        ));
+        assert_eq!(
+            Checked::<ControlMessage>::try_unpack(&buffer[..CONTROL_MESSAGE_SIZE]),
+            Err(RtpError::BufferTooShort {
+                expected: CONTROL_MESSAGE_SIZE + 1,
+                actual: CONTROL_MESSAGE_SIZE,
+            })
+        );
+    }
+}
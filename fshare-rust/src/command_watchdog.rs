@@ -0,0 +1,290 @@
+//!
+//! The failsafe a robot applies when `ControlMessage`s stop arriving.
+//!
+//! The `CommandWatchdog` is fed every `ControlMessage` the robot receives and is asked for the
+//! command to execute on every tick of the control loop.  While commands keep arriving it returns
+//! the latest one.  Once they stop, it:
+//! 1. holds the last command for `WatchdogConfig::hold`
+//! 2. ramps the body velocity linearly to zero over `WatchdogConfig::ramp`
+//! 3. forces `TriggerMode::StandDown` after `WatchdogConfig::kicker_timeout`
+//! 4. stops the dribbler after `WatchdogConfig::dribbler_timeout`
+//!
+//! A `HaltMessage` for the robot's team latches the robot in a halt: it is stopped regardless of
+//! the commands it receives until a resume for its team arrives.  Halt messages are repeated
+//! without acknowledgements and may arrive out of order, so only messages with a newer sequence
+//! number than the last one accepted change the halt (see `HaltLatch`), unless none has been
+//! accepted for `WatchdogConfig::halt_resync` (e.g. because the base station restarted).
+//!
+//! Like the `SequenceTracker`, timestamps are plain `u64`s from any monotonic clock (e.g.
+//! microseconds since startup) and the timeouts are in the same unit.
+//!
+
+use crate::control_message::TriggerMode;
+use crate::sequence::DEFAULT_RESYNC_TIMEOUT;
+use crate::{ControlMessage, ControlMessageBuilder, HaltLatch, HaltMessage, Team};
+
+/// The timeouts of a `CommandWatchdog`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WatchdogConfig {
+    /// How long the last command is executed unchanged after it was received
+    pub hold: u64,
+    /// How long the velocity takes to ramp down to zero once the hold is over
+    pub ramp: u64,
+    /// How long after the last command the kicker is forced to stand down
+    pub kicker_timeout: u64,
+    /// How long after the last command the dribbler is stopped
+    pub dribbler_timeout: u64,
+    /// How long after the last halt message was accepted any sequence number is accepted again
+    pub halt_resync: u64,
+}
+
+impl Default for WatchdogConfig {
+    /// Timeouts in microseconds
+    fn default() -> Self {
+        Self {
+            hold: 100_000,
+            ramp: 250_000,
+            kicker_timeout: 100_000,
+            dribbler_timeout: 500_000,
+            halt_resync: DEFAULT_RESYNC_TIMEOUT,
+        }
+    }
+}
+
+/// What the watchdog is doing with the last command
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchdogState {
+    /// No command has been received yet, so the robot is stopped
+    Waiting,
+    /// The last command is executed unchanged
+    Holding,
+    /// The velocity of the last command is ramping down to zero
+    Ramping,
+    /// The robot is stopped until the next command arrives
+    Stopped,
+    /// The robot is stopped until its team is resumed
+    Halted,
+}
+
+/// Turns the `ControlMessage`s a robot receives into the command it should execute
+#[derive(Clone, Copy, Debug)]
+pub struct CommandWatchdog {
+    config: WatchdogConfig,
+    team: Team,
+    robot_id: u8,
+    /// The last command and when it was received
+    last: Option<(ControlMessage, u64)>,
+    halt: HaltLatch,
+}
+
+impl CommandWatchdog {
+    /// Create a watchdog for a robot that has not received any commands
+    pub fn new(team: Team, robot_id: u8, config: WatchdogConfig) -> Self {
+        Self {
+            config,
+            team,
+            robot_id,
+            last: None,
+            halt: HaltLatch::new(team, config.halt_resync),
+        }
+    }
+
+    /// The timeouts of the watchdog
+    pub fn config(&self) -> &WatchdogConfig {
+        &self.config
+    }
+
+    /// Record a command received at `now`.
+    ///
+    /// Commands for other robots are ignored (returning false)
+    pub fn receive(&mut self, message: &ControlMessage, now: u64) -> bool {
+        if message.team != self.team || message.robot_id != self.robot_id {
+            return false;
+        }
+        self.last = Some((*message, now));
+        true
+    }
+
+    /// Latch (or release) a halt received at `now`.
+    ///
+    /// Halt messages for other teams and repeated or late halt messages (whose sequence number is
+    /// not newer than the last one accepted) are ignored (returning false)
+    pub fn receive_halt(&mut self, message: &HaltMessage, now: u64) -> bool {
+        self.halt.receive(message, now)
+    }
+
+    /// Is the robot latched in a halt (to be reported in `RobotStatusMessage::halted`)
+    pub fn is_halted(&self) -> bool {
+        self.halt.is_halted()
+    }
+
+    /// What the watchdog is doing at `now`
+    pub fn state(&self, now: u64) -> WatchdogState {
+        if self.is_halted() {
+            return WatchdogState::Halted;
+        }
+        let Some((_, received_at)) = self.last else {
+            return WatchdogState::Waiting;
+        };
+        let elapsed = now.saturating_sub(received_at);
+        if elapsed <= self.config.hold {
+            WatchdogState::Holding
+        } else if elapsed < self.config.hold.saturating_add(self.config.ramp) {
+            WatchdogState::Ramping
+        } else {
+            WatchdogState::Stopped
+        }
+    }
+
+    /// The command the robot should execute at `now`
+    pub fn command(&self, now: u64) -> ControlMessage {
+        let Some((mut command, received_at)) = self.last.filter(|_| !self.is_halted()) else {
+            return ControlMessageBuilder::new()
+                .team(self.team)
+                .robot_id(self.robot_id)
+                .build();
+        };
+        let elapsed = now.saturating_sub(received_at);
+
+        let scale = match self.state(now) {
+            WatchdogState::Waiting | WatchdogState::Holding => 1.0,
+            WatchdogState::Ramping => {
+                1.0 - (elapsed - self.config.hold) as f32 / self.config.ramp as f32
+            }
+            WatchdogState::Stopped | WatchdogState::Halted => 0.0,
+        };
+        // truncating rounds towards zero so the ramp never overshoots
+        command.body_x = (command.body_x as f32 * scale) as i16;
+        command.body_y = (command.body_y as f32 * scale) as i16;
+        command.body_w = (command.body_w as f32 * scale) as i16;
+
+        if elapsed > self.config.kicker_timeout {
+            command.trigger_mode = TriggerMode::StandDown;
+            command.kick_strength = 0;
+        }
+        if elapsed > self.config.dribbler_timeout {
+            command.dribbler_speed = 0;
+        }
+        command
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that the last command is held, ramped down to zero and that the kicker and dribbler
+    /// are stopped after their timeouts
+    #[test]
+    fn test_watchdog_failsafe() {
+        let mut watchdog = CommandWatchdog::new(Team::Yellow, 4, WatchdogConfig::default());
+        assert_eq!(watchdog.state(0), WatchdogState::Waiting);
+        let stopped = watchdog.command(0);
+        assert_eq!((stopped.team, stopped.robot_id), (Team::Yellow, 4));
+        assert_eq!(stopped.get_velocity(), nalgebra::Vector3::zeros());
+        assert_eq!(stopped.trigger_mode, TriggerMode::StandDown);
+
+        let command = ControlMessageBuilder::new()
+            .team(Team::Yellow)
+            .robot_id(4)
+            .body_x(2.0)
+            .body_y(-1.0)
+            .body_w(0.5)
+            .trigger_mode(TriggerMode::OnBreakBeam)
+            .kick_strength(100)
+            .dribbler_speed(50)
+            .build();
+        assert!(watchdog.receive(&command, 1_000_000));
+
+        assert_eq!(watchdog.state(1_100_000), WatchdogState::Holding);
+        assert_eq!(watchdog.command(1_100_000), command);
+
+        let ramping = watchdog.command(1_150_000);
+        assert_eq!(watchdog.state(1_150_000), WatchdogState::Ramping);
+        assert_eq!(
+            (ramping.body_x, ramping.body_y, ramping.body_w),
+            (6400, -3200, 800)
+        );
+        assert_eq!(ramping.trigger_mode, TriggerMode::StandDown);
+        assert_eq!(ramping.kick_strength, 0);
+        assert_eq!(ramping.dribbler_speed, 50);
+
+        let stopped = watchdog.command(1_600_000);
+        assert_eq!(watchdog.state(1_600_000), WatchdogState::Stopped);
+        assert_eq!((stopped.body_x, stopped.body_y, stopped.body_w), (0, 0, 0));
+        assert_eq!(stopped.dribbler_speed, 0);
+
+        // a new command resumes immediately
+        assert!(watchdog.receive(&command, 2_000_000));
+        assert_eq!(watchdog.command(2_000_000), command);
+    }
+
+    /// Test that commands for other robots do not reset the watchdog
+    #[test]
+    fn test_watchdog_ignores_other_robots() {
+        let mut watchdog = CommandWatchdog::new(Team::Blue, 1, WatchdogConfig::default());
+        let other = ControlMessageBuilder::new().robot_id(2).body_x(1.0).build();
+        assert!(!watchdog.receive(&other, 0));
+        assert_eq!(watchdog.state(0), WatchdogState::Waiting);
+        assert_eq!(watchdog.command(0).body_x, 0);
+    }
+
+    /// Test that a halt for the robot's team stops it until the team is resumed
+    #[test]
+    fn test_watchdog_halt_is_latched() {
+        let mut watchdog = CommandWatchdog::new(Team::Blue, 1, WatchdogConfig::default());
+        let command = ControlMessageBuilder::new()
+            .robot_id(1)
+            .body_x(1.0)
+            .trigger_mode(TriggerMode::Immediate)
+            .dribbler_speed(20)
+            .build();
+        watchdog.receive(&command, 0);
+
+        assert!(!watchdog.receive_halt(&HaltMessage::halt(Team::Yellow, 0), 0));
+        assert!(watchdog.receive_halt(&HaltMessage::halt(Team::Blue, 0), 0));
+        assert!(watchdog.is_halted());
+        // new commands do not release the halt
+        assert!(watchdog.receive(&command, 10));
+        assert_eq!(watchdog.state(10), WatchdogState::Halted);
+        let halted = watchdog.command(10);
+        assert_eq!((halted.body_x, halted.dribbler_speed), (0, 0));
+        assert_eq!(halted.trigger_mode, TriggerMode::StandDown);
+
+        assert!(!watchdog.receive_halt(&HaltMessage::resume(Team::Yellow, 1), 20));
+        assert!(watchdog.is_halted());
+        assert!(watchdog.receive_halt(&HaltMessage::resume(Team::Blue, 1), 20));
+        assert_eq!(watchdog.state(20), WatchdogState::Holding);
+        assert_eq!(watchdog.command(20), command);
+    }
+
+    /// Test that a repeated halt arriving after the resume does not halt the robot again
+    #[test]
+    fn test_watchdog_ignores_late_halt() {
+        let mut watchdog = CommandWatchdog::new(Team::Blue, 1, WatchdogConfig::default());
+        assert!(watchdog.receive_halt(&HaltMessage::halt(Team::Blue, 255), 0));
+        assert!(!watchdog.receive_halt(&HaltMessage::halt(Team::Blue, 255), 10));
+        assert!(watchdog.receive_halt(&HaltMessage::resume(Team::Blue, 0), 20));
+        assert!(!watchdog.receive_halt(&HaltMessage::halt(Team::Blue, 255), 30));
+        assert!(!watchdog.is_halted());
+
+        assert!(watchdog.receive_halt(&HaltMessage::halt(Team::Blue, 1), 40));
+        assert!(watchdog.is_halted());
+    }
+
+    /// Test that a restarted base station (counting from 0 again) can halt the robot once the
+    /// halt re-sync timeout has passed
+    #[test]
+    fn test_watchdog_halt_resyncs() {
+        let config = WatchdogConfig::default();
+        let mut watchdog = CommandWatchdog::new(Team::Blue, 1, config);
+        assert!(watchdog.receive_halt(&HaltMessage::halt(Team::Blue, 100), 0));
+        assert!(watchdog.receive_halt(&HaltMessage::resume(Team::Blue, 101), 1_000));
+
+        assert!(!watchdog.receive_halt(&HaltMessage::halt(Team::Blue, 0), 2_000));
+        assert!(!watchdog.is_halted());
+        let resync = 1_000 + config.halt_resync + 1;
+        assert!(watchdog.receive_halt(&HaltMessage::halt(Team::Blue, 0), resync));
+        assert!(watchdog.is_halted());
+    }
+}
@@ -1,24 +1,50 @@
 //!
 //! The Control Message is sent to robots over radio to inform them of what actions to take.
 //!
+//! The packing code and layout table are generated from `schema/messages.toml`, edit the schema
+//! and run `cargo run -p rtp-codegen` to change the layout.
+//!
 
 #![allow(dead_code)]
 
+use core::fmt;
+
 use nalgebra::base::*;
-use ncomm_utils::packing::{Packable, PackingError};
 
-use crate::Team;
+use crate::fixed_point::VelocityEncoding;
+use crate::{InvalidDiscriminant, Team, ROBOTS_PER_TEAM};
+
+/// The body{X, Y} are multiplied (upon sending) by the LINEAR_VELOCITY_SCALE_FACTOR and divided
+/// (upon receiving), giving a resolution of 0.25 mm/s up to ±8.19 m/s.
+pub const LINEAR_VELOCITY_SCALE_FACTOR: f32 = 4000.0;
+
+/// The body{W} is multiplied (upon sending) by the ANGULAR_VELOCITY_SCALE_FACTOR and divided
+/// (upon receiving), giving a resolution of 0.5 mrad/s up to ±16.38 rad/s.
+pub const ANGULAR_VELOCITY_SCALE_FACTOR: f32 = 2000.0;
 
-/// The body{X, Y, W} are multiplied (upon sending) by the VELOCITY_SCALE_FACTOR and divided
-/// (upon receiving) to preserve at least 3 decimals of floating point precision.
-pub const VELOCITY_SCALE_FACTOR: f32 = 1000.0;
+/// The scale factor all velocities were multiplied by before the linear and angular velocities
+/// got their own.  It now equals LINEAR_VELOCITY_SCALE_FACTOR, body{W} has to be scaled by
+/// ANGULAR_VELOCITY_SCALE_FACTOR instead.
+#[deprecated(
+    since = "0.7.0",
+    note = "use LINEAR_VELOCITY_SCALE_FACTOR or ANGULAR_VELOCITY_SCALE_FACTOR"
+)]
+pub const VELOCITY_SCALE_FACTOR: f32 = LINEAR_VELOCITY_SCALE_FACTOR;
 
 /// The size of a ControlMessage in Bytes as a constant.
 /// Note: This is tested in the tests so it can be trusted
-pub const CONTROL_MESSAGE_SIZE: usize = 10;
+pub const CONTROL_MESSAGE_SIZE: usize = 11;
+
+/// The largest role that fits into the 2 bits of the role field
+pub const MAX_ROLE: u8 = 0b11;
+
+// every mode fits into the 6 bits of the mode field, so `try_build` has nothing to check
+const _: () = assert!(Mode::FpgaTest as u8 <= 0b111111);
 
 /// The Trigger Mode Kicking
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum TriggerMode {
     /// Slowly expel the charge in the kicker
     StandDown = 0,
@@ -28,13 +54,31 @@ pub enum TriggerMode {
     OnBreakBeam = 2,
 }
 
-impl Into<u8> for TriggerMode {
-    fn into(self) -> u8 {
-        self as u8
+impl TryFrom<u8> for TriggerMode {
+    type Error = InvalidDiscriminant;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::StandDown),
+            1 => Ok(Self::Immediate),
+            2 => Ok(Self::OnBreakBeam),
+            _ => Err(InvalidDiscriminant {
+                field: "trigger_mode",
+                value,
+            }),
+        }
+    }
+}
+
+impl From<TriggerMode> for u8 {
+    fn from(value: TriggerMode) -> Self {
+        value as u8
     }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// How the robot should kick the ball
 pub enum ShootMode {
     /// The robot should kick the ball
@@ -43,16 +87,33 @@ pub enum ShootMode {
     Chip = 1,
 }
 
-impl Into<bool> for ShootMode {
-    fn into(self) -> bool {
-        match self {
+impl TryFrom<u8> for ShootMode {
+    type Error = InvalidDiscriminant;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Kick),
+            1 => Ok(Self::Chip),
+            _ => Err(InvalidDiscriminant {
+                field: "shoot_mode",
+                value,
+            }),
+        }
+    }
+}
+
+impl From<ShootMode> for bool {
+    fn from(value: ShootMode) -> Self {
+        match value {
             ShootMode::Kick => false,
             ShootMode::Chip => true,
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// The `mode` the robot should be in
 /// 
 /// In general, this field should pretty much always be set to Default.  However, I created
@@ -62,6 +123,7 @@ pub enum Mode {
     /// Default execution mode.  In default execution mode, the robot continually
     /// runs the normal motion control update loop and should behave as one would expect
     /// our robots to perform
+    #[default]
     Default = 0,
     /// Test the IMU on the robot
     ImuTest = 1,
@@ -79,57 +141,46 @@ pub enum Mode {
     FpgaTest = 7,
 }
 
-impl Default for Mode {
-    fn default() -> Self {
-        Self::Default
-    }
-}
+impl TryFrom<u8> for Mode {
+    type Error = InvalidDiscriminant;
 
-impl From<u8> for Mode {
-    fn from(value: u8) -> Self {
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
-            0 => Self::Default,
-            1 => Self::ImuTest,
-            2 => Self::ReceiveBenchmark,
-            3 => Self::SendBenchmark,
-            4 => Self::ProgramKickOnBreakbeam,
-            5 => Self::ProgramKicker,
-            6 => Self::KickerTest,
-            7 => Self::FpgaTest,
-            _ => Self::Default,
+            0 => Ok(Self::Default),
+            1 => Ok(Self::ImuTest),
+            2 => Ok(Self::ReceiveBenchmark),
+            3 => Ok(Self::SendBenchmark),
+            4 => Ok(Self::ProgramKickOnBreakbeam),
+            5 => Ok(Self::ProgramKicker),
+            6 => Ok(Self::KickerTest),
+            7 => Ok(Self::FpgaTest),
+            _ => Err(InvalidDiscriminant {
+                field: "mode",
+                value,
+            }),
         }
     }
 }
 
+impl Mode {
+    /// Lenient conversion from a raw mode that maps every unknown value to `Mode::Default`.
+    ///
+    /// This replaces the `From<u8>` implementation of earlier versions (which cannot exist
+    /// alongside `TryFrom<u8>`), so `Mode::from(value)` becomes `Mode::from_raw_lenient(value)`.
+    ///
+    /// Note: Prefer `Mode::try_from` when a mismatch between firmware and software versions
+    /// should be detected
+    pub fn from_raw_lenient(value: u8) -> Self {
+        value.try_into().unwrap_or_default()
+    }
+}
+
 /// The Control Message is Sent from the Base Station to the Robots.
 ///
 /// The Packed Format of this message is as follows:
-/// +---------+---------+---------+---------+---------+---------+---------+---------+
-/// |    7    |    6    |    5    |    4    |    3    |    2    |    1    |    0    |
-/// +---------+---------+---------+---------+---------+---------+---------+---------+
-/// | team    | robot id                              | shoot_m | trigger_mode      |
-/// +---------+---------+---------+---------+---------+---------+---------+---------+
-/// | body_x (lsb)                                                                  |
-/// +---------+---------+---------+---------+---------+---------+---------+---------+
-/// | body_x (msb)                                                                  |
-/// +---------+---------+---------+---------+---------+---------+---------+---------+
-/// | body_y (lsb)                                                                  |
-/// +---------+---------+---------+---------+---------+---------+---------+---------+
-/// | body_y (msb)                                                                  |
-/// +---------+---------+---------+---------+---------+---------+---------+---------+
-/// | body_w (lsb)                                                                  |
-/// +---------+---------+---------+---------+---------+---------+---------+---------+
-/// | body_w (msb)                                                                  |
-/// +---------+---------+---------+---------+---------+---------+---------+---------+
-/// | dribbler_speed                                                                |
-/// +---------+---------+---------+---------+---------+---------+---------+---------+
-/// | kick_strength                                                                 |
-/// +---------+---------+---------+---------+---------+---------+---------+---------+
-/// | role              | mode                                                      |
-/// +---------+---------+---------+---------+---------+---------+---------+---------+
-///
-/// Size = 80 Bits = 10 Bytes
+#[doc = include_str!("generated/control_message_layout.txt")]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ControlMessage {
     /// Team of the Robot (0: Blue) (1: Yellow)
     pub team: Team,
@@ -139,14 +190,20 @@ pub struct ControlMessage {
     pub shoot_mode: ShootMode,
     /// Trigger Mode for the Robot (TODO: Finish Docs)
     pub trigger_mode: TriggerMode,
-    /// X Coordinate of the Robot's Body Frame (multiplied by VELOCITY_SCALE_FACTOR
+    /// X Coordinate of the Robot's Body Frame (multiplied by LINEAR_VELOCITY_SCALE_FACTOR
     /// and truncated)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_impls::linear_velocity"))]
     pub body_x: i16,
-    /// Y Coordinate of the Robot's Body Frame (multiplied by VELOCITY_SCALE_FACTOR
+    /// Y Coordinate of the Robot's Body Frame (multiplied by LINEAR_VELOCITY_SCALE_FACTOR
     /// and truncated)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_impls::linear_velocity"))]
     pub body_y: i16,
-    /// W Coordinate of the Robot's Body Frame (multiplied by VELOCITY_SCALE_FACTOR
-    /// and truncated))
+    /// W Coordinate of the Robot's Body Frame (multiplied by ANGULAR_VELOCITY_SCALE_FACTOR
+    /// and truncated)
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_impls::angular_velocity")
+    )]
     pub body_w: i16,
     /// Speed of the dribbler (TODO: Determine Units)
     pub dribbler_speed: i8,
@@ -156,82 +213,48 @@ pub struct ControlMessage {
     pub role: u8,
     /// The mode of the robot
     pub mode: Mode,
+    /// Rolling sequence number of this message.  The robot echoes it back in the
+    /// `RobotStatusMessage` it replies with.
+    pub sequence: u8,
 }
 
 impl ControlMessage {
     /// Get the velocity (x, y, w) from the control message in a vector
     pub fn get_velocity(&self) -> Vector3<f32> {
-        Vector3::new(
-            (self.body_x as f32) / VELOCITY_SCALE_FACTOR,
-            (self.body_y as f32) / VELOCITY_SCALE_FACTOR,
-            (self.body_w as f32) / VELOCITY_SCALE_FACTOR,
-        )
+        VelocityEncoding::CURRENT.decode(self.body_x, self.body_y, self.body_w)
     }
 }
 
-impl Packable for ControlMessage {
-    fn len() -> usize {
-        CONTROL_MESSAGE_SIZE
-    }
-
-    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
-        if buffer.len() < CONTROL_MESSAGE_SIZE {
-            return Err(PackingError::InvalidBufferSize);
-        }
-
-        buffer[0] = ((self.team as u8) & 0b1) << 7
-            | (self.robot_id & 0b1111) << 3
-            | ((self.shoot_mode as u8) & 0b1) << 2
-            | (self.trigger_mode as u8) & 0b11;
-        let bytes = self.body_x.to_le_bytes();
-        buffer[1] = bytes[0];
-        buffer[2] = bytes[1];
-        let bytes = self.body_y.to_le_bytes();
-        buffer[3] = bytes[0];
-        buffer[4] = bytes[1];
-        let bytes = self.body_w.to_le_bytes();
-        buffer[5] = bytes[0];
-        buffer[6] = bytes[1];
-        buffer[7] = self.dribbler_speed.to_le_bytes()[0];
-        buffer[8] = self.kick_strength;
-        buffer[9] = (self.role & 0b11) << 6 | (self.mode as u8);
-        Ok(())
-    }
-
-    fn unpack(data: &[u8]) -> Result<Self, PackingError> {
-        if data.len() < CONTROL_MESSAGE_SIZE {
-            return Err(PackingError::InvalidBufferSize);
-        }
-        
-        Ok(Self {
-            team: if data[0] & (0b1 << 7) == 0 {
-                Team::Blue
-            } else {
-                Team::Yellow
-            },
-            robot_id: (data[0] & (0b1111 << 3)) >> 3,
-            shoot_mode: if data[0] & (0b1 << 2) != 0 {
-                ShootMode::Chip
-            } else {
-                ShootMode::Kick
-            },
-            trigger_mode: match data[0] & 0b11 {
-                1 => TriggerMode::Immediate,
-                2 => TriggerMode::OnBreakBeam,
-                _ => TriggerMode::StandDown,
-            },
-            body_x: i16::from_le_bytes(data[1..=2].try_into().unwrap()),
-            body_y: i16::from_le_bytes(data[3..=4].try_into().unwrap()),
-            body_w: i16::from_le_bytes(data[5..=6].try_into().unwrap()),
-            dribbler_speed: i8::from_le_bytes([data[7]]),
-            kick_strength: data[8],
-            role: (data[9] & (0b11 << 6)) >> 6,
-            mode: (data[9] & 0b0011_1111).into(),
-        })
+/// Prints the velocities in m/s and rad/s rather than their packed values
+#[cfg(feature = "defmt")]
+impl defmt::Format for ControlMessage {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "ControlMessage {{ team: {}, robot_id: {=u8}, shoot_mode: {}, trigger_mode: {}, \
+             body: ({=f32}, {=f32}) m/s {=f32} rad/s, dribbler_speed: {=i8}, \
+             kick_strength: {=u8}, role: {=u8}, mode: {}, sequence: {=u8} }}",
+            self.team,
+            self.robot_id,
+            self.shoot_mode,
+            self.trigger_mode,
+            VelocityEncoding::CURRENT.linear.decode(self.body_x),
+            VelocityEncoding::CURRENT.linear.decode(self.body_y),
+            VelocityEncoding::CURRENT.angular.decode(self.body_w),
+            self.dribbler_speed,
+            self.kick_strength,
+            self.role,
+            self.mode,
+            self.sequence,
+        )
     }
 }
 
 /// Builder for a Control Message
+///
+/// Note: Since 0.7.0 `body_x`, `body_y` and `body_w` hold velocities (m/s and rad/s) rather than
+/// their packed values, so `try_build` can check them
+#[derive(Clone, Copy, Debug, Default)]
 pub struct ControlMessageBuilder {
     /// The message's team
     pub team: Option<Team>,
@@ -241,12 +264,12 @@ pub struct ControlMessageBuilder {
     pub shoot_mode: Option<ShootMode>,
     /// The message's trigger mode
     pub trigger_mode: Option<TriggerMode>,
-    /// The message's body velocity in the x direction
-    pub body_x: Option<i16>,
-    /// The message's body velocity in the y direction
-    pub body_y: Option<i16>,
-    /// The message's body velocity in the w direction
-    pub body_w: Option<i16>,
+    /// The message's body velocity in the x direction (m/s)
+    pub body_x: Option<f32>,
+    /// The message's body velocity in the y direction (m/s)
+    pub body_y: Option<f32>,
+    /// The message's body velocity in the w direction (rad/s)
+    pub body_w: Option<f32>,
     /// The speed of the dribbler
     pub dribbler_speed: Option<i8>,
     /// The strength of the kicker (used to charge the kicker)
@@ -255,6 +278,12 @@ pub struct ControlMessageBuilder {
     pub role: Option<u8>,
     /// The mode the robot is in
     pub mode: Option<Mode>,
+    /// The sequence number of the message
+    pub sequence: Option<u8>,
+    /// How the velocities are rounded to their fixed-point values
+    pub rounding: Option<Rounding>,
+    /// What `try_build` does with velocities that do not fit into their fields
+    pub overflow: Option<Overflow>,
 }
 
 impl ControlMessageBuilder {
@@ -272,6 +301,9 @@ impl ControlMessageBuilder {
             kick_strength: None,
             role: None,
             mode: None,
+            sequence: None,
+            rounding: None,
+            overflow: None,
         }
     }
 
@@ -301,19 +333,19 @@ impl ControlMessageBuilder {
 
     /// Assign the x-direction body velocity for the control message
     pub fn body_x(mut self, body_x: f32) -> Self {
-        self.body_x = Some((body_x * VELOCITY_SCALE_FACTOR) as i16);
+        self.body_x = Some(body_x);
         self
     }
 
     /// Assign the y-direction body velocity for the control message
     pub fn body_y(mut self, body_y: f32) -> Self {
-        self.body_y = Some((body_y * VELOCITY_SCALE_FACTOR) as i16);
+        self.body_y = Some(body_y);
         self
     }
     
     /// Assign the w-direction body velocity for the control message
     pub fn body_w(mut self, body_w: f32) -> Self {
-        self.body_w = Some((body_w * VELOCITY_SCALE_FACTOR) as i16);
+        self.body_w = Some(body_w);
         self
     }
 
@@ -341,60 +373,79 @@ impl ControlMessageBuilder {
         self
     }
 
+    /// Assign the sequence number for the control message
+    pub fn sequence(mut self, sequence: u8) -> Self {
+        self.sequence = Some(sequence);
+        self
+    }
+
+    /// Choose how the velocities are rounded to their fixed-point values (truncated by default)
+    pub fn rounding(mut self, rounding: Rounding) -> Self {
+        self.rounding = Some(rounding);
+        self
+    }
+
+    /// Choose what `try_build` does with velocities that do not fit into their fields (rejected
+    /// by default)
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = Some(overflow);
+        self
+    }
+
     /// Build the control message from the assigned fields.
+    ///
+    /// Note: velocities that do not fit into their fields are saturated and NaN becomes 0, while
+    /// a `robot_id` or `role` that is too large has its high bits masked off by `pack`.  Use
+    /// `try_build` to find out about them instead.
     pub fn build(self) -> ControlMessage {
-        let team = match self.team {
-            Some(team) => team,
-            None => Team::Blue,
-        };
+        let rounding = self.rounding.unwrap_or_default();
+        let encoding = VelocityEncoding::CURRENT;
 
-        let robot_id = match self.robot_id {
-            Some(robot_id) => robot_id,
-            None => 0,
-        };
+        let team = self.team.unwrap_or(Team::Blue);
 
-        let shoot_mode = match self.shoot_mode {
-            Some(shoot_mode) => shoot_mode.into(),
-            None => ShootMode::Kick,
-        };
+        let robot_id = self.robot_id.unwrap_or_default();
 
-        let trigger_mode = match self.trigger_mode {
-            Some(trigger_mode) => trigger_mode.into(),
-            None => TriggerMode::StandDown,
-        };
+        let shoot_mode = self.shoot_mode.unwrap_or(ShootMode::Kick);
 
-        let body_x = match self.body_x {
-            Some(body_x) => body_x,
-            None => 0,
-        };
+        let trigger_mode = self.trigger_mode.unwrap_or(TriggerMode::StandDown);
 
-        let body_y = match self.body_y {
-            Some(body_y) => body_y,
-            None => 0,
-        };
+        let body_x = encoding
+            .linear
+            .encode(
+                self.body_x.unwrap_or_default(),
+                rounding,
+                Overflow::Saturate,
+            )
+            .unwrap_or_default();
 
-        let body_w = match self.body_w {
-            Some(body_w) => body_w,
-            None => 0,
-        };
+        let body_y = encoding
+            .linear
+            .encode(
+                self.body_y.unwrap_or_default(),
+                rounding,
+                Overflow::Saturate,
+            )
+            .unwrap_or_default();
 
-        let dribbler_speed = match self.dribbler_speed {
-            Some(dribbler_speed) => dribbler_speed,
-            None => 0,
-        };
+        let body_w = encoding
+            .angular
+            .encode(
+                self.body_w.unwrap_or_default(),
+                rounding,
+                Overflow::Saturate,
+            )
+            .unwrap_or_default();
 
-        let kick_strength = match self.kick_strength {
-            Some(kick_strength) => kick_strength,
-            None => 0,
-        };
+        let dribbler_speed = self.dribbler_speed.unwrap_or_default();
 
-        let role = match self.role {
-            Some(role) => role,
-            None => 0,
-        };
+        let kick_strength = self.kick_strength.unwrap_or_default();
+
+        let role = self.role.unwrap_or_default();
 
         let mode = self.mode.unwrap_or_default();
 
+        let sequence = self.sequence.unwrap_or_default();
+
         ControlMessage {
             team,
             robot_id,
@@ -407,14 +458,188 @@ impl ControlMessageBuilder {
             kick_strength,
             role,
             mode,
+            sequence,
+        }
+    }
+
+    /// Build the control message from the assigned fields, checking that every field fits into
+    /// the packed message.
+    ///
+    /// Every field that does not fit is reported in the returned `ControlMessageBuildError`.
+    /// Velocities are rounded and, unless `Overflow::Saturate` was chosen, rejected if they are
+    /// out of range.  NaN velocities are always rejected.
+    pub fn try_build(self) -> Result<ControlMessage, ControlMessageBuildError> {
+        let rounding = self.rounding.unwrap_or_default();
+        let overflow = self.overflow.unwrap_or_default();
+        let encoding = VelocityEncoding::CURRENT;
+        let mut error = ControlMessageBuildError::default();
+
+        let robot_id = self.robot_id.unwrap_or_default();
+        if robot_id as usize >= ROBOTS_PER_TEAM {
+            error.robot_id = Some(FieldError::InvalidRobotId(robot_id));
+        }
+
+        let role = self.role.unwrap_or_default();
+        if role > MAX_ROLE {
+            error.role = Some(FieldError::TooLarge {
+                value: role,
+                max: MAX_ROLE,
+            });
+        }
+
+        let linear = |velocity: Option<f32>| {
+            encoding
+                .linear
+                .encode(velocity.unwrap_or_default(), rounding, overflow)
+        };
+        let body_x = linear(self.body_x).unwrap_or_else(|e| {
+            error.body_x = Some(e);
+            0
+        });
+        let body_y = linear(self.body_y).unwrap_or_else(|e| {
+            error.body_y = Some(e);
+            0
+        });
+        let body_w = encoding
+            .angular
+            .encode(self.body_w.unwrap_or_default(), rounding, overflow)
+            .unwrap_or_else(|e| {
+                error.body_w = Some(e);
+                0
+            });
+
+        if error != ControlMessageBuildError::default() {
+            return Err(error);
+        }
+
+        Ok(ControlMessage {
+            robot_id,
+            body_x,
+            body_y,
+            body_w,
+            role,
+            ..self.build()
+        })
+    }
+}
+
+/// How a velocity is rounded to its fixed-point value
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rounding {
+    /// Round towards zero, so the encoded velocity is never faster than the requested one
+    #[default]
+    Truncate,
+    /// Round to the nearest fixed-point value
+    Nearest,
+}
+
+/// What happens to a velocity that does not fit into its field
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// Report the velocity as a `FieldError::VelocityOutOfRange`
+    #[default]
+    Reject,
+    /// Clamp the velocity to the fastest one the field can hold
+    Saturate,
+}
+
+/// Why the value of a field does not fit into a packed ControlMessage
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldError {
+    /// The velocity is NaN
+    NotANumber,
+    /// The velocity is outside of the range the field can hold
+    VelocityOutOfRange {
+        /// The requested velocity (m/s or rad/s)
+        value: f32,
+        /// The slowest velocity the field can hold
+        min: f32,
+        /// The fastest velocity the field can hold
+        max: f32,
+    },
+    /// The robot id is larger than the number of robots on a team (it would fit into the bits of
+    /// the field, but robots ignore it)
+    InvalidRobotId(u8),
+    /// The value does not fit into the bits of the field
+    TooLarge {
+        /// The requested value
+        value: u8,
+        /// The largest value the field can hold
+        max: u8,
+    },
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotANumber => write!(f, "velocity is NaN"),
+            Self::VelocityOutOfRange { value, min, max } => {
+                write!(f, "velocity {} is out of range ({} to {})", value, min, max)
+            }
+            Self::InvalidRobotId(robot_id) => write!(
+                f,
+                "robot id {} is out of range (max {})",
+                robot_id,
+                ROBOTS_PER_TEAM - 1
+            ),
+            Self::TooLarge { value, max } => write!(f, "{} is out of range (max {})", value, max),
         }
     }
 }
 
+/// The fields of a ControlMessageBuilder that do not fit into a packed ControlMessage
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ControlMessageBuildError {
+    /// Why the robot id does not fit
+    pub robot_id: Option<FieldError>,
+    /// Why the x velocity does not fit
+    pub body_x: Option<FieldError>,
+    /// Why the y velocity does not fit
+    pub body_y: Option<FieldError>,
+    /// Why the w velocity does not fit
+    pub body_w: Option<FieldError>,
+    /// Why the role does not fit
+    pub role: Option<FieldError>,
+}
+
+impl ControlMessageBuildError {
+    /// The name and error of every field that does not fit
+    pub fn fields(&self) -> impl Iterator<Item = (&'static str, FieldError)> {
+        [
+            ("robot_id", self.robot_id),
+            ("body_x", self.body_x),
+            ("body_y", self.body_y),
+            ("body_w", self.body_w),
+            ("role", self.role),
+        ]
+        .into_iter()
+        .filter_map(|(field, error)| Some((field, error?)))
+    }
+}
+
+impl fmt::Display for ControlMessageBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid control message")?;
+        for (i, (field, error)) in self.fields().enumerate() {
+            let separator = if i == 0 { ": " } else { ", " };
+            write!(f, "{}`{}` {}", separator, field, error)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ControlMessageBuildError {}
+
 #[cfg(test)]
+#[allow(clippy::unusual_byte_groupings)]
 mod tests {
     use super::*;
 
+    use ncomm_utils::packing::Packable;
+
+    use crate::RtpError;
+
     /// Test that ControlMessageBuilder uses the correct default fields when
     /// they are not provided.
     #[test]
@@ -433,6 +658,7 @@ mod tests {
             kick_strength: 0,
             role: 0,
             mode: Mode::default(),
+            sequence: 0,
         };
 
         assert_eq!(expected, control_message);
@@ -447,12 +673,13 @@ mod tests {
             .robot_id(3)
             .shoot_mode(ShootMode::Chip)
             .trigger_mode(TriggerMode::OnBreakBeam)
-            .body_x(20.0)
-            .body_y(10.0)
+            .body_x(5.0)
+            .body_y(2.5)
             .body_w(45.0)
             .dribbler_speed(-5)
             .kick_strength(3)
             .role(1)
+            .sequence(200)
             .build();
 
         let expected = ControlMessage {
@@ -467,22 +694,80 @@ mod tests {
             kick_strength: 3,
             role: 1,
             mode: Mode::default(),
+            sequence: 200,
         };
 
         assert_eq!(expected, control_message);
     }
 
+    /// Test that try_build reports every field that does not fit into the packed message
+    #[test]
+    fn test_try_build_errors() {
+        let builder = ControlMessageBuilder::new()
+            .robot_id(3)
+            .body_x(2.0)
+            .body_y(-1.5)
+            .role(1);
+        assert_eq!(builder.try_build(), Ok(builder.build()));
+
+        let error = ControlMessageBuilder::new()
+            .robot_id(9)
+            .body_x(f32::NAN)
+            .body_y(1.0)
+            .body_w(45.0)
+            .role(4)
+            .try_build()
+            .unwrap_err();
+        assert_eq!(error.robot_id, Some(FieldError::InvalidRobotId(9)));
+        assert_eq!(error.body_x, Some(FieldError::NotANumber));
+        assert_eq!(error.body_y, None);
+        assert_eq!(
+            error.body_w,
+            Some(FieldError::VelocityOutOfRange {
+                value: 45.0,
+                min: -16.384,
+                max: 16.3835,
+            })
+        );
+        assert_eq!(error.role, Some(FieldError::TooLarge { value: 4, max: 3 }));
+        assert!(error
+            .fields()
+            .map(|(field, _)| field)
+            .eq(["robot_id", "body_x", "body_w", "role"]));
+    }
+
+    /// Test that the rounding and overflow policies are applied to the velocities
+    #[test]
+    fn test_velocity_policies() {
+        let builder = ControlMessageBuilder::new()
+            .body_x(0.0019)
+            .body_y(-0.0019)
+            .body_w(-45.0);
+        assert_eq!(builder.build().body_x, 7);
+        assert_eq!(builder.build().body_w, -32_768);
+
+        let message = builder
+            .rounding(Rounding::Nearest)
+            .overflow(Overflow::Saturate)
+            .try_build()
+            .unwrap();
+        assert_eq!((message.body_x, message.body_y), (8, -8));
+        assert_eq!(message.body_w, -32_768);
+    }
+
     /// The Control Message for
     /// ControlMessage {
     ///     team: Yellow (false),
     ///     robot_id: 3,
     ///     shoot_mode: Chip (1),
     ///     trigger_mode: OnBreakBeam (2),
-    ///     body_x: 20.0 (20_000),
-    ///     body_y: 10.0 (10_000),
+    ///     body_x: 5.0 (20_000),
+    ///     body_y: 2.5 (10_000),
     ///     body_w: 45.0 (32_767),
     ///     dribbler_speed: -5,
     ///     role: 1,
+    ///     mode: Mode::FpgaTest,
+    ///     sequence: 42,
     /// }
     ///
     /// is as follows:
@@ -498,15 +783,16 @@ mod tests {
     /// body_y (lsb)--------------------------------          |
     /// body_y (msb)-------------------------------------------
     ///
-    ///     11111111 | 01111111 | 11111011 | 00000011 | 01_000111
-    ///         ^          ^          ^          ^       ^    ^
-    ///         |          |          |          |       |    |
-    /// body_w (lsb)       |          |          |       |    |
-    /// body_w (msb)--------          |          |       |    |
-    /// dribbler_speed (2s Comp)-------          |       |    |
-    /// kick_strength-----------------------------       |    |
-    /// role----------------------------------------------    |
-    /// mode---------------------------------------------------
+    ///     11111111 | 01111111 | 11111011 | 00000011 | 01_000111 | 00101010
+    ///         ^          ^          ^          ^       ^    ^        ^
+    ///         |          |          |          |       |    |        |
+    /// body_w (lsb)       |          |          |       |    |        |
+    /// body_w (msb)--------          |          |       |    |        |
+    /// dribbler_speed (2s Comp)-------          |       |    |        |
+    /// kick_strength-----------------------------       |    |        |
+    /// role----------------------------------------------    |        |
+    /// mode---------------------------------------------------        |
+    /// sequence--------------------------------------------------------
     #[test]
     fn test_pack() {
         let control_message = ControlMessageBuilder::new()
@@ -514,13 +800,14 @@ mod tests {
             .robot_id(3)
             .shoot_mode(ShootMode::Chip)
             .trigger_mode(TriggerMode::OnBreakBeam)
-            .body_x(20.0)
-            .body_y(10.0)
+            .body_x(5.0)
+            .body_y(2.5)
             .body_w(45.0)
             .dribbler_speed(-5)
             .kick_strength(3)
             .role(1)
             .mode(Mode::FpgaTest)
+            .sequence(42)
             .build();
 
         let mut packed_data = [0u8; CONTROL_MESSAGE_SIZE];
@@ -537,6 +824,7 @@ mod tests {
         assert_eq!(packed_data[7], 0b11111011);
         assert_eq!(packed_data[8], 0b00000011);
         assert_eq!(packed_data[9], 0b01_000111);
+        assert_eq!(packed_data[10], 0b00101010);
     }
 
     /// The Control Message from:
@@ -552,15 +840,16 @@ mod tests {
     /// body_y (lsb)--------------------------------          |
     /// body_y (msb)-------------------------------------------
     ///
-    ///     11111111 | 01111111 | 11111011 | 00000011 | 01_000010
-    ///         ^          ^          ^          ^       ^    ^
-    ///         |          |          |          |       |    |
-    /// body_w (lsb)       |          |          |       |    |
-    /// body_w (msb)--------          |          |       |    |
-    /// dribbler_speed (2s Comp)-------          |       |    |
-    /// kick_strength-----------------------------       |    |
-    /// role----------------------------------------------    |
-    /// unused-------------------------------------------------
+    ///     11111111 | 01111111 | 11111011 | 00000011 | 01_000010 | 00101010
+    ///         ^          ^          ^          ^       ^    ^        ^
+    ///         |          |          |          |       |    |        |
+    /// body_w (lsb)       |          |          |       |    |        |
+    /// body_w (msb)--------          |          |       |    |        |
+    /// dribbler_speed (2s Comp)-------          |       |    |        |
+    /// kick_strength-----------------------------       |    |        |
+    /// role----------------------------------------------    |        |
+    /// mode---------------------------------------------------        |
+    /// sequence--------------------------------------------------------
     ///
     /// is as follows:
     /// ControlMessage {
@@ -568,12 +857,13 @@ mod tests {
     ///     robot_id: 3,
     ///     shoot_mode: Chip (1),
     ///     trigger_mode: OnBreakBeam (2),
-    ///     body_x: 20.0 (20_000),
-    ///     body_y: 10.0 (10_000),
-    ///     body_w: 45.0 (32_767),
+    ///     body_x: 5.0 (20_000),
+    ///     body_y: 2.5 (10_000),
+    ///     body_w: 16.3835 (32_767),
     ///     dribbler_speed: -5,
     ///     role: 1,
     ///     mode: Mode::ReceiveBenchmark,
+    ///     sequence: 42,
     /// }
     #[test]
     fn test_unpack() {
@@ -588,6 +878,7 @@ mod tests {
             0b11111011,
             0b00000011,
             0b01_000010,
+            0b00101010,
         ];
 
         let control_message = ControlMessage::unpack(&data).unwrap();
@@ -604,8 +895,123 @@ mod tests {
             kick_strength: 3,
             role: 1,
             mode: Mode::ReceiveBenchmark,
+            sequence: 42,
         };
 
         assert_eq!(expected, control_message);
     }
+
+    /// Test that strict unpacking rejects unknown trigger modes, modes and robot ids while
+    /// lenient unpacking coerces them
+    #[test]
+    fn test_unpack_strict() {
+        let mut data = [0u8; CONTROL_MESSAGE_SIZE];
+        data[0] = 0b1_0011_1_10;
+        data[9] = 0b01_000111;
+        assert_eq!(
+            ControlMessage::unpack_strict(&data).unwrap(),
+            ControlMessage::unpack(&data).unwrap()
+        );
+
+        data[0] = 0b1_0011_1_11;
+        assert_eq!(
+            ControlMessage::unpack_strict(&data),
+            Err(RtpError::InvalidDiscriminant {
+                field: "trigger_mode",
+                value: 3,
+            })
+        );
+        assert_eq!(
+            ControlMessage::unpack(&data).unwrap().trigger_mode,
+            TriggerMode::StandDown
+        );
+
+        data[0] = 0b1_0011_1_10;
+        data[9] = 0b01_001000;
+        assert_eq!(
+            ControlMessage::unpack_strict(&data),
+            Err(RtpError::InvalidDiscriminant {
+                field: "mode",
+                value: 8,
+            })
+        );
+        assert_eq!(ControlMessage::unpack(&data).unwrap().mode, Mode::Default);
+
+        data[9] = 0b01_000111;
+        data[0] = 0b1_0111_1_10;
+        assert_eq!(
+            ControlMessage::unpack_strict(&data),
+            Err(RtpError::InvalidRobotId(7))
+        );
+
+        assert_eq!(
+            ControlMessage::unpack_strict(&data[..CONTROL_MESSAGE_SIZE - 1]),
+            Err(RtpError::BufferTooShort {
+                expected: CONTROL_MESSAGE_SIZE,
+                actual: CONTROL_MESSAGE_SIZE - 1,
+            })
+        );
+    }
+
+    /// Test that a control message serializes with float velocities and deserializes back into
+    /// the same message
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_control_message_serde() {
+        let control_message = ControlMessageBuilder::new()
+            .team(Team::Yellow)
+            .robot_id(3)
+            .shoot_mode(ShootMode::Chip)
+            .trigger_mode(TriggerMode::OnBreakBeam)
+            .body_x(1.5)
+            .body_y(-0.25)
+            .body_w(0.123)
+            .mode(Mode::KickerTest)
+            .sequence(42)
+            .build();
+
+        let value = serde_json::to_value(control_message).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "team": "Yellow",
+                "robot_id": 3,
+                "shoot_mode": "Chip",
+                "trigger_mode": "OnBreakBeam",
+                "body_x": 1.5,
+                "body_y": -0.25,
+                "body_w": value["body_w"],
+                "dribbler_speed": 0,
+                "kick_strength": 0,
+                "role": 0,
+                "mode": "KickerTest",
+                "sequence": 42,
+            })
+        );
+        assert!((value["body_w"].as_f64().unwrap() - 0.123).abs() < 1e-6);
+        assert_eq!(
+            serde_json::from_value::<ControlMessage>(value).unwrap(),
+            control_message
+        );
+
+        // velocities are rounded to the nearest packed value and saturate
+        let value = serde_json::json!({
+            "team": "Blue",
+            "robot_id": 0,
+            "shoot_mode": "Kick",
+            "trigger_mode": "StandDown",
+            "body_x": 0.0001,
+            "body_y": -0.00015,
+            "body_w": 1000.0,
+            "dribbler_speed": 0,
+            "kick_strength": 0,
+            "role": 0,
+            "mode": "Default",
+            "sequence": 0,
+        });
+        let control_message: ControlMessage = serde_json::from_value(value).unwrap();
+        assert_eq!(control_message.body_x, 0);
+        assert_eq!(control_message.body_y, -1);
+        assert_eq!(control_message.body_w, i16::MAX);
+    }
 }
@@ -4,14 +4,17 @@
 //! back to the base station.
 //! 
 //! This message is sent when testing the FPGA.
+//!
+//! Note: `delta` is a `u16` so the message still fits into a single radio payload with its
+//! envelope header.
 //! 
 
-use ncomm_utils::packing::{Packable, PackingError};
-
-/// The size of a control update test message
-pub const CONTROL_TEST_MESSAGE_SIZE: usize = 32;
+use crate::Packable;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Packable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[packable(size = CONTROL_TEST_MESSAGE_SIZE)]
 /// A message sent back from the robot containing all relevant
 /// sensor measurements for making control decisions.
 pub struct ControlTestMessage {
@@ -23,56 +26,17 @@ pub struct ControlTestMessage {
     pub accel_y: f32,
     /// The encoder velocities obtained from the FPGA
     pub motor_encoders: [f32; 4],
-    /// The time from the last control test message to this message (us)
-    pub delta: u32,
-}
-
-impl Packable for ControlTestMessage {
-    fn len() -> usize {
-        CONTROL_TEST_MESSAGE_SIZE
-    }
-
-    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
-        if buffer.len() < CONTROL_TEST_MESSAGE_SIZE {
-            return Err(PackingError::InvalidBufferSize);
-        }
-
-        buffer[0..4].copy_from_slice(&self.gyro_z.to_le_bytes());
-        buffer[4..8].copy_from_slice(&self.accel_x.to_le_bytes());
-        buffer[8..12].copy_from_slice(&self.accel_y.to_le_bytes());
-        buffer[12..16].copy_from_slice(&self.motor_encoders[0].to_le_bytes());
-        buffer[16..20].copy_from_slice(&self.motor_encoders[1].to_le_bytes());
-        buffer[20..24].copy_from_slice(&self.motor_encoders[2].to_le_bytes());
-        buffer[24..28].copy_from_slice(&self.motor_encoders[3].to_le_bytes());
-        buffer[28..32].copy_from_slice(&self.delta.to_le_bytes());
-
-        Ok(())
-    }
-
-    fn unpack(data: &[u8]) -> Result<Self, PackingError> {
-        if data.len() < CONTROL_TEST_MESSAGE_SIZE {
-            return Err(PackingError::InvalidBufferSize);
-        }
-
-        Ok(Self {
-            gyro_z: f32::from_le_bytes(data[0..4].try_into().unwrap()),
-            accel_x: f32::from_le_bytes(data[4..8].try_into().unwrap()),
-            accel_y: f32::from_le_bytes(data[8..12].try_into().unwrap()),
-            motor_encoders: [
-                f32::from_le_bytes(data[12..16].try_into().unwrap()),
-                f32::from_le_bytes(data[16..20].try_into().unwrap()),
-                f32::from_le_bytes(data[20..24].try_into().unwrap()),
-                f32::from_le_bytes(data[24..28].try_into().unwrap()),
-            ],
-            delta: u32::from_le_bytes(data[28..32].try_into().unwrap()),
-        })
-    }
+    /// The time from the last control test message to this message (us, saturating at
+    /// `u16::MAX`)
+    pub delta: u16,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use ncomm_utils::packing::Packable;
+
     /// Test that Control Test Messages are packed and unpacked correctly
     #[test]
     fn test_control_test_message_pack_and_unpack() {
@@ -0,0 +1,431 @@
+//!
+//! The envelope wraps any rtp message with a one byte header describing the kind of message
+//! that follows and the protocol version it was packed with.  This allows a single radio stream
+//! to carry every message kind without the receiver having to remember which `Mode` it last
+//! requested.
+//!
+
+use ncomm_utils::packing::{Packable, PackingError};
+
+use crate::control_message::{ControlMessage, Mode, CONTROL_MESSAGE_SIZE};
+use crate::control_test_message::{ControlTestMessage, CONTROL_TEST_MESSAGE_SIZE};
+use crate::imu_test_message::{ImuTestMessage, IMU_MESSAGE_SIZE};
+use crate::kicker_program_message::{KickerProgramMessage, KICKER_PROGRAM_MESSAGE};
+use crate::kicker_testing::{KickerTestingMessage, KICKER_TESTING_SIZE};
+use crate::radio_benchmarks::{
+    RadioReceiveBenchmarkMessage, RadioSendBenchmarkMessage, RADIO_RECEIVE_BENCHMARK_SIZE,
+    RADIO_SEND_BENCHMARK_SIZE,
+};
+use crate::error::check_len;
+use crate::game_state::{GameStateMessage, GAME_STATE_MESSAGE_SIZE};
+use crate::halt_message::{HaltMessage, HALT_MESSAGE_SIZE};
+use crate::radio_link::MAX_PAYLOAD_SIZE;
+use crate::robot_status_message::{RobotStatusMessage, ROBOT_STATUS_SIZE};
+use crate::RtpError;
+
+/// The version of the protocol packed into every envelope header.
+///
+/// Note: This should be incremented whenever the packed layout of any message changes
+pub const PROTOCOL_VERSION: u8 = 1;
+
+/// The size of the envelope header in Bytes
+pub const HEADER_SIZE: usize = 1;
+
+/// The size of the largest possible envelope (header + largest message) in Bytes
+pub const MAX_ENVELOPE_SIZE: usize = HEADER_SIZE + MessageKind::max_payload_size();
+
+/// Fails to compile when an enveloped message does not fit into a single radio payload
+const _: () = assert!(
+    MAX_ENVELOPE_SIZE <= MAX_PAYLOAD_SIZE,
+    "every enveloped message must fit into a single radio payload"
+);
+
+/// The kind of message carried by an envelope
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MessageKind {
+    /// A `ControlMessage`
+    Control = 0,
+    /// A `RobotStatusMessage`
+    RobotStatus = 1,
+    /// An `ImuTestMessage`
+    ImuTest = 2,
+    /// A `KickerTestingMessage`
+    KickerTesting = 3,
+    /// A `KickerProgramMessage`
+    KickerProgram = 4,
+    /// A `RadioReceiveBenchmarkMessage`
+    RadioReceiveBenchmark = 5,
+    /// A `RadioSendBenchmarkMessage`
+    RadioSendBenchmark = 6,
+    /// A `ControlTestMessage`
+    ControlTest = 7,
+    /// A `HaltMessage`
+    Halt = 8,
+    /// A `GameStateMessage`
+    GameState = 9,
+}
+
+impl MessageKind {
+    /// Every kind of message
+    pub const ALL: [Self; 10] = [
+        Self::Control,
+        Self::RobotStatus,
+        Self::ImuTest,
+        Self::KickerTesting,
+        Self::KickerProgram,
+        Self::RadioReceiveBenchmark,
+        Self::RadioSendBenchmark,
+        Self::ControlTest,
+        Self::Halt,
+        Self::GameState,
+    ];
+
+    /// The size (in Bytes) of the message carried for this kind (excluding the header)
+    pub const fn payload_size(self) -> usize {
+        match self {
+            Self::Control => CONTROL_MESSAGE_SIZE,
+            Self::RobotStatus => ROBOT_STATUS_SIZE,
+            Self::ImuTest => IMU_MESSAGE_SIZE,
+            Self::KickerTesting => KICKER_TESTING_SIZE,
+            Self::KickerProgram => KICKER_PROGRAM_MESSAGE,
+            Self::RadioReceiveBenchmark => RADIO_RECEIVE_BENCHMARK_SIZE,
+            Self::RadioSendBenchmark => RADIO_SEND_BENCHMARK_SIZE,
+            Self::ControlTest => CONTROL_TEST_MESSAGE_SIZE,
+            Self::Halt => HALT_MESSAGE_SIZE,
+            Self::GameState => GAME_STATE_MESSAGE_SIZE,
+        }
+    }
+
+    /// The size (in Bytes) of the largest message of any kind (excluding the header)
+    pub const fn max_payload_size() -> usize {
+        let mut max = 0;
+        let mut index = 0;
+        while index < Self::ALL.len() {
+            if Self::ALL[index].payload_size() > max {
+                max = Self::ALL[index].payload_size();
+            }
+            index += 1;
+        }
+        max
+    }
+
+    /// The kind of message a robot replies with while it is in a given mode
+    pub fn reply_to(mode: Mode) -> Self {
+        match mode {
+            Mode::Default => Self::RobotStatus,
+            Mode::ImuTest => Self::ImuTest,
+            Mode::ReceiveBenchmark => Self::RadioReceiveBenchmark,
+            Mode::SendBenchmark => Self::RadioSendBenchmark,
+            Mode::ProgramKickOnBreakbeam | Mode::ProgramKicker => Self::KickerProgram,
+            Mode::KickerTest => Self::KickerTesting,
+            Mode::FpgaTest => Self::ControlTest,
+        }
+    }
+}
+
+impl TryFrom<u8> for MessageKind {
+    type Error = RtpError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Control),
+            1 => Ok(Self::RobotStatus),
+            2 => Ok(Self::ImuTest),
+            3 => Ok(Self::KickerTesting),
+            4 => Ok(Self::KickerProgram),
+            5 => Ok(Self::RadioReceiveBenchmark),
+            6 => Ok(Self::RadioSendBenchmark),
+            7 => Ok(Self::ControlTest),
+            8 => Ok(Self::Halt),
+            9 => Ok(Self::GameState),
+            _ => Err(RtpError::UnknownMessageKind(value)),
+        }
+    }
+}
+
+/// The header byte placed in front of every message in an envelope.
+///
+/// The packed format of the header is as follows:
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
+/// |    7    |    6    |    5    |    4    |    3    |    2    |    1    |    0    |
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
+/// | version                               | kind                                  |
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
+///
+/// Size = 1 Byte
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    /// The protocol version the message was packed with
+    pub version: u8,
+    /// The kind of message following the header
+    pub kind: MessageKind,
+}
+
+impl Header {
+    /// Create a header for a given kind of message using the current protocol version
+    pub fn new(kind: MessageKind) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            kind,
+        }
+    }
+
+    /// Pack the header into a single byte
+    pub fn to_byte(self) -> u8 {
+        (self.version & 0b1111) << 4 | (self.kind as u8) & 0b1111
+    }
+
+    /// Unpack a header from a single byte, rejecting unknown kinds and headers from other
+    /// protocol versions
+    pub fn from_byte(byte: u8) -> Result<Self, RtpError> {
+        let version = (byte & (0b1111 << 4)) >> 4;
+        if version != PROTOCOL_VERSION {
+            return Err(RtpError::VersionMismatch {
+                expected: PROTOCOL_VERSION,
+                found: version,
+            });
+        }
+
+        Ok(Self {
+            version,
+            kind: (byte & 0b1111).try_into()?,
+        })
+    }
+}
+
+/// Any rtp message that can be sent inside of an envelope
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RtpMessage {
+    /// A `ControlMessage`
+    Control(ControlMessage),
+    /// A `RobotStatusMessage`
+    RobotStatus(RobotStatusMessage),
+    /// An `ImuTestMessage`
+    ImuTest(ImuTestMessage),
+    /// A `KickerTestingMessage`
+    KickerTesting(KickerTestingMessage),
+    /// A `KickerProgramMessage`
+    KickerProgram(KickerProgramMessage),
+    /// A `RadioReceiveBenchmarkMessage`
+    RadioReceiveBenchmark(RadioReceiveBenchmarkMessage),
+    /// A `RadioSendBenchmarkMessage`
+    RadioSendBenchmark(RadioSendBenchmarkMessage),
+    /// A `ControlTestMessage`
+    ControlTest(ControlTestMessage),
+    /// A `HaltMessage`
+    Halt(HaltMessage),
+    /// A `GameStateMessage`
+    GameState(GameStateMessage),
+}
+
+impl RtpMessage {
+    /// The kind of message this is
+    pub fn kind(&self) -> MessageKind {
+        match self {
+            Self::Control(_) => MessageKind::Control,
+            Self::RobotStatus(_) => MessageKind::RobotStatus,
+            Self::ImuTest(_) => MessageKind::ImuTest,
+            Self::KickerTesting(_) => MessageKind::KickerTesting,
+            Self::KickerProgram(_) => MessageKind::KickerProgram,
+            Self::RadioReceiveBenchmark(_) => MessageKind::RadioReceiveBenchmark,
+            Self::RadioSendBenchmark(_) => MessageKind::RadioSendBenchmark,
+            Self::ControlTest(_) => MessageKind::ControlTest,
+            Self::Halt(_) => MessageKind::Halt,
+            Self::GameState(_) => MessageKind::GameState,
+        }
+    }
+
+    /// The number of Bytes this message occupies when packed (including the header)
+    pub fn packed_len(&self) -> usize {
+        HEADER_SIZE + self.kind().payload_size()
+    }
+
+    /// Pack the message (with its header) into a buffer, returning the number of
+    /// Bytes written
+    pub fn encode(self, buffer: &mut [u8]) -> Result<usize, RtpError> {
+        let packed_len = self.packed_len();
+        check_len(buffer, packed_len)?;
+
+        buffer[0] = Header::new(self.kind()).to_byte();
+        self.encode_payload(&mut buffer[HEADER_SIZE..])?;
+
+        Ok(packed_len)
+    }
+
+    /// Pack the message without an envelope header into a buffer, returning the number of
+    /// Bytes written
+    pub fn encode_payload(self, buffer: &mut [u8]) -> Result<usize, RtpError> {
+        let payload_size = self.kind().payload_size();
+        check_len(buffer, payload_size)?;
+
+        let payload = &mut buffer[..payload_size];
+        match self {
+            Self::Control(message) => message.pack(payload)?,
+            Self::RobotStatus(message) => message.pack(payload)?,
+            Self::ImuTest(message) => message.pack(payload)?,
+            Self::KickerTesting(message) => message.pack(payload)?,
+            Self::KickerProgram(message) => message.pack(payload)?,
+            Self::RadioReceiveBenchmark(message) => message.pack(payload)?,
+            Self::RadioSendBenchmark(message) => message.pack(payload)?,
+            Self::ControlTest(message) => message.pack(payload)?,
+            Self::Halt(message) => message.pack(payload)?,
+            Self::GameState(message) => message.pack(payload)?,
+        }
+
+        Ok(payload_size)
+    }
+
+    /// Unpack a message (with its header) from a slice of Bytes
+    pub fn decode(data: &[u8]) -> Result<Self, RtpError> {
+        check_len(data, HEADER_SIZE)?;
+        let header = Header::from_byte(data[0])?;
+        check_len(data, HEADER_SIZE + header.kind.payload_size())?;
+
+        Self::decode_payload(header.kind, &data[HEADER_SIZE..])
+    }
+
+    /// Unpack a message of a known kind that was sent without an envelope header
+    pub fn decode_payload(kind: MessageKind, payload: &[u8]) -> Result<Self, RtpError> {
+        check_len(payload, kind.payload_size())?;
+        Ok(match kind {
+            MessageKind::Control => Self::Control(ControlMessage::unpack(payload)?),
+            MessageKind::RobotStatus => Self::RobotStatus(RobotStatusMessage::unpack(payload)?),
+            MessageKind::ImuTest => Self::ImuTest(ImuTestMessage::unpack(payload)?),
+            MessageKind::KickerTesting => {
+                Self::KickerTesting(KickerTestingMessage::unpack(payload)?)
+            }
+            MessageKind::KickerProgram => {
+                Self::KickerProgram(KickerProgramMessage::unpack(payload)?)
+            }
+            MessageKind::RadioReceiveBenchmark => {
+                Self::RadioReceiveBenchmark(RadioReceiveBenchmarkMessage::unpack(payload)?)
+            }
+            MessageKind::RadioSendBenchmark => {
+                Self::RadioSendBenchmark(RadioSendBenchmarkMessage::unpack(payload)?)
+            }
+            MessageKind::ControlTest => Self::ControlTest(ControlTestMessage::unpack(payload)?),
+            MessageKind::Halt => Self::Halt(HaltMessage::unpack(payload)?),
+            MessageKind::GameState => Self::GameState(GameStateMessage::unpack(payload)?),
+        })
+    }
+}
+
+impl Packable for RtpMessage {
+    fn len() -> usize {
+        MAX_ENVELOPE_SIZE
+    }
+
+    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
+        self.encode(buffer)?;
+        Ok(())
+    }
+
+    fn unpack(data: &[u8]) -> Result<Self, PackingError> {
+        Ok(Self::decode(data)?)
+    }
+}
+
+macro_rules! impl_from_message {
+    ($message: ident, $variant: ident) => {
+        impl From<$message> for RtpMessage {
+            fn from(value: $message) -> Self {
+                Self::$variant(value)
+            }
+        }
+    };
+}
+
+impl_from_message!(ControlMessage, Control);
+impl_from_message!(RobotStatusMessage, RobotStatus);
+impl_from_message!(ImuTestMessage, ImuTest);
+impl_from_message!(KickerTestingMessage, KickerTesting);
+impl_from_message!(KickerProgramMessage, KickerProgram);
+impl_from_message!(RadioReceiveBenchmarkMessage, RadioReceiveBenchmark);
+impl_from_message!(RadioSendBenchmarkMessage, RadioSendBenchmark);
+impl_from_message!(ControlTestMessage, ControlTest);
+impl_from_message!(HaltMessage, Halt);
+impl_from_message!(GameStateMessage, GameState);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{ControlMessageBuilder, RobotStatusMessageBuilder, Team};
+
+    /// Test that every kind of message survives being packed into and unpacked from an envelope
+    #[test]
+    fn test_envelope_pack_and_unpack() {
+        let messages: [RtpMessage; 5] = [
+            ControlMessageBuilder::new()
+                .team(Team::Yellow)
+                .robot_id(3)
+                .body_x(1.5)
+                .build()
+                .into(),
+            RobotStatusMessageBuilder::new()
+                .robot_id(2)
+                .battery_voltage(150)
+                .build()
+                .into(),
+            KickerProgramMessage {
+                kick_on_breakbeam: true,
+                finished: false,
+                page: 12,
+            }
+            .into(),
+            ControlTestMessage {
+                gyro_z: 1.0,
+                accel_x: 2.0,
+                accel_y: 3.0,
+                motor_encoders: [4.0, 5.0, 6.0, 7.0],
+                delta: 8,
+            }
+            .into(),
+            HaltMessage::halt(Team::Blue, 9).into(),
+        ];
+
+        for message in messages {
+            let mut buffer = [0u8; MAX_ENVELOPE_SIZE];
+            let written = message.encode(&mut buffer).unwrap();
+            assert_eq!(written, message.packed_len());
+            assert_eq!(buffer[0] & 0b1111, message.kind() as u8);
+            assert_eq!(buffer[0] >> 4, PROTOCOL_VERSION);
+
+            let unpacked = RtpMessage::decode(&buffer[..written]).unwrap();
+            assert_eq!(message, unpacked);
+        }
+    }
+
+    /// Test that unknown kinds, other protocol versions and short buffers are rejected
+    #[test]
+    fn test_envelope_rejects_invalid_headers() {
+        let mut buffer = [0u8; MAX_ENVELOPE_SIZE];
+        buffer[0] = PROTOCOL_VERSION << 4 | 0b1111;
+        assert_eq!(
+            RtpMessage::decode(&buffer),
+            Err(RtpError::UnknownMessageKind(0b1111))
+        );
+
+        buffer[0] = (PROTOCOL_VERSION + 1) << 4 | MessageKind::Control as u8;
+        assert_eq!(
+            RtpMessage::decode(&buffer),
+            Err(RtpError::VersionMismatch {
+                expected: PROTOCOL_VERSION,
+                found: PROTOCOL_VERSION + 1,
+            })
+        );
+
+        buffer[0] = Header::new(MessageKind::Control).to_byte();
+        assert_eq!(
+            RtpMessage::decode(&buffer[..CONTROL_MESSAGE_SIZE]),
+            Err(RtpError::BufferTooShort {
+                expected: HEADER_SIZE + CONTROL_MESSAGE_SIZE,
+                actual: CONTROL_MESSAGE_SIZE,
+            })
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//!
+//! Errors produced while packing and unpacking rtp messages.
+//!
+
+use core::fmt;
+
+use ncomm_utils::packing::PackingError;
+
+/// A raw value that does not correspond to any variant of the enum stored in a field
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidDiscriminant {
+    /// The name of the field the value was decoded for
+    pub field: &'static str,
+    /// The raw value that was found
+    pub value: u8,
+}
+
+/// An error from packing or unpacking an rtp message.
+///
+/// Note: `RtpError` converts to and from `PackingError` so it can be used with `?` inside of
+/// `Packable` implementations.  Every `RtpError` becomes `PackingError::InvalidBufferSize`
+/// because that is the only error `Packable` can report.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RtpError {
+    /// The buffer is too short for the message being packed or unpacked
+    BufferTooShort {
+        /// The number of Bytes needed
+        expected: usize,
+        /// The number of Bytes available
+        actual: usize,
+    },
+    /// A field contains a value that does not correspond to any variant of its enum
+    InvalidDiscriminant {
+        /// The name of the field
+        field: &'static str,
+        /// The raw value that was found
+        value: u8,
+    },
+    /// The robot id is larger than the number of robots on a team
+    InvalidRobotId(u8),
+    /// The checksum trailer does not match the checksum of the received message
+    ChecksumMismatch {
+        /// The checksum computed over the received message
+        expected: u16,
+        /// The checksum found in the trailer
+        found: u16,
+    },
+    /// The message was packed with a different protocol version
+    VersionMismatch {
+        /// The protocol version of this library
+        expected: u8,
+        /// The protocol version found in the message
+        found: u8,
+    },
+    /// The envelope header names a message kind this version of rtp does not know about
+    UnknownMessageKind(u8),
+    /// A broadcast frame holds a different number of commands than expected
+    SlotCountMismatch {
+        /// The number of commands expected
+        expected: usize,
+        /// The number of commands in the frame header
+        found: usize,
+    },
+    /// A `Packable` implementation failed without further detail
+    Packing(PackingError),
+}
+
+impl fmt::Display for RtpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BufferTooShort { expected, actual } => write!(
+                f,
+                "buffer too short: expected at least {} bytes but found {}",
+                expected, actual
+            ),
+            Self::InvalidDiscriminant { field, value } => {
+                write!(f, "invalid value {} for field `{}`", value, field)
+            }
+            Self::InvalidRobotId(robot_id) => write!(
+                f,
+                "robot id {} is out of range (max {})",
+                robot_id,
+                crate::ROBOTS_PER_TEAM - 1
+            ),
+            Self::ChecksumMismatch { expected, found } => write!(
+                f,
+                "checksum mismatch: computed {:#06x} but trailer contains {:#06x}",
+                expected, found
+            ),
+            Self::VersionMismatch { expected, found } => write!(
+                f,
+                "protocol version mismatch: expected {} but found {}",
+                expected, found
+            ),
+            Self::UnknownMessageKind(kind) => write!(f, "unknown message kind {}", kind),
+            Self::SlotCountMismatch { expected, found } => write!(
+                f,
+                "broadcast frame holds {} commands but {} were expected",
+                found, expected
+            ),
+            Self::Packing(PackingError::InvalidBufferSize) => write!(f, "invalid buffer size"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RtpError {}
+
+impl From<InvalidDiscriminant> for RtpError {
+    fn from(value: InvalidDiscriminant) -> Self {
+        Self::InvalidDiscriminant {
+            field: value.field,
+            value: value.value,
+        }
+    }
+}
+
+impl From<PackingError> for RtpError {
+    fn from(value: PackingError) -> Self {
+        Self::Packing(value)
+    }
+}
+
+impl From<RtpError> for PackingError {
+    fn from(value: RtpError) -> Self {
+        match value {
+            RtpError::Packing(error) => error,
+            _ => PackingError::InvalidBufferSize,
+        }
+    }
+}
+
+/// Check that a buffer holds at least `expected` Bytes
+pub(crate) fn check_len(buffer: &[u8], expected: usize) -> Result<(), RtpError> {
+    if buffer.len() < expected {
+        return Err(RtpError::BufferTooShort {
+            expected,
+            actual: buffer.len(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+
+    use std::string::ToString;
+
+    /// Test that errors are formatted with the details of what went wrong
+    #[test]
+    fn test_rtp_error_display() {
+        assert_eq!(
+            RtpError::BufferTooShort {
+                expected: 10,
+                actual: 4
+            }
+            .to_string(),
+            "buffer too short: expected at least 10 bytes but found 4"
+        );
+        assert_eq!(
+            RtpError::from(InvalidDiscriminant {
+                field: "mode",
+                value: 12
+            })
+            .to_string(),
+            "invalid value 12 for field `mode`"
+        );
+        assert_eq!(
+            RtpError::ChecksumMismatch {
+                expected: 0x29B1,
+                found: 0x0001
+            }
+            .to_string(),
+            "checksum mismatch: computed 0x29b1 but trailer contains 0x0001"
+        );
+    }
+
+    /// Test that errors round trip through `PackingError`
+    #[test]
+    fn test_packing_error_conversion() {
+        let error: RtpError = PackingError::InvalidBufferSize.into();
+        assert_eq!(error, RtpError::Packing(PackingError::InvalidBufferSize));
+        assert_eq!(
+            PackingError::from(RtpError::InvalidRobotId(9)),
+            PackingError::InvalidBufferSize
+        );
+    }
+}
@@ -0,0 +1,123 @@
+//!
+//! C ABI for the rtp packers so robocup-software (C++) can share a single wire implementation
+//! with the firmware instead of relying on compiler specific bitfield layouts.
+//!
+//! Every message has a `#[repr(C)]` mirror (`Rtp<Message>`) along with an
+//! `rtp_<message>_pack` and `rtp_<message>_unpack` function.  Each function returns `RTP_OK`
+//! or one of the negative `RTP_ERROR_*` codes.  Enums are passed through the mirrors as their
+//! raw value; packing a mirror whose enum fields are out of range fails with
+//! `RTP_ERROR_INVALID_DISCRIMINANT` while unpacking is lenient (like `Packable::unpack`).
+//!
+//! The mirrors, functions, and the matching header (`include/rc-fshare/rtp_ffi.h`) are generated
+//! from `schema/messages.toml`.  The `robojackets-robocup-rtp-ffi` crate builds them into a static
+//! library.
+//!
+
+use ncomm_utils::packing::PackingError;
+
+use crate::RtpError;
+
+// C ABI generated from schema/messages.toml by rtp-codegen
+#[rustfmt::skip]
+#[path = "generated/ffi.rs"]
+mod generated;
+pub use generated::*;
+
+/// Convert the result of packing or unpacking into a status code
+fn status(result: Result<(), RtpError>) -> i32 {
+    match result {
+        Ok(()) => RTP_OK,
+        Err(RtpError::BufferTooShort { .. })
+        | Err(RtpError::Packing(PackingError::InvalidBufferSize)) => RTP_ERROR_BUFFER_TOO_SHORT,
+        Err(RtpError::InvalidDiscriminant { .. }) => RTP_ERROR_INVALID_DISCRIMINANT,
+        Err(_) => RTP_ERROR_OTHER,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::ptr;
+
+    use ncomm_utils::packing::Packable;
+
+    use crate::control_message::{ControlMessageBuilder, Mode, CONTROL_MESSAGE_SIZE};
+    use crate::control_test_message::{ControlTestMessage, CONTROL_TEST_MESSAGE_SIZE};
+    use crate::Team;
+
+    /// Test that messages packed through the C ABI match the Rust packers and unpack back into
+    /// the same mirror
+    #[test]
+    fn test_ffi_pack_and_unpack() {
+        let message = ControlMessageBuilder::new()
+            .team(Team::Yellow)
+            .robot_id(3)
+            .body_x(1.5)
+            .mode(Mode::KickerTest)
+            .build();
+        let mirror = RtpControlMessage::from(message);
+
+        let mut expected = [0u8; CONTROL_MESSAGE_SIZE];
+        message.pack(&mut expected).unwrap();
+        let mut buffer = [0u8; CONTROL_MESSAGE_SIZE];
+        let result =
+            unsafe { rtp_control_message_pack(&mirror, buffer.as_mut_ptr(), buffer.len()) };
+        assert_eq!(result, RTP_OK);
+        assert_eq!(buffer, expected);
+
+        let mut unpacked = RtpControlMessage::default();
+        let result =
+            unsafe { rtp_control_message_unpack(buffer.as_ptr(), buffer.len(), &mut unpacked) };
+        assert_eq!(result, RTP_OK);
+        assert_eq!(unpacked, mirror);
+
+        let message = ControlTestMessage {
+            gyro_z: 1.0,
+            accel_x: -2.0,
+            accel_y: 0.5,
+            motor_encoders: [1.0, 2.0, 3.0, 4.0],
+            delta: 1234,
+        };
+        let mirror = RtpControlTestMessage::from(message);
+        let mut buffer = [0u8; CONTROL_TEST_MESSAGE_SIZE];
+        let result =
+            unsafe { rtp_control_test_message_pack(&mirror, buffer.as_mut_ptr(), buffer.len()) };
+        assert_eq!(result, RTP_OK);
+        assert_eq!(ControlTestMessage::unpack(&buffer).unwrap(), message);
+    }
+
+    /// Test that invalid arguments are reported with the matching status code
+    #[test]
+    fn test_ffi_errors() {
+        let mirror = RtpControlMessage::from(ControlMessageBuilder::new().build());
+        let mut buffer = [0u8; CONTROL_MESSAGE_SIZE];
+
+        unsafe {
+            assert_eq!(
+                rtp_control_message_pack(ptr::null(), buffer.as_mut_ptr(), buffer.len()),
+                RTP_ERROR_NULL_POINTER
+            );
+            assert_eq!(
+                rtp_control_message_unpack(buffer.as_ptr(), buffer.len(), ptr::null_mut()),
+                RTP_ERROR_NULL_POINTER
+            );
+            assert_eq!(
+                rtp_control_message_pack(&mirror, buffer.as_mut_ptr(), buffer.len() - 1),
+                RTP_ERROR_BUFFER_TOO_SHORT
+            );
+
+            let mut unpacked = RtpControlMessage::default();
+            assert_eq!(
+                rtp_control_message_unpack(buffer.as_ptr(), buffer.len() - 1, &mut unpacked),
+                RTP_ERROR_BUFFER_TOO_SHORT
+            );
+
+            let invalid = RtpControlMessage { mode: 63, ..mirror };
+            assert_eq!(
+                rtp_control_message_pack(&invalid, buffer.as_mut_ptr(), buffer.len()),
+                RTP_ERROR_INVALID_DISCRIMINANT
+            );
+        }
+    }
+}
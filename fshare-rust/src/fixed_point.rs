@@ -0,0 +1,152 @@
+//!
+//! The fixed-point encoding of the velocities in a `ControlMessage`.
+//!
+//! Velocities are sent as an `i16` holding the velocity multiplied by a scale factor.  The linear
+//! (x, y) and angular (w) velocities have a scale factor each, so range the robots can never use
+//! is traded for precision instead.
+//!
+
+use nalgebra::{ComplexField, Vector3};
+
+use crate::control_message::{
+    FieldError, Overflow, Rounding, ANGULAR_VELOCITY_SCALE_FACTOR, LINEAR_VELOCITY_SCALE_FACTOR,
+};
+
+/// A signed 16 bit fixed-point number holding a value multiplied by a scale factor
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FixedPoint {
+    scale: f32,
+}
+
+impl FixedPoint {
+    /// Create a fixed-point encoding with a given scale factor
+    pub const fn new(scale: f32) -> Self {
+        Self { scale }
+    }
+
+    /// The factor values are multiplied by when they are encoded
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// The difference between two neighbouring encoded values
+    pub fn resolution(&self) -> f32 {
+        1.0 / self.scale
+    }
+
+    /// The smallest value that can be encoded
+    pub fn min(&self) -> f32 {
+        i16::MIN as f32 / self.scale
+    }
+
+    /// The largest value that can be encoded
+    pub fn max(&self) -> f32 {
+        i16::MAX as f32 / self.scale
+    }
+
+    /// Decode a value
+    pub fn decode(&self, raw: i16) -> f32 {
+        raw as f32 / self.scale
+    }
+
+    /// Encode a value.
+    ///
+    /// NaN is always rejected, values out of range are rejected or saturated depending on
+    /// `overflow`
+    pub fn encode(
+        &self,
+        value: f32,
+        rounding: Rounding,
+        overflow: Overflow,
+    ) -> Result<i16, FieldError> {
+        if value.is_nan() {
+            return Err(FieldError::NotANumber);
+        }
+
+        let scaled = match rounding {
+            Rounding::Truncate => ComplexField::trunc(value * self.scale),
+            Rounding::Nearest => ComplexField::round(value * self.scale),
+        };
+        if overflow == Overflow::Reject && !(i16::MIN as f32..=i16::MAX as f32).contains(&scaled) {
+            return Err(FieldError::VelocityOutOfRange {
+                value,
+                min: self.min(),
+                max: self.max(),
+            });
+        }
+        // `as` saturates
+        Ok(scaled as i16)
+    }
+}
+
+/// The fixed-point encodings of the velocities in a `ControlMessage`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VelocityEncoding {
+    /// The encoding of body_x and body_y (m/s)
+    pub linear: FixedPoint,
+    /// The encoding of body_w (rad/s)
+    pub angular: FixedPoint,
+}
+
+impl VelocityEncoding {
+    /// 0.25 mm/s (±8.19 m/s) and 0.5 mrad/s (±16.38 rad/s)
+    pub const CURRENT: Self = Self {
+        linear: FixedPoint::new(LINEAR_VELOCITY_SCALE_FACTOR),
+        angular: FixedPoint::new(ANGULAR_VELOCITY_SCALE_FACTOR),
+    };
+
+    /// Decode the velocity (x, y, w) of the packed body_x, body_y and body_w
+    pub fn decode(&self, body_x: i16, body_y: i16, body_w: i16) -> Vector3<f32> {
+        Vector3::new(
+            self.linear.decode(body_x),
+            self.linear.decode(body_y),
+            self.angular.decode(body_w),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that the linear and angular velocities trade range for precision
+    #[test]
+    fn test_velocity_encoding() {
+        assert_eq!(
+            VelocityEncoding::CURRENT.decode(6000, -1000, 5000),
+            Vector3::new(1.5, -0.25, 2.5)
+        );
+        assert_eq!(VelocityEncoding::CURRENT.linear.resolution(), 0.00025);
+        assert_eq!(VelocityEncoding::CURRENT.angular.max(), 16.3835);
+    }
+
+    /// Test that rounding and overflow are applied when encoding
+    #[test]
+    fn test_encode() {
+        let linear = VelocityEncoding::CURRENT.linear;
+        assert_eq!(
+            linear.encode(0.0019, Rounding::Truncate, Overflow::Reject),
+            Ok(7)
+        );
+        assert_eq!(
+            linear.encode(-0.0019, Rounding::Nearest, Overflow::Reject),
+            Ok(-8)
+        );
+        assert_eq!(
+            linear.encode(9.0, Rounding::Truncate, Overflow::Reject),
+            Err(FieldError::VelocityOutOfRange {
+                value: 9.0,
+                min: -8.192,
+                max: 8.19175,
+            })
+        );
+        assert_eq!(
+            linear.encode(9.0, Rounding::Truncate, Overflow::Saturate),
+            Ok(i16::MAX)
+        );
+        assert_eq!(
+            linear.encode(f32::NAN, Rounding::Truncate, Overflow::Saturate),
+            Err(FieldError::NotANumber)
+        );
+    }
+}
@@ -0,0 +1,296 @@
+//!
+//! The state of the game called by the referee and the limits it places on the robots.
+//!
+//! The SSL rules stop every robot during `Halt`, cap the speed of the robots and forbid kicking
+//! during `Stop` and `BallPlacement`.  The base station tells the robots of a team about the
+//! current state with a `GameStateMessage` (sent in an envelope to the team's
+//! `radio_addresses::halt_address`, like `HaltMessage`s), and a `GameStateLimiter` applies the
+//! `GameStateLimits` of the state to every `ControlMessage`.  The limiter does not depend on the
+//! radio, so the base station can apply it before sending commands and the robot can apply it
+//! again to the commands it executes.
+//!
+//! Like halt messages, game state messages are repeated with the same sequence number and the
+//! limiter ignores those that are not newer than the last one it accepted, so a late `Running`
+//! cannot lift the limits of a `Stop` that was called after it.  Once it has not accepted a game
+//! state message for `DEFAULT_RESYNC_TIMEOUT` it accepts any sequence number again, so the
+//! robots follow a restarted base station (counting from 0 again) after at most that long.
+//!
+//! The packing code and layout table are generated from `schema/messages.toml`, edit the schema
+//! and run `cargo run -p rtp-codegen` to change the layout.
+//!
+
+use nalgebra::Vector2;
+
+use crate::control_message::TriggerMode;
+use crate::fixed_point::VelocityEncoding;
+use crate::sequence::{SequenceLatch, DEFAULT_RESYNC_TIMEOUT};
+use crate::{ControlMessage, InvalidDiscriminant, Team};
+
+/// The size of a GameStateMessage in Bytes as a constant.
+/// Note: This is tested in the tests so it can be trusted
+pub const GAME_STATE_MESSAGE_SIZE: usize = 2;
+
+/// The state of the game called by the referee
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum GameState {
+    /// Every robot must stop immediately
+    Halt = 0,
+    /// The robots may move slowly but must not kick
+    Stop = 1,
+    /// The game is running normally
+    #[default]
+    Running = 2,
+    /// The ball is being placed, the robots may move slowly but must not kick
+    BallPlacement = 3,
+}
+
+impl TryFrom<u8> for GameState {
+    type Error = InvalidDiscriminant;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Halt),
+            1 => Ok(Self::Stop),
+            2 => Ok(Self::Running),
+            3 => Ok(Self::BallPlacement),
+            _ => Err(InvalidDiscriminant {
+                field: "state",
+                value,
+            }),
+        }
+    }
+}
+
+impl From<GameState> for u8 {
+    fn from(value: GameState) -> Self {
+        value as u8
+    }
+}
+
+/// Tells every robot of a team the state of the game.
+///
+/// The GameStateMessage has the following format:
+#[doc = include_str!("generated/game_state_layout.txt")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GameStateMessage {
+    /// Team of the robots (0: Blue) (1: Yellow)
+    pub team: Team,
+    /// The state of the game
+    pub state: GameState,
+    /// Rolling sequence number of the game state message
+    pub sequence: u8,
+}
+
+impl GameStateMessage {
+    /// Create a message telling every robot of a team the state of the game
+    pub fn new(team: Team, state: GameState, sequence: u8) -> Self {
+        Self {
+            team,
+            state,
+            sequence,
+        }
+    }
+}
+
+/// The limits placed on the robots by each game state
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GameStateLimits {
+    /// The fastest a robot may drive during `GameState::Stop` (m/s)
+    pub stop_speed: f32,
+    /// The fastest a robot may drive during `GameState::BallPlacement` (m/s)
+    pub ball_placement_speed: f32,
+}
+
+impl Default for GameStateLimits {
+    /// The 1.5 m/s speed limit of the SSL rules
+    fn default() -> Self {
+        Self {
+            stop_speed: 1.5,
+            ball_placement_speed: 1.5,
+        }
+    }
+}
+
+impl GameStateLimits {
+    /// The fastest a robot may drive in a given state (m/s), or `None` if it is not limited
+    pub fn max_speed(&self, state: GameState) -> Option<f32> {
+        match state {
+            GameState::Halt => Some(0.0),
+            GameState::Stop => Some(self.stop_speed),
+            GameState::Running => None,
+            GameState::BallPlacement => Some(self.ball_placement_speed),
+        }
+    }
+
+    /// Limit a command to what is allowed in a given state.
+    ///
+    /// The magnitude of (body_x, body_y) is clamped to `max_speed` keeping its direction and the
+    /// kicker stands down in every state but `Running`.  During `Halt` the robot does not rotate
+    /// and the dribbler is stopped as well
+    pub fn apply(&self, state: GameState, mut command: ControlMessage) -> ControlMessage {
+        if state == GameState::Running {
+            return command;
+        }
+
+        if let Some(max_speed) = self.max_speed(state) {
+            let velocity = Vector2::new(command.body_x as f32, command.body_y as f32);
+            let max_speed = max_speed.max(0.0) * VelocityEncoding::CURRENT.linear.scale();
+            let speed = velocity.norm();
+            if speed > max_speed {
+                // truncating rounds towards zero so the limit is never exceeded
+                let scale = max_speed / speed;
+                command.body_x = (command.body_x as f32 * scale) as i16;
+                command.body_y = (command.body_y as f32 * scale) as i16;
+            }
+        }
+        command.trigger_mode = TriggerMode::StandDown;
+        command.kick_strength = 0;
+
+        if state == GameState::Halt {
+            command.body_w = 0;
+            command.dribbler_speed = 0;
+        }
+        command
+    }
+}
+
+/// Tracks the game state of a team and limits its commands accordingly
+#[derive(Clone, Copy, Debug)]
+pub struct GameStateLimiter {
+    team: Team,
+    limits: GameStateLimits,
+    state: GameState,
+    sequence: SequenceLatch,
+}
+
+impl GameStateLimiter {
+    /// Create a limiter for a team.
+    ///
+    /// Note: the game is assumed to be `Running` until told otherwise so robots keep working
+    /// with base stations that never send a `GameStateMessage`
+    pub fn new(team: Team, limits: GameStateLimits) -> Self {
+        Self {
+            team,
+            limits,
+            state: GameState::Running,
+            sequence: SequenceLatch::new(DEFAULT_RESYNC_TIMEOUT),
+        }
+    }
+
+    /// The limits placed on the robots by each game state
+    pub fn limits(&self) -> &GameStateLimits {
+        &self.limits
+    }
+
+    /// The current state of the game
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+
+    /// Change the state of the game (e.g. from the referee in the base station)
+    pub fn set_state(&mut self, state: GameState) {
+        self.state = state;
+    }
+
+    /// Record a game state message received at `now` (in microseconds).
+    ///
+    /// Game state messages for other teams and repeated or late game state messages (whose
+    /// sequence number is not newer than the last one accepted) are ignored (returning false)
+    pub fn receive(&mut self, message: &GameStateMessage, now: u64) -> bool {
+        if message.team != self.team || !self.sequence.accept(message.sequence, now) {
+            return false;
+        }
+        self.state = message.state;
+        true
+    }
+
+    /// Limit a command to what is allowed in the current state
+    pub fn limit(&self, command: ControlMessage) -> ControlMessage {
+        self.limits.apply(self.state, command)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unusual_byte_groupings)]
+mod tests {
+    use super::*;
+
+    use ncomm_utils::packing::Packable;
+
+    use crate::ControlMessageBuilder;
+
+    /// Test that game state messages pack to the layout in the schema and unpack back
+    #[test]
+    fn test_pack_and_unpack() {
+        let message = GameStateMessage::new(Team::Yellow, GameState::BallPlacement, 42);
+        let mut buffer = [0u8; GAME_STATE_MESSAGE_SIZE];
+        message.pack(&mut buffer).unwrap();
+        assert_eq!(buffer, [0b1_11_00000, 42]);
+        assert_eq!(GameStateMessage::unpack(&buffer).unwrap(), message);
+    }
+
+    /// Test that the limiter clamps the speed and stands the kicker down in every state but
+    /// running
+    #[test]
+    fn test_game_state_limits() {
+        let mut limiter = GameStateLimiter::new(Team::Blue, GameStateLimits::default());
+        let command = ControlMessageBuilder::new()
+            .body_x(3.0)
+            .body_y(-4.0)
+            .body_w(2.0)
+            .trigger_mode(TriggerMode::OnBreakBeam)
+            .kick_strength(200)
+            .dribbler_speed(50)
+            .build();
+        assert_eq!(limiter.limit(command), command);
+
+        assert!(!limiter.receive(&GameStateMessage::new(Team::Yellow, GameState::Stop, 0), 0));
+        assert!(limiter.receive(&GameStateMessage::new(Team::Blue, GameState::Stop, 0), 0));
+        let stopped = limiter.limit(command);
+        assert_eq!((stopped.body_x, stopped.body_y), (3600, -4800));
+        assert_eq!((stopped.body_w, stopped.dribbler_speed), (4000, 50));
+        assert_eq!(stopped.trigger_mode, TriggerMode::StandDown);
+        assert_eq!(stopped.kick_strength, 0);
+        // slow commands are not changed
+        let slow = ControlMessageBuilder::new().body_x(1.0).body_y(1.0).build();
+        assert_eq!(limiter.limit(slow), slow);
+
+        limiter.set_state(GameState::Halt);
+        let halted = limiter.limit(command);
+        assert_eq!(halted.get_velocity(), nalgebra::Vector3::zeros());
+        assert_eq!(halted.dribbler_speed, 0);
+        assert_eq!(halted.trigger_mode, TriggerMode::StandDown);
+    }
+
+    /// Test that a late `Running` arriving after a `Stop` does not lift the limits
+    #[test]
+    fn test_game_state_ignores_late_messages() {
+        let mut limiter = GameStateLimiter::new(Team::Yellow, GameStateLimits::default());
+        let message = |state, sequence| GameStateMessage::new(Team::Yellow, state, sequence);
+        assert!(limiter.receive(&message(GameState::Stop, 0), 0));
+        assert!(!limiter.receive(&message(GameState::Running, 255), 10));
+        assert!(!limiter.receive(&message(GameState::Running, 0), 20));
+        assert_eq!(limiter.state(), GameState::Stop);
+
+        assert!(limiter.receive(&message(GameState::Running, 1), 30));
+        assert_eq!(limiter.state(), GameState::Running);
+    }
+
+    /// Test that a restarted base station (counting from 0 again) can stop the robots once the
+    /// re-sync timeout has passed
+    #[test]
+    fn test_game_state_resyncs() {
+        let mut limiter = GameStateLimiter::new(Team::Yellow, GameStateLimits::default());
+        let message = |state, sequence| GameStateMessage::new(Team::Yellow, state, sequence);
+        assert!(limiter.receive(&message(GameState::Running, 100), 0));
+
+        assert!(!limiter.receive(&message(GameState::Stop, 0), 1_000));
+        assert_eq!(limiter.state(), GameState::Running);
+        assert!(limiter.receive(&message(GameState::Stop, 0), DEFAULT_RESYNC_TIMEOUT + 1));
+        assert_eq!(limiter.state(), GameState::Stop);
+    }
+}
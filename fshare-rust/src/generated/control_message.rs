@@ -0,0 +1,87 @@
+// @generated by rtp-codegen from fshare-rust/schema/messages.toml.
+// Do not edit by hand, run `cargo run -p rtp-codegen` from fshare-rust instead.
+
+use ncomm_utils::packing::{Packable, PackingError};
+
+use crate::control_message::{ControlMessage, Mode, ShootMode, TriggerMode, CONTROL_MESSAGE_SIZE, LINEAR_VELOCITY_SCALE_FACTOR, ANGULAR_VELOCITY_SCALE_FACTOR};
+use crate::error::check_len;
+use crate::{RtpError, Team, ROBOTS_PER_TEAM};
+
+const _: () = assert!(CONTROL_MESSAGE_SIZE == 11);
+const _: () = assert!(LINEAR_VELOCITY_SCALE_FACTOR == 4000.0);
+const _: () = assert!(ANGULAR_VELOCITY_SCALE_FACTOR == 2000.0);
+#[allow(deprecated)]
+const _: () = assert!(crate::control_message::VELOCITY_SCALE_FACTOR == 4000.0);
+
+impl ControlMessage {
+    /// Unpack a ControlMessage, rejecting unknown enum values and robot ids that are out of range
+    /// instead of coercing them.
+    ///
+    /// Note: `Packable::unpack` remains lenient for backwards compatibility
+    pub fn unpack_strict(data: &[u8]) -> Result<Self, RtpError> {
+        check_len(data, CONTROL_MESSAGE_SIZE)?;
+
+        let robot_id = (data[0] & (0b1111 << 3)) >> 3;
+        if robot_id as usize >= ROBOTS_PER_TEAM {
+            return Err(RtpError::InvalidRobotId(robot_id));
+        }
+
+        Ok(Self {
+            team: ((data[0] & (0b1 << 7)) >> 7).try_into()?,
+            robot_id,
+            shoot_mode: ((data[0] & (0b1 << 2)) >> 2).try_into()?,
+            trigger_mode: (data[0] & 0b11).try_into()?,
+            body_x: i16::from_le_bytes([data[1], data[2]]),
+            body_y: i16::from_le_bytes([data[3], data[4]]),
+            body_w: i16::from_le_bytes([data[5], data[6]]),
+            dribbler_speed: i8::from_le_bytes([data[7]]),
+            kick_strength: data[8],
+            role: (data[9] & (0b11 << 6)) >> 6,
+            mode: (data[9] & 0b111111).try_into()?,
+            sequence: data[10],
+        })
+    }
+}
+
+impl Packable for ControlMessage {
+    fn len() -> usize {
+        CONTROL_MESSAGE_SIZE
+    }
+
+    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
+        check_len(buffer, CONTROL_MESSAGE_SIZE)?;
+
+        buffer[0] = ((self.team as u8) & 0b1) << 7
+            | (self.robot_id & 0b1111) << 3
+            | ((self.shoot_mode as u8) & 0b1) << 2
+            | ((self.trigger_mode as u8) & 0b11);
+        buffer[1..3].copy_from_slice(&self.body_x.to_le_bytes());
+        buffer[3..5].copy_from_slice(&self.body_y.to_le_bytes());
+        buffer[5..7].copy_from_slice(&self.body_w.to_le_bytes());
+        buffer[7] = self.dribbler_speed.to_le_bytes()[0];
+        buffer[8] = self.kick_strength;
+        buffer[9] = (self.role & 0b11) << 6
+            | ((self.mode as u8) & 0b111111);
+        buffer[10] = self.sequence;
+        Ok(())
+    }
+
+    fn unpack(data: &[u8]) -> Result<Self, PackingError> {
+        check_len(data, CONTROL_MESSAGE_SIZE)?;
+
+        Ok(Self {
+            team: Team::try_from((data[0] & (0b1 << 7)) >> 7).unwrap_or(Team::Blue),
+            robot_id: (data[0] & (0b1111 << 3)) >> 3,
+            shoot_mode: ShootMode::try_from((data[0] & (0b1 << 2)) >> 2).unwrap_or(ShootMode::Kick),
+            trigger_mode: TriggerMode::try_from(data[0] & 0b11).unwrap_or(TriggerMode::StandDown),
+            body_x: i16::from_le_bytes([data[1], data[2]]),
+            body_y: i16::from_le_bytes([data[3], data[4]]),
+            body_w: i16::from_le_bytes([data[5], data[6]]),
+            dribbler_speed: i8::from_le_bytes([data[7]]),
+            kick_strength: data[8],
+            role: (data[9] & (0b11 << 6)) >> 6,
+            mode: Mode::try_from(data[9] & 0b111111).unwrap_or(Mode::Default),
+            sequence: data[10],
+        })
+    }
+}
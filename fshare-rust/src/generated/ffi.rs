@@ -0,0 +1,884 @@
+// @generated by rtp-codegen from fshare-rust/schema/messages.toml.
+// Do not edit by hand, run `cargo run -p rtp-codegen` from fshare-rust instead.
+
+use ncomm_utils::packing::Packable;
+
+use crate::control_message::{ControlMessage, CONTROL_MESSAGE_SIZE};
+use crate::robot_status_message::{RobotStatusMessage, ROBOT_STATUS_SIZE};
+use crate::halt_message::{HaltMessage, HALT_MESSAGE_SIZE};
+use crate::game_state::{GameStateMessage, GAME_STATE_MESSAGE_SIZE};
+use crate::imu_test_message::{ImuTestMessage, IMU_MESSAGE_SIZE};
+use crate::kicker_program_message::{KickerProgramMessage, KICKER_PROGRAM_MESSAGE};
+use crate::kicker_testing::{KickerTestingMessage, KICKER_TESTING_SIZE};
+use crate::radio_benchmarks::{RadioReceiveBenchmarkMessage, RADIO_RECEIVE_BENCHMARK_SIZE, RadioSendBenchmarkMessage, RADIO_SEND_BENCHMARK_SIZE};
+use crate::control_test_message::{ControlTestMessage, CONTROL_TEST_MESSAGE_SIZE};
+use crate::RtpError;
+
+use super::status;
+
+/// The operation succeeded
+pub const RTP_OK: i32 = 0;
+
+/// A pointer argument was null
+pub const RTP_ERROR_NULL_POINTER: i32 = -1;
+
+/// The buffer is too short for the message
+pub const RTP_ERROR_BUFFER_TOO_SHORT: i32 = -2;
+
+/// A field contains a value that does not correspond to any variant of its enum
+pub const RTP_ERROR_INVALID_DISCRIMINANT: i32 = -3;
+
+/// Any other error
+pub const RTP_ERROR_OTHER: i32 = -4;
+
+const _: () = assert!(CONTROL_MESSAGE_SIZE == 11);
+
+/// C mirror of `ControlMessage`
+///
+/// Note: enums are passed as their raw value
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RtpControlMessage {
+    /// Team of the robot (0: Blue) (1: Yellow)
+    pub team: u8,
+    /// Id of the robot
+    pub robot_id: u8,
+    /// 0 -> Kick; 1 -> Chip
+    pub shoot_mode: u8,
+    /// 0 -> Stand Down; 1 -> Immediate; 2 -> On Break Beam
+    pub trigger_mode: u8,
+    /// x velocity (body frame) multiplied by LINEAR_VELOCITY_SCALE_FACTOR
+    pub body_x: i16,
+    /// y velocity (body frame) multiplied by LINEAR_VELOCITY_SCALE_FACTOR
+    pub body_y: i16,
+    /// w velocity (body frame) multiplied by ANGULAR_VELOCITY_SCALE_FACTOR
+    pub body_w: i16,
+    /// Speed of the dribbler
+    pub dribbler_speed: i8,
+    /// Strength of the kicker
+    pub kick_strength: u8,
+    /// Robot role
+    pub role: u8,
+    /// Special mode of the robot (see ControlMode)
+    pub mode: u8,
+    /// Rolling sequence number echoed back in the RobotStatusMessage
+    pub sequence: u8,
+}
+
+impl From<ControlMessage> for RtpControlMessage {
+    fn from(message: ControlMessage) -> Self {
+        Self {
+            team: message.team as u8,
+            robot_id: message.robot_id,
+            shoot_mode: message.shoot_mode as u8,
+            trigger_mode: message.trigger_mode as u8,
+            body_x: message.body_x,
+            body_y: message.body_y,
+            body_w: message.body_w,
+            dribbler_speed: message.dribbler_speed,
+            kick_strength: message.kick_strength,
+            role: message.role,
+            mode: message.mode as u8,
+            sequence: message.sequence,
+        }
+    }
+}
+
+impl TryFrom<RtpControlMessage> for ControlMessage {
+    type Error = RtpError;
+
+    fn try_from(mirror: RtpControlMessage) -> Result<Self, Self::Error> {
+        Ok(Self {
+            team: mirror.team.try_into()?,
+            robot_id: mirror.robot_id,
+            shoot_mode: mirror.shoot_mode.try_into()?,
+            trigger_mode: mirror.trigger_mode.try_into()?,
+            body_x: mirror.body_x,
+            body_y: mirror.body_y,
+            body_w: mirror.body_w,
+            dribbler_speed: mirror.dribbler_speed,
+            kick_strength: mirror.kick_strength,
+            role: mirror.role,
+            mode: mirror.mode.try_into()?,
+            sequence: mirror.sequence,
+        })
+    }
+}
+
+/// Pack a `ControlMessage` into `buffer`, returning `RTP_OK` or a negative error code
+///
+/// # Safety
+/// `message` must point to a valid `RtpControlMessage` and `buffer` to `buffer_len` writable Bytes
+#[no_mangle]
+pub unsafe extern "C" fn rtp_control_message_pack(
+    message: *const RtpControlMessage,
+    buffer: *mut u8,
+    buffer_len: usize,
+) -> i32 {
+    if message.is_null() || buffer.is_null() {
+        return RTP_ERROR_NULL_POINTER;
+    }
+    let buffer = core::slice::from_raw_parts_mut(buffer, buffer_len);
+    status(
+        ControlMessage::try_from(*message)
+            .and_then(|message| message.pack(buffer).map_err(RtpError::from)),
+    )
+}
+
+/// Unpack a `ControlMessage` from `data` into `message`, returning `RTP_OK` or a negative error code
+///
+/// # Safety
+/// `data` must point to `data_len` readable Bytes and `message` to a writable `RtpControlMessage`
+#[no_mangle]
+pub unsafe extern "C" fn rtp_control_message_unpack(
+    data: *const u8,
+    data_len: usize,
+    message: *mut RtpControlMessage,
+) -> i32 {
+    if data.is_null() || message.is_null() {
+        return RTP_ERROR_NULL_POINTER;
+    }
+    let data = core::slice::from_raw_parts(data, data_len);
+    match ControlMessage::unpack(data) {
+        Ok(unpacked) => {
+            message.write(unpacked.into());
+            RTP_OK
+        }
+        Err(err) => status(Err(err.into())),
+    }
+}
+
+const _: () = assert!(ROBOT_STATUS_SIZE == 4);
+
+/// C mirror of `RobotStatusMessage`
+///
+/// Note: enums are passed as their raw value
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RtpRobotStatusMessage {
+    /// Team of the robot (0: Blue) (1: Yellow)
+    pub team: u8,
+    /// Id of the robot
+    pub robot_id: u8,
+    /// True if the robot has ball sense
+    pub ball_sense_status: bool,
+    /// True if is kicking
+    pub kick_status: bool,
+    /// True if the kicker is healthy
+    pub kick_healthy: bool,
+    /// Battery voltage (multiply by BATTERY_SCALE_FACTOR for volts)
+    pub battery_voltage: u8,
+    /// Error per motor
+    pub motor_errors: u8,
+    /// FPGA is working
+    pub fpga_status: bool,
+    /// True while the robot is latched in a team halt
+    pub halted: bool,
+    /// Sequence number of the last ControlMessage received
+    pub sequence: u8,
+}
+
+impl From<RobotStatusMessage> for RtpRobotStatusMessage {
+    fn from(message: RobotStatusMessage) -> Self {
+        Self {
+            team: message.team as u8,
+            robot_id: message.robot_id,
+            ball_sense_status: message.ball_sense_status,
+            kick_status: message.kick_status,
+            kick_healthy: message.kick_healthy,
+            battery_voltage: message.battery_voltage,
+            motor_errors: message.motor_errors,
+            fpga_status: message.fpga_status,
+            halted: message.halted,
+            sequence: message.sequence,
+        }
+    }
+}
+
+impl TryFrom<RtpRobotStatusMessage> for RobotStatusMessage {
+    type Error = RtpError;
+
+    fn try_from(mirror: RtpRobotStatusMessage) -> Result<Self, Self::Error> {
+        Ok(Self {
+            team: mirror.team.try_into()?,
+            robot_id: mirror.robot_id,
+            ball_sense_status: mirror.ball_sense_status,
+            kick_status: mirror.kick_status,
+            kick_healthy: mirror.kick_healthy,
+            battery_voltage: mirror.battery_voltage,
+            motor_errors: mirror.motor_errors,
+            fpga_status: mirror.fpga_status,
+            halted: mirror.halted,
+            sequence: mirror.sequence,
+        })
+    }
+}
+
+/// Pack a `RobotStatusMessage` into `buffer`, returning `RTP_OK` or a negative error code
+///
+/// # Safety
+/// `message` must point to a valid `RtpRobotStatusMessage` and `buffer` to `buffer_len` writable Bytes
+#[no_mangle]
+pub unsafe extern "C" fn rtp_robot_status_message_pack(
+    message: *const RtpRobotStatusMessage,
+    buffer: *mut u8,
+    buffer_len: usize,
+) -> i32 {
+    if message.is_null() || buffer.is_null() {
+        return RTP_ERROR_NULL_POINTER;
+    }
+    let buffer = core::slice::from_raw_parts_mut(buffer, buffer_len);
+    status(
+        RobotStatusMessage::try_from(*message)
+            .and_then(|message| message.pack(buffer).map_err(RtpError::from)),
+    )
+}
+
+/// Unpack a `RobotStatusMessage` from `data` into `message`, returning `RTP_OK` or a negative error code
+///
+/// # Safety
+/// `data` must point to `data_len` readable Bytes and `message` to a writable `RtpRobotStatusMessage`
+#[no_mangle]
+pub unsafe extern "C" fn rtp_robot_status_message_unpack(
+    data: *const u8,
+    data_len: usize,
+    message: *mut RtpRobotStatusMessage,
+) -> i32 {
+    if data.is_null() || message.is_null() {
+        return RTP_ERROR_NULL_POINTER;
+    }
+    let data = core::slice::from_raw_parts(data, data_len);
+    match RobotStatusMessage::unpack(data) {
+        Ok(unpacked) => {
+            message.write(unpacked.into());
+            RTP_OK
+        }
+        Err(err) => status(Err(err.into())),
+    }
+}
+
+const _: () = assert!(HALT_MESSAGE_SIZE == 2);
+
+/// C mirror of `HaltMessage`
+///
+/// Note: enums are passed as their raw value
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RtpHaltMessage {
+    /// Team of the robots (0: Blue) (1: Yellow)
+    pub team: u8,
+    /// True to halt the robots, false to let them resume
+    pub halt: bool,
+    /// Rolling sequence number of the halt message
+    pub sequence: u8,
+}
+
+impl From<HaltMessage> for RtpHaltMessage {
+    fn from(message: HaltMessage) -> Self {
+        Self {
+            team: message.team as u8,
+            halt: message.halt,
+            sequence: message.sequence,
+        }
+    }
+}
+
+impl TryFrom<RtpHaltMessage> for HaltMessage {
+    type Error = RtpError;
+
+    fn try_from(mirror: RtpHaltMessage) -> Result<Self, Self::Error> {
+        Ok(Self {
+            team: mirror.team.try_into()?,
+            halt: mirror.halt,
+            sequence: mirror.sequence,
+        })
+    }
+}
+
+/// Pack a `HaltMessage` into `buffer`, returning `RTP_OK` or a negative error code
+///
+/// # Safety
+/// `message` must point to a valid `RtpHaltMessage` and `buffer` to `buffer_len` writable Bytes
+#[no_mangle]
+pub unsafe extern "C" fn rtp_halt_message_pack(
+    message: *const RtpHaltMessage,
+    buffer: *mut u8,
+    buffer_len: usize,
+) -> i32 {
+    if message.is_null() || buffer.is_null() {
+        return RTP_ERROR_NULL_POINTER;
+    }
+    let buffer = core::slice::from_raw_parts_mut(buffer, buffer_len);
+    status(
+        HaltMessage::try_from(*message)
+            .and_then(|message| message.pack(buffer).map_err(RtpError::from)),
+    )
+}
+
+/// Unpack a `HaltMessage` from `data` into `message`, returning `RTP_OK` or a negative error code
+///
+/// # Safety
+/// `data` must point to `data_len` readable Bytes and `message` to a writable `RtpHaltMessage`
+#[no_mangle]
+pub unsafe extern "C" fn rtp_halt_message_unpack(
+    data: *const u8,
+    data_len: usize,
+    message: *mut RtpHaltMessage,
+) -> i32 {
+    if data.is_null() || message.is_null() {
+        return RTP_ERROR_NULL_POINTER;
+    }
+    let data = core::slice::from_raw_parts(data, data_len);
+    match HaltMessage::unpack(data) {
+        Ok(unpacked) => {
+            message.write(unpacked.into());
+            RTP_OK
+        }
+        Err(err) => status(Err(err.into())),
+    }
+}
+
+const _: () = assert!(GAME_STATE_MESSAGE_SIZE == 2);
+
+/// C mirror of `GameStateMessage`
+///
+/// Note: enums are passed as their raw value
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RtpGameStateMessage {
+    /// Team of the robots (0: Blue) (1: Yellow)
+    pub team: u8,
+    /// 0 -> Halt; 1 -> Stop; 2 -> Running; 3 -> Ball Placement
+    pub state: u8,
+    /// Rolling sequence number of the game state message
+    pub sequence: u8,
+}
+
+impl From<GameStateMessage> for RtpGameStateMessage {
+    fn from(message: GameStateMessage) -> Self {
+        Self {
+            team: message.team as u8,
+            state: message.state as u8,
+            sequence: message.sequence,
+        }
+    }
+}
+
+impl TryFrom<RtpGameStateMessage> for GameStateMessage {
+    type Error = RtpError;
+
+    fn try_from(mirror: RtpGameStateMessage) -> Result<Self, Self::Error> {
+        Ok(Self {
+            team: mirror.team.try_into()?,
+            state: mirror.state.try_into()?,
+            sequence: mirror.sequence,
+        })
+    }
+}
+
+/// Pack a `GameStateMessage` into `buffer`, returning `RTP_OK` or a negative error code
+///
+/// # Safety
+/// `message` must point to a valid `RtpGameStateMessage` and `buffer` to `buffer_len` writable Bytes
+#[no_mangle]
+pub unsafe extern "C" fn rtp_game_state_message_pack(
+    message: *const RtpGameStateMessage,
+    buffer: *mut u8,
+    buffer_len: usize,
+) -> i32 {
+    if message.is_null() || buffer.is_null() {
+        return RTP_ERROR_NULL_POINTER;
+    }
+    let buffer = core::slice::from_raw_parts_mut(buffer, buffer_len);
+    status(
+        GameStateMessage::try_from(*message)
+            .and_then(|message| message.pack(buffer).map_err(RtpError::from)),
+    )
+}
+
+/// Unpack a `GameStateMessage` from `data` into `message`, returning `RTP_OK` or a negative error code
+///
+/// # Safety
+/// `data` must point to `data_len` readable Bytes and `message` to a writable `RtpGameStateMessage`
+#[no_mangle]
+pub unsafe extern "C" fn rtp_game_state_message_unpack(
+    data: *const u8,
+    data_len: usize,
+    message: *mut RtpGameStateMessage,
+) -> i32 {
+    if data.is_null() || message.is_null() {
+        return RTP_ERROR_NULL_POINTER;
+    }
+    let data = core::slice::from_raw_parts(data, data_len);
+    match GameStateMessage::unpack(data) {
+        Ok(unpacked) => {
+            message.write(unpacked.into());
+            RTP_OK
+        }
+        Err(err) => status(Err(err.into())),
+    }
+}
+
+const _: () = assert!(IMU_MESSAGE_SIZE == 13);
+
+/// C mirror of `ImuTestMessage`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RtpImuTestMessage {
+    /// Is this the first IMU test message
+    pub first_message: bool,
+    /// Is this the last IMU test message
+    pub last_message: bool,
+    /// The z-gyro value obtained from the IMU
+    pub gyro_z: f32,
+    /// The x-accelerometer value obtained from the IMU
+    pub accel_x: f32,
+    /// The y-accelerometer value obtained from the IMU
+    pub accel_y: f32,
+}
+
+impl From<ImuTestMessage> for RtpImuTestMessage {
+    fn from(message: ImuTestMessage) -> Self {
+        Self {
+            first_message: message.first_message,
+            last_message: message.last_message,
+            gyro_z: message.gyro_z,
+            accel_x: message.accel_x,
+            accel_y: message.accel_y,
+        }
+    }
+}
+
+impl From<RtpImuTestMessage> for ImuTestMessage {
+    fn from(mirror: RtpImuTestMessage) -> Self {
+        Self {
+            first_message: mirror.first_message,
+            last_message: mirror.last_message,
+            gyro_z: mirror.gyro_z,
+            accel_x: mirror.accel_x,
+            accel_y: mirror.accel_y,
+        }
+    }
+}
+
+/// Pack a `ImuTestMessage` into `buffer`, returning `RTP_OK` or a negative error code
+///
+/// # Safety
+/// `message` must point to a valid `RtpImuTestMessage` and `buffer` to `buffer_len` writable Bytes
+#[no_mangle]
+pub unsafe extern "C" fn rtp_imu_test_message_pack(
+    message: *const RtpImuTestMessage,
+    buffer: *mut u8,
+    buffer_len: usize,
+) -> i32 {
+    if message.is_null() || buffer.is_null() {
+        return RTP_ERROR_NULL_POINTER;
+    }
+    let buffer = core::slice::from_raw_parts_mut(buffer, buffer_len);
+    status(ImuTestMessage::from(*message).pack(buffer).map_err(RtpError::from))
+}
+
+/// Unpack a `ImuTestMessage` from `data` into `message`, returning `RTP_OK` or a negative error code
+///
+/// # Safety
+/// `data` must point to `data_len` readable Bytes and `message` to a writable `RtpImuTestMessage`
+#[no_mangle]
+pub unsafe extern "C" fn rtp_imu_test_message_unpack(
+    data: *const u8,
+    data_len: usize,
+    message: *mut RtpImuTestMessage,
+) -> i32 {
+    if data.is_null() || message.is_null() {
+        return RTP_ERROR_NULL_POINTER;
+    }
+    let data = core::slice::from_raw_parts(data, data_len);
+    match ImuTestMessage::unpack(data) {
+        Ok(unpacked) => {
+            message.write(unpacked.into());
+            RTP_OK
+        }
+        Err(err) => status(Err(err.into())),
+    }
+}
+
+const _: () = assert!(KICKER_PROGRAM_MESSAGE == 5);
+
+/// C mirror of `KickerProgramMessage`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RtpKickerProgramMessage {
+    /// Is the kicker being programmed with kick on breakbeam
+    pub kick_on_breakbeam: bool,
+    /// Is the kicker finished programming
+    pub finished: bool,
+    /// The current page being programmed
+    pub page: u32,
+}
+
+impl From<KickerProgramMessage> for RtpKickerProgramMessage {
+    fn from(message: KickerProgramMessage) -> Self {
+        Self {
+            kick_on_breakbeam: message.kick_on_breakbeam,
+            finished: message.finished,
+            page: message.page,
+        }
+    }
+}
+
+impl From<RtpKickerProgramMessage> for KickerProgramMessage {
+    fn from(mirror: RtpKickerProgramMessage) -> Self {
+        Self {
+            kick_on_breakbeam: mirror.kick_on_breakbeam,
+            finished: mirror.finished,
+            page: mirror.page,
+        }
+    }
+}
+
+/// Pack a `KickerProgramMessage` into `buffer`, returning `RTP_OK` or a negative error code
+///
+/// # Safety
+/// `message` must point to a valid `RtpKickerProgramMessage` and `buffer` to `buffer_len` writable Bytes
+#[no_mangle]
+pub unsafe extern "C" fn rtp_kicker_program_message_pack(
+    message: *const RtpKickerProgramMessage,
+    buffer: *mut u8,
+    buffer_len: usize,
+) -> i32 {
+    if message.is_null() || buffer.is_null() {
+        return RTP_ERROR_NULL_POINTER;
+    }
+    let buffer = core::slice::from_raw_parts_mut(buffer, buffer_len);
+    status(KickerProgramMessage::from(*message).pack(buffer).map_err(RtpError::from))
+}
+
+/// Unpack a `KickerProgramMessage` from `data` into `message`, returning `RTP_OK` or a negative error code
+///
+/// # Safety
+/// `data` must point to `data_len` readable Bytes and `message` to a writable `RtpKickerProgramMessage`
+#[no_mangle]
+pub unsafe extern "C" fn rtp_kicker_program_message_unpack(
+    data: *const u8,
+    data_len: usize,
+    message: *mut RtpKickerProgramMessage,
+) -> i32 {
+    if data.is_null() || message.is_null() {
+        return RTP_ERROR_NULL_POINTER;
+    }
+    let data = core::slice::from_raw_parts(data, data_len);
+    match KickerProgramMessage::unpack(data) {
+        Ok(unpacked) => {
+            message.write(unpacked.into());
+            RTP_OK
+        }
+        Err(err) => status(Err(err.into())),
+    }
+}
+
+const _: () = assert!(KICKER_TESTING_SIZE == 2);
+
+/// C mirror of `KickerTestingMessage`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RtpKickerTestingMessage {
+    /// Is the kicker healthy
+    pub healthy: bool,
+    /// Does the kicker have ball sense
+    pub ball_sense: bool,
+    /// Is the kicker kicking (otherwise it is chipping)
+    pub kicking: bool,
+    /// Should the kicker activate on ball sense
+    pub kick_on_ball_sense: bool,
+    /// Should the kicker be kicking immediately
+    pub kick_immediately: bool,
+    /// The current voltage of the kicker
+    pub voltage: u8,
+}
+
+impl From<KickerTestingMessage> for RtpKickerTestingMessage {
+    fn from(message: KickerTestingMessage) -> Self {
+        Self {
+            healthy: message.healthy,
+            ball_sense: message.ball_sense,
+            kicking: message.kicking,
+            kick_on_ball_sense: message.kick_on_ball_sense,
+            kick_immediately: message.kick_immediately,
+            voltage: message.voltage,
+        }
+    }
+}
+
+impl From<RtpKickerTestingMessage> for KickerTestingMessage {
+    fn from(mirror: RtpKickerTestingMessage) -> Self {
+        Self {
+            healthy: mirror.healthy,
+            ball_sense: mirror.ball_sense,
+            kicking: mirror.kicking,
+            kick_on_ball_sense: mirror.kick_on_ball_sense,
+            kick_immediately: mirror.kick_immediately,
+            voltage: mirror.voltage,
+        }
+    }
+}
+
+/// Pack a `KickerTestingMessage` into `buffer`, returning `RTP_OK` or a negative error code
+///
+/// # Safety
+/// `message` must point to a valid `RtpKickerTestingMessage` and `buffer` to `buffer_len` writable Bytes
+#[no_mangle]
+pub unsafe extern "C" fn rtp_kicker_testing_message_pack(
+    message: *const RtpKickerTestingMessage,
+    buffer: *mut u8,
+    buffer_len: usize,
+) -> i32 {
+    if message.is_null() || buffer.is_null() {
+        return RTP_ERROR_NULL_POINTER;
+    }
+    let buffer = core::slice::from_raw_parts_mut(buffer, buffer_len);
+    status(KickerTestingMessage::from(*message).pack(buffer).map_err(RtpError::from))
+}
+
+/// Unpack a `KickerTestingMessage` from `data` into `message`, returning `RTP_OK` or a negative error code
+///
+/// # Safety
+/// `data` must point to `data_len` readable Bytes and `message` to a writable `RtpKickerTestingMessage`
+#[no_mangle]
+pub unsafe extern "C" fn rtp_kicker_testing_message_unpack(
+    data: *const u8,
+    data_len: usize,
+    message: *mut RtpKickerTestingMessage,
+) -> i32 {
+    if data.is_null() || message.is_null() {
+        return RTP_ERROR_NULL_POINTER;
+    }
+    let data = core::slice::from_raw_parts(data, data_len);
+    match KickerTestingMessage::unpack(data) {
+        Ok(unpacked) => {
+            message.write(unpacked.into());
+            RTP_OK
+        }
+        Err(err) => status(Err(err.into())),
+    }
+}
+
+const _: () = assert!(RADIO_RECEIVE_BENCHMARK_SIZE == 8);
+
+/// C mirror of `RadioReceiveBenchmarkMessage`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RtpRadioReceiveBenchmarkMessage {
+    /// The amount of time the radio was receiving for (ms)
+    pub receive_time_ms: u32,
+    /// The total number of packets received during the time interval
+    pub received_packets: u32,
+}
+
+impl From<RadioReceiveBenchmarkMessage> for RtpRadioReceiveBenchmarkMessage {
+    fn from(message: RadioReceiveBenchmarkMessage) -> Self {
+        Self {
+            receive_time_ms: message.receive_time_ms,
+            received_packets: message.received_packets,
+        }
+    }
+}
+
+impl From<RtpRadioReceiveBenchmarkMessage> for RadioReceiveBenchmarkMessage {
+    fn from(mirror: RtpRadioReceiveBenchmarkMessage) -> Self {
+        Self {
+            receive_time_ms: mirror.receive_time_ms,
+            received_packets: mirror.received_packets,
+        }
+    }
+}
+
+/// Pack a `RadioReceiveBenchmarkMessage` into `buffer`, returning `RTP_OK` or a negative error code
+///
+/// # Safety
+/// `message` must point to a valid `RtpRadioReceiveBenchmarkMessage` and `buffer` to `buffer_len` writable Bytes
+#[no_mangle]
+pub unsafe extern "C" fn rtp_radio_receive_benchmark_message_pack(
+    message: *const RtpRadioReceiveBenchmarkMessage,
+    buffer: *mut u8,
+    buffer_len: usize,
+) -> i32 {
+    if message.is_null() || buffer.is_null() {
+        return RTP_ERROR_NULL_POINTER;
+    }
+    let buffer = core::slice::from_raw_parts_mut(buffer, buffer_len);
+    status(RadioReceiveBenchmarkMessage::from(*message).pack(buffer).map_err(RtpError::from))
+}
+
+/// Unpack a `RadioReceiveBenchmarkMessage` from `data` into `message`, returning `RTP_OK` or a negative error code
+///
+/// # Safety
+/// `data` must point to `data_len` readable Bytes and `message` to a writable `RtpRadioReceiveBenchmarkMessage`
+#[no_mangle]
+pub unsafe extern "C" fn rtp_radio_receive_benchmark_message_unpack(
+    data: *const u8,
+    data_len: usize,
+    message: *mut RtpRadioReceiveBenchmarkMessage,
+) -> i32 {
+    if data.is_null() || message.is_null() {
+        return RTP_ERROR_NULL_POINTER;
+    }
+    let data = core::slice::from_raw_parts(data, data_len);
+    match RadioReceiveBenchmarkMessage::unpack(data) {
+        Ok(unpacked) => {
+            message.write(unpacked.into());
+            RTP_OK
+        }
+        Err(err) => status(Err(err.into())),
+    }
+}
+
+const _: () = assert!(RADIO_SEND_BENCHMARK_SIZE == 8);
+
+/// C mirror of `RadioSendBenchmarkMessage`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RtpRadioSendBenchmarkMessage {
+    /// The number of packets successfully sent (and acknowledged) according to the robot
+    pub acknowledged_packets: u32,
+    /// The total number of packets sent by the robot
+    pub sent_packets: u32,
+}
+
+impl From<RadioSendBenchmarkMessage> for RtpRadioSendBenchmarkMessage {
+    fn from(message: RadioSendBenchmarkMessage) -> Self {
+        Self {
+            acknowledged_packets: message.acknowledged_packets,
+            sent_packets: message.sent_packets,
+        }
+    }
+}
+
+impl From<RtpRadioSendBenchmarkMessage> for RadioSendBenchmarkMessage {
+    fn from(mirror: RtpRadioSendBenchmarkMessage) -> Self {
+        Self {
+            acknowledged_packets: mirror.acknowledged_packets,
+            sent_packets: mirror.sent_packets,
+        }
+    }
+}
+
+/// Pack a `RadioSendBenchmarkMessage` into `buffer`, returning `RTP_OK` or a negative error code
+///
+/// # Safety
+/// `message` must point to a valid `RtpRadioSendBenchmarkMessage` and `buffer` to `buffer_len` writable Bytes
+#[no_mangle]
+pub unsafe extern "C" fn rtp_radio_send_benchmark_message_pack(
+    message: *const RtpRadioSendBenchmarkMessage,
+    buffer: *mut u8,
+    buffer_len: usize,
+) -> i32 {
+    if message.is_null() || buffer.is_null() {
+        return RTP_ERROR_NULL_POINTER;
+    }
+    let buffer = core::slice::from_raw_parts_mut(buffer, buffer_len);
+    status(RadioSendBenchmarkMessage::from(*message).pack(buffer).map_err(RtpError::from))
+}
+
+/// Unpack a `RadioSendBenchmarkMessage` from `data` into `message`, returning `RTP_OK` or a negative error code
+///
+/// # Safety
+/// `data` must point to `data_len` readable Bytes and `message` to a writable `RtpRadioSendBenchmarkMessage`
+#[no_mangle]
+pub unsafe extern "C" fn rtp_radio_send_benchmark_message_unpack(
+    data: *const u8,
+    data_len: usize,
+    message: *mut RtpRadioSendBenchmarkMessage,
+) -> i32 {
+    if data.is_null() || message.is_null() {
+        return RTP_ERROR_NULL_POINTER;
+    }
+    let data = core::slice::from_raw_parts(data, data_len);
+    match RadioSendBenchmarkMessage::unpack(data) {
+        Ok(unpacked) => {
+            message.write(unpacked.into());
+            RTP_OK
+        }
+        Err(err) => status(Err(err.into())),
+    }
+}
+
+const _: () = assert!(CONTROL_TEST_MESSAGE_SIZE == 30);
+
+/// C mirror of `ControlTestMessage`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RtpControlTestMessage {
+    /// The z-gyro value obtained from the IMU
+    pub gyro_z: f32,
+    /// The x-accelerometer value obtained from the IMU
+    pub accel_x: f32,
+    /// The y-accelerometer value obtained from the IMU
+    pub accel_y: f32,
+    /// The encoder velocities obtained from the FPGA
+    pub motor_encoders: [f32; 4],
+    /// The time from the last control test message to this message (us, saturating at `u16::MAX`)
+    pub delta: u16,
+}
+
+impl From<ControlTestMessage> for RtpControlTestMessage {
+    fn from(message: ControlTestMessage) -> Self {
+        Self {
+            gyro_z: message.gyro_z,
+            accel_x: message.accel_x,
+            accel_y: message.accel_y,
+            motor_encoders: message.motor_encoders,
+            delta: message.delta,
+        }
+    }
+}
+
+impl From<RtpControlTestMessage> for ControlTestMessage {
+    fn from(mirror: RtpControlTestMessage) -> Self {
+        Self {
+            gyro_z: mirror.gyro_z,
+            accel_x: mirror.accel_x,
+            accel_y: mirror.accel_y,
+            motor_encoders: mirror.motor_encoders,
+            delta: mirror.delta,
+        }
+    }
+}
+
+/// Pack a `ControlTestMessage` into `buffer`, returning `RTP_OK` or a negative error code
+///
+/// # Safety
+/// `message` must point to a valid `RtpControlTestMessage` and `buffer` to `buffer_len` writable Bytes
+#[no_mangle]
+pub unsafe extern "C" fn rtp_control_test_message_pack(
+    message: *const RtpControlTestMessage,
+    buffer: *mut u8,
+    buffer_len: usize,
+) -> i32 {
+    if message.is_null() || buffer.is_null() {
+        return RTP_ERROR_NULL_POINTER;
+    }
+    let buffer = core::slice::from_raw_parts_mut(buffer, buffer_len);
+    status(ControlTestMessage::from(*message).pack(buffer).map_err(RtpError::from))
+}
+
+/// Unpack a `ControlTestMessage` from `data` into `message`, returning `RTP_OK` or a negative error code
+///
+/// # Safety
+/// `data` must point to `data_len` readable Bytes and `message` to a writable `RtpControlTestMessage`
+#[no_mangle]
+pub unsafe extern "C" fn rtp_control_test_message_unpack(
+    data: *const u8,
+    data_len: usize,
+    message: *mut RtpControlTestMessage,
+) -> i32 {
+    if data.is_null() || message.is_null() {
+        return RTP_ERROR_NULL_POINTER;
+    }
+    let data = core::slice::from_raw_parts(data, data_len);
+    match ControlTestMessage::unpack(data) {
+        Ok(unpacked) => {
+            message.write(unpacked.into());
+            RTP_OK
+        }
+        Err(err) => status(Err(err.into())),
+    }
+}
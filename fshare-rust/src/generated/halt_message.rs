@@ -0,0 +1,51 @@
+// @generated by rtp-codegen from fshare-rust/schema/messages.toml.
+// Do not edit by hand, run `cargo run -p rtp-codegen` from fshare-rust instead.
+
+use ncomm_utils::packing::{Packable, PackingError};
+
+use crate::halt_message::{HaltMessage, HALT_MESSAGE_SIZE};
+use crate::error::check_len;
+use crate::{RtpError, Team};
+
+const _: () = assert!(HALT_MESSAGE_SIZE == 2);
+
+impl HaltMessage {
+    /// Unpack a HaltMessage, rejecting unknown enum values and robot ids that are out of range
+    /// instead of coercing them.
+    ///
+    /// Note: `Packable::unpack` remains lenient for backwards compatibility
+    pub fn unpack_strict(data: &[u8]) -> Result<Self, RtpError> {
+        check_len(data, HALT_MESSAGE_SIZE)?;
+
+        Ok(Self {
+            team: ((data[0] & (0b1 << 7)) >> 7).try_into()?,
+            halt: data[0] & (0b1 << 6) != 0,
+            sequence: data[1],
+        })
+    }
+}
+
+impl Packable for HaltMessage {
+    fn len() -> usize {
+        HALT_MESSAGE_SIZE
+    }
+
+    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
+        check_len(buffer, HALT_MESSAGE_SIZE)?;
+
+        buffer[0] = ((self.team as u8) & 0b1) << 7
+            | (self.halt as u8) << 6;
+        buffer[1] = self.sequence;
+        Ok(())
+    }
+
+    fn unpack(data: &[u8]) -> Result<Self, PackingError> {
+        check_len(data, HALT_MESSAGE_SIZE)?;
+
+        Ok(Self {
+            team: Team::try_from((data[0] & (0b1 << 7)) >> 7).unwrap_or(Team::Blue),
+            halt: data[0] & (0b1 << 6) != 0,
+            sequence: data[1],
+        })
+    }
+}
@@ -0,0 +1,35 @@
+// @generated by rtp-codegen from fshare-rust/schema/messages.toml.
+// Do not edit by hand, run `cargo run -p rtp-codegen` from fshare-rust instead.
+
+mod control_message;
+mod robot_status_message;
+mod halt_message;
+mod game_state;
+
+use crate::control_message::{Mode, ShootMode, TriggerMode};
+use crate::Team;
+use crate::game_state::GameState;
+
+const _: () = assert!(Team::Blue as u8 == 0);
+const _: () = assert!(Team::Yellow as u8 == 1);
+
+const _: () = assert!(TriggerMode::StandDown as u8 == 0);
+const _: () = assert!(TriggerMode::Immediate as u8 == 1);
+const _: () = assert!(TriggerMode::OnBreakBeam as u8 == 2);
+
+const _: () = assert!(ShootMode::Kick as u8 == 0);
+const _: () = assert!(ShootMode::Chip as u8 == 1);
+
+const _: () = assert!(Mode::Default as u8 == 0);
+const _: () = assert!(Mode::ImuTest as u8 == 1);
+const _: () = assert!(Mode::ReceiveBenchmark as u8 == 2);
+const _: () = assert!(Mode::SendBenchmark as u8 == 3);
+const _: () = assert!(Mode::ProgramKickOnBreakbeam as u8 == 4);
+const _: () = assert!(Mode::ProgramKicker as u8 == 5);
+const _: () = assert!(Mode::KickerTest as u8 == 6);
+const _: () = assert!(Mode::FpgaTest as u8 == 7);
+
+const _: () = assert!(GameState::Halt as u8 == 0);
+const _: () = assert!(GameState::Stop as u8 == 1);
+const _: () = assert!(GameState::Running as u8 == 2);
+const _: () = assert!(GameState::BallPlacement as u8 == 3);
@@ -0,0 +1,78 @@
+// @generated by rtp-codegen from fshare-rust/schema/messages.toml.
+// Do not edit by hand, run `cargo run -p rtp-codegen` from fshare-rust instead.
+
+use ncomm_utils::packing::{Packable, PackingError};
+
+use crate::robot_status_message::{RobotStatusMessage, ROBOT_STATUS_SIZE, BATTERY_SCALE_FACTOR};
+use crate::error::check_len;
+use crate::{RtpError, Team, ROBOTS_PER_TEAM};
+
+const _: () = assert!(ROBOT_STATUS_SIZE == 4);
+const _: () = assert!(BATTERY_SCALE_FACTOR == 0.09884);
+
+impl RobotStatusMessage {
+    /// Unpack a RobotStatusMessage, rejecting unknown enum values and robot ids that are out of range
+    /// instead of coercing them.
+    ///
+    /// Note: `Packable::unpack` remains lenient for backwards compatibility
+    pub fn unpack_strict(data: &[u8]) -> Result<Self, RtpError> {
+        check_len(data, ROBOT_STATUS_SIZE)?;
+
+        let robot_id = (data[0] & (0b1111 << 3)) >> 3;
+        if robot_id as usize >= ROBOTS_PER_TEAM {
+            return Err(RtpError::InvalidRobotId(robot_id));
+        }
+
+        Ok(Self {
+            team: ((data[0] & (0b1 << 7)) >> 7).try_into()?,
+            robot_id,
+            ball_sense_status: data[0] & (0b1 << 2) != 0,
+            kick_status: data[0] & (0b1 << 1) != 0,
+            kick_healthy: data[0] & 0b1 != 0,
+            battery_voltage: data[1],
+            motor_errors: (data[2] & (0b11111 << 3)) >> 3,
+            fpga_status: data[2] & (0b1 << 2) != 0,
+            halted: data[2] & (0b1 << 1) != 0,
+            sequence: data[3],
+        })
+    }
+}
+
+impl Packable for RobotStatusMessage {
+    fn len() -> usize {
+        ROBOT_STATUS_SIZE
+    }
+
+    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
+        check_len(buffer, ROBOT_STATUS_SIZE)?;
+
+        buffer[0] = ((self.team as u8) & 0b1) << 7
+            | (self.robot_id & 0b1111) << 3
+            | (self.ball_sense_status as u8) << 2
+            | (self.kick_status as u8) << 1
+            | self.kick_healthy as u8;
+        buffer[1] = self.battery_voltage;
+        buffer[2] = (self.motor_errors & 0b11111) << 3
+            | (self.fpga_status as u8) << 2
+            | (self.halted as u8) << 1;
+        buffer[3] = self.sequence;
+        Ok(())
+    }
+
+    fn unpack(data: &[u8]) -> Result<Self, PackingError> {
+        check_len(data, ROBOT_STATUS_SIZE)?;
+
+        Ok(Self {
+            team: Team::try_from((data[0] & (0b1 << 7)) >> 7).unwrap_or(Team::Blue),
+            robot_id: (data[0] & (0b1111 << 3)) >> 3,
+            ball_sense_status: data[0] & (0b1 << 2) != 0,
+            kick_status: data[0] & (0b1 << 1) != 0,
+            kick_healthy: data[0] & 0b1 != 0,
+            battery_voltage: data[1],
+            motor_errors: (data[2] & (0b11111 << 3)) >> 3,
+            fpga_status: data[2] & (0b1 << 2) != 0,
+            halted: data[2] & (0b1 << 1) != 0,
+            sequence: data[3],
+        })
+    }
+}
@@ -0,0 +1,150 @@
+//!
+//! The Halt Message is sent from the base station to every robot of a team at once to stop them
+//! (e.g. when the referee calls HALT) and to let them resume afterwards.
+//!
+//! Robots latch a halt until they receive a resume for their team, so a single halt that arrives
+//! is enough to stop a robot even if the `ControlMessage`s after it keep moving it.  Whether a
+//! robot is halted is reported back in `RobotStatusMessage::halted`.
+//!
+//! Halt messages are sent in an envelope to the team's `radio_addresses::halt_address`, which
+//! every robot of the team listens on, so they have to be sent without acknowledgements and
+//! should be repeated a few times.  Repeats keep their sequence number and every new halt or
+//! resume gets the next one: the `HaltLatch` of a robot ignores halt messages whose sequence
+//! number is not newer than the last one it accepted, so a late repeat of a halt cannot halt a
+//! robot that was resumed.  A restarted base station counts from 0 again, so the latch accepts
+//! any sequence number once it has not accepted a halt message for its re-sync timeout.
+//!
+//! The packing code and layout table are generated from `schema/messages.toml`, edit the schema
+//! and run `cargo run -p rtp-codegen` to change the layout.
+//!
+
+use crate::sequence::SequenceLatch;
+use crate::Team;
+
+/// The size of a HaltMessage in Bytes as a constant.
+/// Note: This is tested in the tests so it can be trusted
+pub const HALT_MESSAGE_SIZE: usize = 2;
+
+/// Halts (or resumes) every robot of a team.
+///
+/// The HaltMessage has the following format:
+#[doc = include_str!("generated/halt_message_layout.txt")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HaltMessage {
+    /// Team of the robots (0: Blue) (1: Yellow)
+    pub team: Team,
+    /// True to halt the robots, false to let them resume
+    pub halt: bool,
+    /// Rolling sequence number of the halt message
+    pub sequence: u8,
+}
+
+impl HaltMessage {
+    /// Create a message halting every robot of a team
+    pub fn halt(team: Team, sequence: u8) -> Self {
+        Self {
+            team,
+            halt: true,
+            sequence,
+        }
+    }
+
+    /// Create a message letting every robot of a team resume
+    pub fn resume(team: Team, sequence: u8) -> Self {
+        Self {
+            team,
+            halt: false,
+            sequence,
+        }
+    }
+}
+
+/// The halt a robot is latched in by the halt messages of its team
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HaltLatch {
+    team: Team,
+    halted: bool,
+    sequence: SequenceLatch,
+}
+
+impl HaltLatch {
+    /// Create a latch for a robot of a team that is not halted.
+    ///
+    /// Once no halt message has been accepted for `resync_timeout` the next one is accepted
+    /// whatever its sequence number
+    pub const fn new(team: Team, resync_timeout: u64) -> Self {
+        Self {
+            team,
+            halted: false,
+            sequence: SequenceLatch::new(resync_timeout),
+        }
+    }
+
+    /// Latch (or release) a halt received at `now`.
+    ///
+    /// Halt messages for other teams and repeated or late halt messages (whose sequence number is
+    /// not newer than the last one accepted) are ignored (returning false)
+    pub fn receive(&mut self, message: &HaltMessage, now: u64) -> bool {
+        if message.team != self.team || !self.sequence.accept(message.sequence, now) {
+            return false;
+        }
+        self.halted = message.halt;
+        true
+    }
+
+    /// Is the robot latched in a halt
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unusual_byte_groupings)]
+mod tests {
+    use super::*;
+
+    use ncomm_utils::packing::Packable;
+
+    /// Test that halt and resume messages pack to the layout in the schema and unpack back
+    #[test]
+    fn test_pack_and_unpack() {
+        let mut buffer = [0u8; HALT_MESSAGE_SIZE];
+        HaltMessage::halt(Team::Yellow, 42)
+            .pack(&mut buffer)
+            .unwrap();
+        assert_eq!(buffer, [0b1_1_000000, 42]);
+        assert_eq!(
+            HaltMessage::unpack(&buffer).unwrap(),
+            HaltMessage::halt(Team::Yellow, 42)
+        );
+
+        HaltMessage::resume(Team::Blue, 7)
+            .pack(&mut buffer)
+            .unwrap();
+        assert_eq!(buffer, [0b0_0_000000, 7]);
+        assert_eq!(
+            HaltMessage::unpack(&buffer).unwrap(),
+            HaltMessage::resume(Team::Blue, 7)
+        );
+    }
+
+    /// Test that a halt is latched until a newer resume and that a late halt is ignored until
+    /// the latch re-syncs
+    #[test]
+    fn test_halt_latch() {
+        let mut latch = HaltLatch::new(Team::Blue, 1_000);
+        assert!(!latch.receive(&HaltMessage::halt(Team::Yellow, 0), 0));
+        assert!(latch.receive(&HaltMessage::halt(Team::Blue, 10), 0));
+        assert!(latch.is_halted());
+        assert!(latch.receive(&HaltMessage::resume(Team::Blue, 11), 100));
+        assert!(!latch.receive(&HaltMessage::halt(Team::Blue, 10), 200));
+        assert!(!latch.is_halted());
+
+        // a restarted base station is obeyed after the re-sync timeout
+        assert!(!latch.receive(&HaltMessage::halt(Team::Blue, 0), 1_000));
+        assert!(latch.receive(&HaltMessage::halt(Team::Blue, 0), 1_101));
+        assert!(latch.is_halted());
+    }
+}
@@ -3,55 +3,31 @@
 //! programmer operation
 //! 
 
-use ncomm_utils::packing::{Packable, PackingError};
+use crate::Packable;
 
-/// The size of a Kicker Program Message
-pub const KICKER_PROGRAM_MESSAGE: usize = 5;
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Packable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[packable(size = KICKER_PROGRAM_MESSAGE)]
 /// Messages sent from the robot when the kicker is being programmed
 pub struct KickerProgramMessage {
     /// Is the kicker being programmed with kick on breakbeam
+    #[packable(offset = 3)]
     pub kick_on_breakbeam: bool,
     /// Is the kicker finished programming
+    #[packable(offset = 7)]
     pub finished: bool, 
     /// The current page being programmed
+    #[packable(offset = 8)]
     pub page: u32,
 }
 
-impl Packable for KickerProgramMessage {
-    fn len() -> usize {
-        KICKER_PROGRAM_MESSAGE
-    }
-
-    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
-        if buffer.len() < KICKER_PROGRAM_MESSAGE {
-            return Err(PackingError::InvalidBufferSize);
-        }
-
-        buffer[0] = (self.kick_on_breakbeam as u8) << 4 | (self.finished as u8);
-        buffer[1..5].copy_from_slice(&self.page.to_le_bytes());
-
-        Ok(())
-    }
-
-    fn unpack(data: &[u8]) -> Result<Self, PackingError> {
-        if data.len() < KICKER_PROGRAM_MESSAGE {
-            return Err(PackingError::InvalidBufferSize);
-        }
-
-        Ok(Self {
-            kick_on_breakbeam: data[0] & 0b1 << 4 != 0,
-            finished: data[0] & 0b1 != 0,
-            page: u32::from_le_bytes(data[1..5].try_into().unwrap()),
-        })
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use ncomm_utils::packing::Packable;
+
     /// Test that a kicker program message can be packed and unpacked
     #[test]
     fn test_kicker_program_message_pack_and_unpack() {
@@ -3,68 +3,49 @@
 //! running in Kicker Testing Mode
 //! 
 
-use ncomm_utils::packing::{Packable, PackingError};
+use crate::Packable;
 
-/// The size of a Kicker Testing Message
-pub const KICKER_TESTING_SIZE: usize = 2;
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Packable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[packable(size = KICKER_TESTING_SIZE)]
 /// Messages sent from the robot while testing the kicker
+///
+/// The packed format of this message is as follows:
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
+/// |    7    |    6    |    5    |    4    |    3    |    2    |    1    |    0    |
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
+/// | unused                      | k_immed | k_on_bs | kicking | b_sense | healthy |
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
+/// | voltage                                                                       |
+/// +---------+---------+---------+---------+---------+---------+---------+---------+
 pub struct KickerTestingMessage {
     /// Is the kicker healthy
+    #[packable(offset = 7)]
     pub healthy: bool,
     /// Does the kicker have ball sense
+    #[packable(offset = 6)]
     pub ball_sense: bool,
     /// Is the kicker kicking (otherwise it is chipping)
+    #[packable(offset = 5)]
     pub kicking: bool,
     /// Should the kicker activate on ball sense
+    #[packable(offset = 4)]
     pub kick_on_ball_sense: bool,
     /// Should the kicker be kicking immediately
+    #[packable(offset = 3)]
     pub kick_immediately: bool,
     /// The current voltage of the kicker
+    #[packable(offset = 8)]
     pub voltage: u8,
 }
 
-impl Packable for KickerTestingMessage {
-    fn len() -> usize {
-        KICKER_TESTING_SIZE
-    }
-
-    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
-        if buffer.len() < KICKER_TESTING_SIZE {
-            return Err(PackingError::InvalidBufferSize);
-        }
-
-        buffer[0] = (self.healthy as u8) |
-            (self.ball_sense as u8) << 1 |
-            (self.kicking as u8) << 2 |
-            (self.kick_on_ball_sense as u8) << 3 |
-            (self.kick_immediately as u8) << 4;
-        buffer[1] = self.voltage;
-
-        Ok(())
-    }
-
-    fn unpack(data: &[u8]) -> Result<Self, PackingError> {
-        if data.len() < KICKER_TESTING_SIZE {
-            return Err(PackingError::InvalidBufferSize);
-        }
-
-        Ok(Self {
-            ball_sense: data[0] & 0b1 != 0,
-            kicking: data[0] & 0b10 != 0,
-            healthy: data[0] & 0b100 != 0,
-            kick_on_ball_sense: data[0] & 0b1000 != 0,
-            kick_immediately: data[0] & 0b1_0000 != 0,
-            voltage: data[1],
-        })
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use ncomm_utils::packing::Packable;
+
     /// Test that a kicker testing message can be packed and unpacked
     #[test]
     fn test_kicker_testing_message_pack_and_unpack() {
@@ -89,4 +70,22 @@ mod tests {
             unpacked_message,
         )
     }
-}
\ No newline at end of file
+
+    /// Test that each flag is unpacked from the same bit it is packed into
+    #[test]
+    fn test_kicker_testing_message_flag_bits() {
+        let message = KickerTestingMessage {
+            healthy: true,
+            ball_sense: false,
+            kicking: false,
+            kick_on_ball_sense: true,
+            kick_immediately: false,
+            voltage: 0,
+        };
+
+        let mut buffer = [0u8; KICKER_TESTING_SIZE];
+        message.pack(&mut buffer).unwrap();
+        assert_eq!(buffer[0], 0b0000_1001);
+        assert_eq!(KickerTestingMessage::unpack(&buffer).unwrap(), message);
+    }
+}
@@ -6,12 +6,35 @@
 #![no_std]
 #![deny(missing_docs)]
 
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod error;
+pub use error::{InvalidDiscriminant, RtpError};
+
+pub use robojackets_robocup_rtp_derive::Packable;
+
 pub mod control_message;
-pub use control_message::{ControlMessage, ControlMessageBuilder, CONTROL_MESSAGE_SIZE};
+pub use control_message::{
+    ControlMessage, ControlMessageBuildError, ControlMessageBuilder, CONTROL_MESSAGE_SIZE,
+};
+
+pub mod fixed_point;
+pub use fixed_point::VelocityEncoding;
 
 pub mod robot_status_message;
 pub use robot_status_message::{RobotStatusMessage, RobotStatusMessageBuilder, ROBOT_STATUS_SIZE};
 
+pub mod halt_message;
+pub use halt_message::{HaltLatch, HaltMessage, HALT_MESSAGE_SIZE};
+
+pub mod game_state;
+pub use game_state::{GameState, GameStateLimiter, GameStateMessage, GAME_STATE_MESSAGE_SIZE};
+
+// Packable implementations generated from schema/messages.toml by rtp-codegen
+#[rustfmt::skip]
+mod generated;
+
 pub mod imu_test_message;
 
 pub mod kicker_program_message;
@@ -22,16 +45,66 @@ pub mod radio_benchmarks;
 
 pub mod control_test_message;
 
+pub mod envelope;
+pub use envelope::{MessageKind, RtpMessage};
+
+pub mod checksum;
+pub use checksum::Checked;
+
+pub mod broadcast;
+pub use broadcast::BroadcastFrame;
+
+pub mod sequence;
+pub use sequence::SequenceTracker;
+
+pub mod robot_registry;
+pub use robot_registry::RobotRegistry;
+
+pub mod pid;
+pub use pid::Pid;
+
+pub mod robot_model;
+pub use robot_model::{RobotGeometry, RobotModel};
+
+pub mod simulated_robot;
+pub use simulated_robot::{SimulatedRobot, SimulatedRobotConfig};
+
+pub mod command_watchdog;
+pub use command_watchdog::CommandWatchdog;
+
 pub mod radio_addresses;
 pub use radio_addresses::BASE_STATION_ADDRESSES;
 pub use radio_addresses::ROBOT_RADIO_ADDRESSES;
 
+pub mod radio_link;
+pub use radio_link::RadioLink;
+
+#[cfg(feature = "std")]
+pub mod radio_channel;
+
+#[cfg(feature = "nrf24")]
+pub mod nrf24;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "serde")]
+mod serde_impls;
+
+#[cfg(feature = "std")]
+pub mod radio_log;
+
 /// Constant used to select the blue team
 pub const BLUE_TEAM: usize = 0;
 /// Constant used to select the yellow team
 pub const YELLOW_TEAM: usize = 1;
 
+/// The number of robots on each team (robot ids range from 0 to ROBOTS_PER_TEAM - 1)
+pub const ROBOTS_PER_TEAM: usize = 6;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// The Team the Robots are on
 pub enum Team {
     /// Blue Team
@@ -40,9 +113,21 @@ pub enum Team {
     Yellow = 1,
 }
 
-impl Into<bool> for Team {
-    fn into(self) -> bool {
-        match self {
+impl TryFrom<u8> for Team {
+    type Error = InvalidDiscriminant;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Blue),
+            1 => Ok(Self::Yellow),
+            _ => Err(InvalidDiscriminant { field: "team", value }),
+        }
+    }
+}
+
+impl From<Team> for bool {
+    fn from(value: Team) -> Self {
+        match value {
             Team::Blue => false,
             Team::Yellow => true,
         }
@@ -0,0 +1,1026 @@
+//!
+//! A driver for the nRF24L01+ radios used by the robots and base stations, built on the
+//! `embedded-hal` 1.0 SPI, digital and delay traits.
+//!
+//! `Nrf24` configures the radio from an `Nrf24Config` (channel, data rate, output power, CRC,
+//! retransmission, auto acknowledgement and dynamic payloads), assigns the addresses from
+//! `radio_addresses` to its data pipes and implements `RadioLink`.
+//!
+//! Pipes 2 to 5 only have their own least significant Byte and share the other 4 Bytes of their
+//! address with pipe 1.  Pipe 0 has a full address of its own, but it also receives the
+//! acknowledgements for the address last sent to.  While sending, its address is temporarily
+//! replaced and it is restored when the radio starts listening again, so robots use it for their
+//! team's halt address.
+//!
+//! Every robot of a team listens on the halt (pipe 0) and broadcast (pipe 2) addresses, so
+//! `listen_as_robot` turns auto acknowledgement off for them.  Otherwise all robots would answer
+//! the same packet at once.
+//!
+//! Note: the addresses in `radio_addresses` are written most significant Byte first while the
+//! nRF24L01+ expects them least significant Byte first, so they are reversed when written.
+//!
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{self, OutputPin};
+use embedded_hal::spi::{self, SpiDevice};
+
+use crate::radio_addresses::{
+    base_station_address, broadcast_address, halt_address, robot_address, RadioAddress,
+};
+use crate::radio_link::{check_payload, LinkError, RadioLink, Received, MAX_PAYLOAD_SIZE};
+use crate::Team;
+
+/// The highest channel of the nRF24L01+ (2400 + 125 MHz)
+pub const MAX_CHANNEL: u8 = 125;
+
+/// The number of data pipes of the nRF24L01+
+pub const PIPES: usize = 6;
+
+/// How often `receive` checks for a packet while waiting (us)
+const RECEIVE_POLL_INTERVAL: u32 = 100;
+
+/// How often `send` checks whether a packet was sent (us)
+const SEND_POLL_INTERVAL: u32 = 10;
+
+/// The register map of the nRF24L01+
+mod register {
+    pub const CONFIG: u8 = 0x00;
+    pub const EN_AA: u8 = 0x01;
+    pub const EN_RXADDR: u8 = 0x02;
+    pub const SETUP_AW: u8 = 0x03;
+    pub const SETUP_RETR: u8 = 0x04;
+    pub const RF_CH: u8 = 0x05;
+    pub const RF_SETUP: u8 = 0x06;
+    pub const STATUS: u8 = 0x07;
+    pub const OBSERVE_TX: u8 = 0x08;
+    pub const RPD: u8 = 0x09;
+    pub const RX_ADDR_P0: u8 = 0x0A;
+    pub const RX_ADDR_P1: u8 = 0x0B;
+    pub const TX_ADDR: u8 = 0x10;
+    pub const RX_PW_P0: u8 = 0x11;
+    pub const DYNPD: u8 = 0x1C;
+    pub const FEATURE: u8 = 0x1D;
+}
+
+/// The SPI commands of the nRF24L01+
+mod command {
+    pub const R_REGISTER: u8 = 0x00;
+    pub const W_REGISTER: u8 = 0x20;
+    pub const R_RX_PL_WID: u8 = 0x60;
+    pub const R_RX_PAYLOAD: u8 = 0x61;
+    pub const W_TX_PAYLOAD: u8 = 0xA0;
+    pub const W_TX_PAYLOAD_NO_ACK: u8 = 0xB0;
+    pub const FLUSH_TX: u8 = 0xE1;
+    pub const FLUSH_RX: u8 = 0xE2;
+    pub const NOP: u8 = 0xFF;
+}
+
+const CONFIG_EN_CRC: u8 = 1 << 3;
+const CONFIG_CRCO: u8 = 1 << 2;
+const CONFIG_PWR_UP: u8 = 1 << 1;
+const CONFIG_PRIM_RX: u8 = 1 << 0;
+
+const STATUS_RX_DR: u8 = 1 << 6;
+const STATUS_TX_DS: u8 = 1 << 5;
+const STATUS_MAX_RT: u8 = 1 << 4;
+
+const RF_SETUP_RF_DR_LOW: u8 = 1 << 5;
+const RF_SETUP_RF_DR_HIGH: u8 = 1 << 3;
+
+const FEATURE_EN_DPL: u8 = 1 << 2;
+const FEATURE_EN_DYN_ACK: u8 = 1 << 0;
+
+/// Every pipe (for EN_AA, EN_RXADDR and DYNPD)
+const ALL_PIPES: u8 = 0b11_1111;
+
+/// The rate data is sent over the air
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataRate {
+    /// 250 kbps
+    Kbps250,
+    /// 1 Mbps
+    Mbps1,
+    /// 2 Mbps
+    Mbps2,
+}
+
+/// The output power of the radio
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerLevel {
+    /// -18 dBm
+    Min = 0b00,
+    /// -12 dBm
+    Low = 0b01,
+    /// -6 dBm
+    High = 0b10,
+    /// 0 dBm
+    Max = 0b11,
+}
+
+/// The length of the CRC appended to every packet
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Crc {
+    /// No CRC (the radio forces a 1 Byte CRC while auto acknowledgement is enabled)
+    Disabled,
+    /// 1 Byte CRC
+    OneByte,
+    /// 2 Byte CRC
+    TwoBytes,
+}
+
+/// The configuration of an nRF24L01+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Nrf24Config {
+    /// The RF channel (2400 + channel MHz) from 0 to `MAX_CHANNEL`
+    pub channel: u8,
+    /// The rate data is sent over the air
+    pub data_rate: DataRate,
+    /// The output power
+    pub power: PowerLevel,
+    /// The length of the CRC
+    pub crc: Crc,
+    /// Should received packets be acknowledged (and sent packets wait for acknowledgements)
+    pub auto_ack: bool,
+    /// The number of retransmissions of an unacknowledged packet (0 to 15)
+    pub retries: u8,
+    /// The delay before a retransmission in steps of 250 us (0: 250 us to 15: 4000 us)
+    pub retry_delay: u8,
+    /// Should packets carry their own length (otherwise every packet is `payload_size` long)
+    pub dynamic_payloads: bool,
+    /// The length of every packet without dynamic payloads (1 to `MAX_PAYLOAD_SIZE`)
+    pub payload_size: u8,
+}
+
+impl Default for Nrf24Config {
+    fn default() -> Self {
+        Self {
+            channel: 76,
+            data_rate: DataRate::Mbps2,
+            power: PowerLevel::Max,
+            crc: Crc::TwoBytes,
+            auto_ack: true,
+            retries: 3,
+            retry_delay: 0,
+            dynamic_payloads: true,
+            payload_size: MAX_PAYLOAD_SIZE as u8,
+        }
+    }
+}
+
+/// An error from the nRF24L01+ driver
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Nrf24Error<S, P> {
+    /// Communicating over SPI failed
+    Spi(S),
+    /// Setting the CE pin failed
+    Pin(P),
+    /// The channel is larger than `MAX_CHANNEL`
+    InvalidChannel(u8),
+    /// The payload size is 0 or larger than `MAX_PAYLOAD_SIZE`
+    InvalidPayloadSize(u8),
+    /// The pipe does not exist
+    InvalidPipe(u8),
+    /// The address of pipes 2 to 5 must share the upper 4 Bytes of the address of pipe 1
+    PipeAddressMismatch(u8),
+    /// The robot id is larger than the number of robots on a team
+    InvalidRobotId(u8),
+    /// A packet should be acknowledged but auto acknowledgement is disabled in the config
+    AutoAckDisabled,
+    /// The radio never reported whether a packet was sent (it is probably not connected)
+    Timeout,
+}
+
+/// The `Nrf24Error` of a driver using the SPI device `SPI` and CE pin `CE`
+pub type Nrf24DriverError<SPI, CE> =
+    Nrf24Error<<SPI as spi::ErrorType>::Error, <CE as digital::ErrorType>::Error>;
+
+/// An nRF24L01+ connected over SPI with its CE pin
+pub struct Nrf24<SPI, CE, D> {
+    spi: SPI,
+    ce: CE,
+    delay: D,
+    config: Nrf24Config,
+    /// The address every pipe listens on
+    pipes: [Option<RadioAddress>; PIPES],
+    /// The pipes whose address other radios listen on as well, which never acknowledge packets
+    shared_pipes: u8,
+    /// The addresses in TX_ADDR and RX_ADDR_P0, which are overwritten while sending
+    tx_address: Option<RadioAddress>,
+    rx_address_0: Option<RadioAddress>,
+    listening: bool,
+}
+
+impl<SPI: SpiDevice, CE: OutputPin, D: DelayNs> Nrf24<SPI, CE, D> {
+    /// Configure a radio and power it up
+    pub fn new(
+        spi: SPI,
+        ce: CE,
+        delay: D,
+        config: Nrf24Config,
+    ) -> Result<Self, Nrf24DriverError<SPI, CE>> {
+        let mut radio = Self {
+            spi,
+            ce,
+            delay,
+            config,
+            pipes: [None; PIPES],
+            shared_pipes: 0,
+            tx_address: None,
+            rx_address_0: None,
+            listening: false,
+        };
+        radio.configure(config)?;
+        Ok(radio)
+    }
+
+    /// Reconfigure the radio, leaving it powered up in standby.  The pipes keep their addresses
+    pub fn configure(&mut self, config: Nrf24Config) -> Result<(), Nrf24DriverError<SPI, CE>> {
+        if config.channel > MAX_CHANNEL {
+            return Err(Nrf24Error::InvalidChannel(config.channel));
+        }
+        if config.payload_size == 0 || config.payload_size as usize > MAX_PAYLOAD_SIZE {
+            return Err(Nrf24Error::InvalidPayloadSize(config.payload_size));
+        }
+        self.ce.set_low().map_err(Nrf24Error::Pin)?;
+        self.listening = false;
+        self.config = config;
+
+        let data_rate = match config.data_rate {
+            DataRate::Kbps250 => RF_SETUP_RF_DR_LOW,
+            DataRate::Mbps1 => 0,
+            DataRate::Mbps2 => RF_SETUP_RF_DR_HIGH,
+        };
+        let dynamic_payloads = if config.dynamic_payloads {
+            ALL_PIPES
+        } else {
+            0
+        };
+        let feature = if config.dynamic_payloads {
+            FEATURE_EN_DPL | FEATURE_EN_DYN_ACK
+        } else {
+            FEATURE_EN_DYN_ACK
+        };
+
+        // 5 Byte addresses
+        self.write_register(register::SETUP_AW, 0b11)?;
+        self.write_register(
+            register::SETUP_RETR,
+            (config.retry_delay.min(15) << 4) | config.retries.min(15),
+        )?;
+        self.write_register(register::RF_CH, config.channel)?;
+        self.write_register(register::RF_SETUP, data_rate | (config.power as u8) << 1)?;
+        self.write_register(register::FEATURE, feature)?;
+        self.write_register(register::DYNPD, dynamic_payloads)?;
+        self.write_register(register::EN_AA, self.auto_ack_pipes())?;
+        for pipe in 0..PIPES as u8 {
+            self.write_register(register::RX_PW_P0 + pipe, config.payload_size)?;
+        }
+        self.write_register(register::EN_RXADDR, self.reading_pipes())?;
+
+        self.command(&mut [command::FLUSH_TX])?;
+        self.command(&mut [command::FLUSH_RX])?;
+        self.clear_status()?;
+        self.write_register(register::CONFIG, self.config_register(false))?;
+        // power down -> standby takes 1.5 ms
+        self.delay.delay_us(1500);
+        Ok(())
+    }
+
+    /// The configuration of the radio
+    pub fn config(&self) -> Nrf24Config {
+        self.config
+    }
+
+    /// Change the RF channel
+    pub fn set_channel(&mut self, channel: u8) -> Result<(), Nrf24DriverError<SPI, CE>> {
+        if channel > MAX_CHANNEL {
+            return Err(Nrf24Error::InvalidChannel(channel));
+        }
+        self.config.channel = channel;
+        self.write_register(register::RF_CH, channel)
+    }
+
+    /// Listen on `address` with one of the pipes
+    pub fn open_reading_pipe(
+        &mut self,
+        pipe: u8,
+        address: RadioAddress,
+    ) -> Result<(), Nrf24DriverError<SPI, CE>> {
+        if pipe as usize >= PIPES {
+            return Err(Nrf24Error::InvalidPipe(pipe));
+        }
+        if pipe == 0 {
+            self.write_address(register::RX_ADDR_P0, address)?;
+            self.rx_address_0 = Some(address);
+        } else if pipe == 1 {
+            self.write_address(register::RX_ADDR_P1, address)?;
+        } else {
+            match self.pipes[1] {
+                Some(pipe_1) if pipe_1[..4] == address[..4] => (),
+                _ => return Err(Nrf24Error::PipeAddressMismatch(pipe)),
+            }
+            self.write_register(register::RX_ADDR_P0 + pipe, address[4])?;
+        }
+
+        self.pipes[pipe as usize] = Some(address);
+        self.set_shared(pipe, false)?;
+        self.write_register(register::EN_RXADDR, self.reading_pipes())
+    }
+
+    /// Stop listening with one of the pipes
+    pub fn close_reading_pipe(&mut self, pipe: u8) -> Result<(), Nrf24DriverError<SPI, CE>> {
+        if pipe as usize >= PIPES {
+            return Err(Nrf24Error::InvalidPipe(pipe));
+        }
+        self.pipes[pipe as usize] = None;
+        self.set_shared(pipe, false)?;
+        self.write_register(register::EN_RXADDR, self.reading_pipes())
+    }
+
+    /// Listen as a robot: on the robot's address (pipe 1), its team's broadcast
+    /// address (pipe 2) and its team's halt address (pipe 0).
+    ///
+    /// Note: only the robot's own address is acknowledged, the broadcast and halt addresses are
+    /// shared by the whole team
+    pub fn listen_as_robot(
+        &mut self,
+        team: Team,
+        robot_id: u8,
+    ) -> Result<(), Nrf24DriverError<SPI, CE>> {
+        let address =
+            robot_address(team, robot_id).map_err(|_| Nrf24Error::InvalidRobotId(robot_id))?;
+        self.open_reading_pipe(1, address)?;
+        self.open_reading_pipe(2, broadcast_address(team))?;
+        self.open_reading_pipe(0, halt_address(team))?;
+        self.set_shared(2, true)?;
+        self.set_shared(0, true)
+    }
+
+    /// Listen as a team's base station (on pipe 1)
+    pub fn listen_as_base_station(&mut self, team: Team) -> Result<(), Nrf24DriverError<SPI, CE>> {
+        self.open_reading_pipe(1, base_station_address(team))
+    }
+
+    /// Switch to receive mode
+    pub fn start_listening(&mut self) -> Result<(), Nrf24DriverError<SPI, CE>> {
+        // sending borrows pipe 0 for acknowledgements
+        if let Some(address) = self.pipes[0] {
+            if self.rx_address_0 != Some(address) {
+                self.write_address(register::RX_ADDR_P0, address)?;
+                self.rx_address_0 = Some(address);
+            }
+        }
+        // and acknowledgements (which pipe 0 may not send while listening)
+        if self.shared_pipes & 1 != 0 {
+            self.write_register(register::EN_AA, self.auto_ack_pipes())?;
+        }
+        self.write_register(register::EN_RXADDR, self.reading_pipes())?;
+        self.clear_status()?;
+        self.write_register(register::CONFIG, self.config_register(true))?;
+        self.ce.set_high().map_err(Nrf24Error::Pin)?;
+        // standby -> receive mode takes 130 us
+        self.delay.delay_us(130);
+        self.listening = true;
+        Ok(())
+    }
+
+    /// Switch back to standby
+    pub fn stop_listening(&mut self) -> Result<(), Nrf24DriverError<SPI, CE>> {
+        self.ce.set_low().map_err(Nrf24Error::Pin)?;
+        self.write_register(register::CONFIG, self.config_register(false))?;
+        self.listening = false;
+        Ok(())
+    }
+
+    /// Did the radio receive a signal stronger than -64 dBm on its channel since it started
+    /// listening (Received Power Detector)
+    pub fn received_power_detected(&mut self) -> Result<bool, Nrf24DriverError<SPI, CE>> {
+        Ok(self.read_register(register::RPD)? & 1 == 1)
+    }
+
+    /// The number of packets lost (since the channel was last set) and the number of
+    /// retransmissions of the last packet sent
+    pub fn observe_tx(&mut self) -> Result<(u8, u8), Nrf24DriverError<SPI, CE>> {
+        let observe_tx = self.read_register(register::OBSERVE_TX)?;
+        Ok((observe_tx >> 4, observe_tx & 0x0F))
+    }
+
+    /// Give back the SPI device, CE pin and delay
+    pub fn release(self) -> (SPI, CE, D) {
+        (self.spi, self.ce, self.delay)
+    }
+
+    /// The value of the CONFIG register (powered up, receiving or not)
+    fn config_register(&self, receive: bool) -> u8 {
+        let crc = match self.config.crc {
+            Crc::Disabled => 0,
+            Crc::OneByte => CONFIG_EN_CRC,
+            Crc::TwoBytes => CONFIG_EN_CRC | CONFIG_CRCO,
+        };
+        crc | CONFIG_PWR_UP | if receive { CONFIG_PRIM_RX } else { 0 }
+    }
+
+    /// The EN_AA bits of the pipes that acknowledge received packets
+    fn auto_ack_pipes(&self) -> u8 {
+        if self.config.auto_ack {
+            ALL_PIPES & !self.shared_pipes
+        } else {
+            0
+        }
+    }
+
+    /// Mark a pipe as shared with other radios (or not) and update which pipes acknowledge
+    fn set_shared(&mut self, pipe: u8, shared: bool) -> Result<(), Nrf24DriverError<SPI, CE>> {
+        let shared_pipes = if shared {
+            self.shared_pipes | 1 << pipe
+        } else {
+            self.shared_pipes & !(1 << pipe)
+        };
+        if shared_pipes != self.shared_pipes {
+            self.shared_pipes = shared_pipes;
+            self.write_register(register::EN_AA, self.auto_ack_pipes())?;
+        }
+        Ok(())
+    }
+
+    /// The EN_RXADDR bits of the pipes with an address
+    fn reading_pipes(&self) -> u8 {
+        (0..PIPES)
+            .filter(|pipe| self.pipes[*pipe].is_some())
+            .fold(0, |pipes, pipe| pipes | 1 << pipe)
+    }
+
+    /// Send a command, returning the STATUS register shifted out with it
+    fn command(&mut self, buffer: &mut [u8]) -> Result<u8, Nrf24DriverError<SPI, CE>> {
+        self.spi
+            .transfer_in_place(buffer)
+            .map_err(Nrf24Error::Spi)?;
+        Ok(buffer[0])
+    }
+
+    fn status(&mut self) -> Result<u8, Nrf24DriverError<SPI, CE>> {
+        self.command(&mut [command::NOP])
+    }
+
+    fn clear_status(&mut self) -> Result<(), Nrf24DriverError<SPI, CE>> {
+        self.write_register(
+            register::STATUS,
+            STATUS_RX_DR | STATUS_TX_DS | STATUS_MAX_RT,
+        )
+    }
+
+    fn read_register(&mut self, register: u8) -> Result<u8, Nrf24DriverError<SPI, CE>> {
+        let mut buffer = [command::R_REGISTER | register, 0];
+        self.command(&mut buffer)?;
+        Ok(buffer[1])
+    }
+
+    fn write_register(&mut self, register: u8, value: u8) -> Result<(), Nrf24DriverError<SPI, CE>> {
+        self.command(&mut [command::W_REGISTER | register, value])?;
+        Ok(())
+    }
+
+    /// Write a 5 Byte address (least significant Byte first)
+    fn write_address(
+        &mut self,
+        register: u8,
+        address: RadioAddress,
+    ) -> Result<(), Nrf24DriverError<SPI, CE>> {
+        let mut buffer = [command::W_REGISTER | register, 0, 0, 0, 0, 0];
+        for (byte, value) in buffer[1..].iter_mut().zip(address.iter().rev()) {
+            *byte = *value;
+        }
+        self.command(&mut buffer)?;
+        Ok(())
+    }
+
+    /// The pipe the next packet in the receive FIFO arrived on
+    fn received_pipe(&mut self) -> Result<Option<usize>, Nrf24DriverError<SPI, CE>> {
+        let pipe = (self.status()? >> 1) & 0b111;
+        Ok(if (pipe as usize) < PIPES {
+            Some(pipe as usize)
+        } else {
+            None
+        })
+    }
+
+    /// Wait for the radio to report that the packet in the transmit FIFO was sent, returning
+    /// whether it was acknowledged
+    fn wait_for_send(&mut self) -> Result<bool, Nrf24DriverError<SPI, CE>> {
+        // every attempt takes at most the retry delay plus the time on air of a packet and its
+        // acknowledgement (well under 1 ms at 250 kbps)
+        let attempts = self.config.retries.min(15) as u32 + 1;
+        let timeout = attempts * (250 * (self.config.retry_delay.min(15) as u32 + 1) + 1000);
+        for _ in 0..timeout.div_ceil(SEND_POLL_INTERVAL) {
+            let status = self.status()?;
+            if status & STATUS_TX_DS != 0 {
+                return Ok(true);
+            }
+            if status & STATUS_MAX_RT != 0 {
+                return Ok(false);
+            }
+            self.delay.delay_us(SEND_POLL_INTERVAL);
+        }
+        Err(Nrf24Error::Timeout)
+    }
+}
+
+impl<SPI: SpiDevice, CE: OutputPin, D: DelayNs> RadioLink for Nrf24<SPI, CE, D> {
+    type Error = Nrf24DriverError<SPI, CE>;
+
+    fn send(
+        &mut self,
+        address: RadioAddress,
+        data: &[u8],
+        ack: bool,
+    ) -> Result<(), LinkError<Self::Error>> {
+        check_payload(data)?;
+        if ack && !self.config.auto_ack {
+            return Err(LinkError::Radio(Nrf24Error::AutoAckDisabled));
+        }
+        // without dynamic payloads shorter packets are padded but longer ones would be cut off
+        if !self.config.dynamic_payloads && data.len() > self.config.payload_size as usize {
+            return Err(LinkError::PayloadTooLarge(data.len()));
+        }
+        let was_listening = self.listening;
+        if was_listening {
+            self.stop_listening().map_err(LinkError::Radio)?;
+        }
+
+        if self.tx_address != Some(address) {
+            self.write_address(register::TX_ADDR, address)
+                .map_err(LinkError::Radio)?;
+            self.tx_address = Some(address);
+        }
+        // the acknowledgement comes back from `address` on pipe 0
+        if self.rx_address_0 != Some(address) {
+            self.write_address(register::RX_ADDR_P0, address)
+                .map_err(LinkError::Radio)?;
+            self.rx_address_0 = Some(address);
+        }
+        self.write_register(register::EN_RXADDR, self.reading_pipes() | 1)
+            .map_err(LinkError::Radio)?;
+        // acknowledgements are only received with auto acknowledgement on pipe 0
+        if ack && self.shared_pipes & 1 != 0 {
+            self.write_register(register::EN_AA, self.auto_ack_pipes() | 1)
+                .map_err(LinkError::Radio)?;
+        }
+
+        let len = if self.config.dynamic_payloads {
+            data.len()
+        } else {
+            self.config.payload_size as usize
+        };
+        let mut buffer = [0u8; MAX_PAYLOAD_SIZE + 1];
+        buffer[0] = if ack {
+            command::W_TX_PAYLOAD
+        } else {
+            command::W_TX_PAYLOAD_NO_ACK
+        };
+        buffer[1..][..data.len()].copy_from_slice(data);
+        self.clear_status().map_err(LinkError::Radio)?;
+        self.command(&mut buffer[..len + 1])
+            .map_err(LinkError::Radio)?;
+
+        // a CE pulse of at least 10 us sends the packet
+        self.ce
+            .set_high()
+            .map_err(|error| LinkError::Radio(Nrf24Error::Pin(error)))?;
+        self.delay.delay_us(15);
+        self.ce
+            .set_low()
+            .map_err(|error| LinkError::Radio(Nrf24Error::Pin(error)))?;
+        let sent = self.wait_for_send();
+        if !matches!(sent, Ok(true)) {
+            self.command(&mut [command::FLUSH_TX])
+                .map_err(LinkError::Radio)?;
+        }
+        self.clear_status().map_err(LinkError::Radio)?;
+
+        if was_listening {
+            self.start_listening().map_err(LinkError::Radio)?;
+        }
+        match sent.map_err(LinkError::Radio)? {
+            true => Ok(()),
+            false => Err(LinkError::NotAcknowledged),
+        }
+    }
+
+    fn receive(
+        &mut self,
+        buffer: &mut [u8],
+        timeout: u64,
+    ) -> Result<Option<Received>, LinkError<Self::Error>> {
+        if !self.listening {
+            self.start_listening().map_err(LinkError::Radio)?;
+        }
+
+        let mut waited = 0;
+        loop {
+            if let Some(pipe) = self.received_pipe().map_err(LinkError::Radio)? {
+                let len = if self.config.dynamic_payloads {
+                    let mut width = [command::R_RX_PL_WID, 0];
+                    self.command(&mut width).map_err(LinkError::Radio)?;
+                    width[1] as usize
+                } else {
+                    self.config.payload_size as usize
+                };
+                if len > MAX_PAYLOAD_SIZE {
+                    // the datasheet requires flushing a corrupt payload width
+                    self.command(&mut [command::FLUSH_RX])
+                        .map_err(LinkError::Radio)?;
+                    continue;
+                }
+
+                let mut payload = [0u8; MAX_PAYLOAD_SIZE + 1];
+                payload[0] = command::R_RX_PAYLOAD;
+                self.command(&mut payload[..len + 1])
+                    .map_err(LinkError::Radio)?;
+                self.write_register(register::STATUS, STATUS_RX_DR)
+                    .map_err(LinkError::Radio)?;
+                if buffer.len() < len {
+                    return Err(LinkError::BufferTooShort {
+                        expected: len,
+                        actual: buffer.len(),
+                    });
+                }
+                buffer[..len].copy_from_slice(&payload[1..len + 1]);
+                return Ok(Some(Received {
+                    address: self.pipes[pipe].unwrap_or_default(),
+                    len,
+                }));
+            }
+
+            if waited >= timeout {
+                return Ok(None);
+            }
+            let step = (timeout - waited).min(RECEIVE_POLL_INTERVAL as u64);
+            self.delay.delay_us(step as u32);
+            waited += step;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+
+    use core::convert::Infallible;
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+    use std::vec::Vec;
+
+    use embedded_hal::spi::Operation;
+
+    use crate::radio_addresses::{
+        ROBOT_RADIO_ADDRESSES, TEAM_BROADCAST_ADDRESSES, TEAM_HALT_ADDRESSES,
+    };
+
+    /// A register level model of an nRF24L01+ (only what the driver uses)
+    #[derive(Default)]
+    struct Chip {
+        registers: [u8; 0x1E],
+        /// RX_ADDR_P0, RX_ADDR_P1 and TX_ADDR (least significant Byte first)
+        rx_addr_p0: [u8; 5],
+        rx_addr_p1: [u8; 5],
+        tx_addr: [u8; 5],
+        flags: u8,
+        ce: bool,
+        rx_fifo: VecDeque<(u8, Vec<u8>)>,
+        /// Every packet sent with the address it was sent to and whether it wanted an ack
+        sent: Vec<(RadioAddress, Vec<u8>, bool)>,
+        /// Do receivers acknowledge packets
+        acknowledge: bool,
+        rpd: bool,
+    }
+
+    impl Chip {
+        fn status(&self) -> u8 {
+            let pipe = self.rx_fifo.front().map_or(0b111, |(pipe, _)| *pipe);
+            self.flags | pipe << 1
+        }
+
+        /// Handle a single SPI transfer
+        fn transfer(&mut self, buffer: &mut [u8]) {
+            let command = buffer[0];
+            buffer[0] = self.status();
+            let data = &mut buffer[1..];
+            match command {
+                command::R_RX_PL_WID => {
+                    data[0] = self
+                        .rx_fifo
+                        .front()
+                        .map_or(0, |(_, packet)| packet.len() as u8)
+                }
+                command::R_RX_PAYLOAD => {
+                    let (_, packet) = self.rx_fifo.pop_front().unwrap();
+                    data.copy_from_slice(&packet[..data.len()]);
+                }
+                command::W_TX_PAYLOAD | command::W_TX_PAYLOAD_NO_ACK => {
+                    let mut address = self.tx_addr;
+                    address.reverse();
+                    let ack = command == command::W_TX_PAYLOAD
+                        && self.registers[register::EN_AA as usize] & 1 != 0;
+                    // acknowledgements are only received if pipe 0 listens on TX_ADDR
+                    let acknowledged = self.acknowledge
+                        && self.rx_addr_p0 == self.tx_addr
+                        && self.registers[register::EN_RXADDR as usize] & 1 != 0;
+                    self.sent.push((address, data.to_vec(), ack));
+                    self.flags |= if !ack || acknowledged {
+                        STATUS_TX_DS
+                    } else {
+                        STATUS_MAX_RT
+                    };
+                }
+                command::FLUSH_TX | command::NOP => (),
+                command::FLUSH_RX => self.rx_fifo.clear(),
+                _ if command & 0xE0 == command::R_REGISTER => match command & 0x1F {
+                    register::RX_ADDR_P0 => data.copy_from_slice(&self.rx_addr_p0),
+                    register::RX_ADDR_P1 => data.copy_from_slice(&self.rx_addr_p1),
+                    register::TX_ADDR => data.copy_from_slice(&self.tx_addr),
+                    register::STATUS => data[0] = self.status(),
+                    register::RPD => data[0] = self.rpd as u8,
+                    register => data[0] = self.registers[register as usize],
+                },
+                _ if command & 0xE0 == command::W_REGISTER => match command & 0x1F {
+                    register::RX_ADDR_P0 => self.rx_addr_p0.copy_from_slice(data),
+                    register::RX_ADDR_P1 => self.rx_addr_p1.copy_from_slice(data),
+                    register::TX_ADDR => self.tx_addr.copy_from_slice(data),
+                    register::STATUS => self.flags &= !data[0],
+                    register => self.registers[register as usize] = data[0],
+                },
+                _ => panic!("unexpected command {:#04x}", command),
+            }
+        }
+
+        /// A packet sent over the air to `address`, returning whether the radio received it
+        fn deliver(&mut self, address: RadioAddress, packet: &[u8]) -> bool {
+            let config = self.registers[register::CONFIG as usize];
+            if !self.ce || config & CONFIG_PRIM_RX == 0 {
+                return false;
+            }
+            let mut address = address;
+            address.reverse();
+            let pipe = (0..PIPES as u8).find(|pipe| {
+                let pipe_address = match pipe {
+                    0 => self.rx_addr_p0,
+                    1 => self.rx_addr_p1,
+                    _ => {
+                        let mut pipe_address = self.rx_addr_p1;
+                        pipe_address[0] = self.registers[(register::RX_ADDR_P0 + pipe) as usize];
+                        pipe_address
+                    }
+                };
+                self.registers[register::EN_RXADDR as usize] & 1 << pipe != 0
+                    && pipe_address == address
+            });
+            let Some(pipe) = pipe else {
+                return false;
+            };
+            self.rx_fifo.push_back((pipe, packet.to_vec()));
+            self.flags |= STATUS_RX_DR;
+            true
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct MockSpi(Rc<RefCell<Chip>>);
+
+    impl spi::ErrorType for MockSpi {
+        type Error = Infallible;
+    }
+
+    impl SpiDevice for MockSpi {
+        fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Infallible> {
+            for operation in operations {
+                match operation {
+                    Operation::TransferInPlace(buffer) => self.0.borrow_mut().transfer(buffer),
+                    _ => panic!("the driver only uses transfer_in_place"),
+                }
+            }
+            Ok(())
+        }
+    }
+
+    struct MockCe(Rc<RefCell<Chip>>);
+
+    impl digital::ErrorType for MockCe {
+        type Error = Infallible;
+    }
+
+    impl OutputPin for MockCe {
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            self.0.borrow_mut().ce = false;
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            self.0.borrow_mut().ce = true;
+            Ok(())
+        }
+    }
+
+    struct NoDelay;
+
+    impl DelayNs for NoDelay {
+        fn delay_ns(&mut self, _ns: u32) {}
+    }
+
+    type MockRadio = Nrf24<MockSpi, MockCe, NoDelay>;
+
+    fn mock_radio(config: Nrf24Config) -> (MockRadio, Rc<RefCell<Chip>>) {
+        let spi = MockSpi::default();
+        let chip = spi.0.clone();
+        let radio = Nrf24::new(spi, MockCe(chip.clone()), NoDelay, config).unwrap();
+        (radio, chip)
+    }
+
+    /// Test that the configuration is written into the registers of the radio
+    #[test]
+    fn test_nrf24_configure() {
+        let (mut radio, chip) = mock_radio(Nrf24Config::default());
+        {
+            let chip = chip.borrow();
+            let registers = &chip.registers;
+            assert_eq!(registers[register::CONFIG as usize], 0b0000_1110);
+            assert_eq!(registers[register::RF_CH as usize], 76);
+            assert_eq!(registers[register::RF_SETUP as usize], 0b0000_1110);
+            assert_eq!(registers[register::SETUP_AW as usize], 0b11);
+            assert_eq!(registers[register::SETUP_RETR as usize], 0x03);
+            assert_eq!(registers[register::EN_AA as usize], 0x3F);
+            assert_eq!(registers[register::DYNPD as usize], 0x3F);
+            assert_eq!(registers[register::FEATURE as usize], 0b101);
+        }
+
+        radio
+            .configure(Nrf24Config {
+                data_rate: DataRate::Kbps250,
+                power: PowerLevel::Min,
+                crc: Crc::OneByte,
+                auto_ack: false,
+                retries: 15,
+                retry_delay: 2,
+                dynamic_payloads: false,
+                payload_size: 11,
+                ..Default::default()
+            })
+            .unwrap();
+        radio.set_channel(MAX_CHANNEL).unwrap();
+        {
+            let chip = chip.borrow();
+            let registers = &chip.registers;
+            assert_eq!(registers[register::CONFIG as usize], 0b0000_1010);
+            assert_eq!(registers[register::RF_CH as usize], MAX_CHANNEL);
+            assert_eq!(registers[register::RF_SETUP as usize], 0b0010_0000);
+            assert_eq!(registers[register::SETUP_RETR as usize], 0x2F);
+            assert_eq!(registers[register::EN_AA as usize], 0);
+            assert_eq!(registers[register::DYNPD as usize], 0);
+            assert_eq!(registers[register::RX_PW_P0 as usize + 3], 11);
+        }
+
+        assert_eq!(
+            radio.set_channel(MAX_CHANNEL + 1),
+            Err(Nrf24Error::InvalidChannel(MAX_CHANNEL + 1))
+        );
+        assert_eq!(
+            radio.open_reading_pipe(6, [0; 5]),
+            Err(Nrf24Error::InvalidPipe(6))
+        );
+        assert_eq!(
+            radio.listen_as_robot(Team::Blue, 6),
+            Err(Nrf24Error::InvalidRobotId(6))
+        );
+    }
+
+    /// Test that a robot receives packets sent to its own address and its team's broadcast and
+    /// halt addresses (and nothing else), also after borrowing pipe 0 to send
+    #[test]
+    fn test_nrf24_receive() {
+        let (mut radio, chip) = mock_radio(Nrf24Config::default());
+        radio.listen_as_robot(Team::Yellow, 3).unwrap();
+        assert_eq!(chip.borrow().rx_addr_p1, [0xD4, 0xD5, 0xD5, 0xD5, 0xD5]);
+        // only the robot's own address is acknowledged
+        assert_eq!(chip.borrow().registers[register::EN_AA as usize], 0b11_1010);
+        assert_eq!(
+            radio.open_reading_pipe(3, base_station_address(Team::Yellow)),
+            Err(Nrf24Error::PipeAddressMismatch(3))
+        );
+
+        let mut buffer = [0u8; MAX_PAYLOAD_SIZE];
+        assert_eq!(radio.receive(&mut buffer, 1_000), Ok(None));
+
+        let robot = ROBOT_RADIO_ADDRESSES[1][3];
+        assert!(chip.borrow_mut().deliver(robot, &[1, 2, 3]));
+        assert!(chip
+            .borrow_mut()
+            .deliver(TEAM_BROADCAST_ADDRESSES[1], &[4; 20]));
+        assert!(!chip.borrow_mut().deliver(ROBOT_RADIO_ADDRESSES[1][4], &[5]));
+        assert!(!chip.borrow_mut().deliver(TEAM_BROADCAST_ADDRESSES[0], &[6]));
+
+        assert_eq!(
+            radio.receive(&mut buffer, 0),
+            Ok(Some(Received {
+                address: robot,
+                len: 3
+            }))
+        );
+        assert_eq!(buffer[..3], [1, 2, 3]);
+        assert_eq!(
+            radio.receive(&mut buffer, 0),
+            Ok(Some(Received {
+                address: TEAM_BROADCAST_ADDRESSES[1],
+                len: 20
+            }))
+        );
+        assert_eq!(radio.receive(&mut buffer, 0), Ok(None));
+
+        let halt = TEAM_HALT_ADDRESSES[1];
+        assert!(!chip.borrow_mut().deliver(TEAM_HALT_ADDRESSES[0], &[7]));
+        chip.borrow_mut().acknowledge = true;
+        radio
+            .send(base_station_address(Team::Yellow), &[8], true)
+            .unwrap();
+        assert_eq!(chip.borrow().rx_addr_p0, [0x5B, 0xA4, 0xA4, 0xA4, 0xA4]);
+        assert!(chip.borrow().sent[0].2);
+        assert_eq!(chip.borrow().registers[register::EN_AA as usize], 0b11_1010);
+        assert!(chip.borrow_mut().deliver(halt, &[9, 9]));
+        assert_eq!(
+            radio.receive(&mut buffer, 0),
+            Ok(Some(Received {
+                address: halt,
+                len: 2
+            }))
+        );
+
+        assert!(!radio.received_power_detected().unwrap());
+        chip.borrow_mut().rpd = true;
+        assert!(radio.received_power_detected().unwrap());
+    }
+
+    /// Test that packets are sent to the given address and that missing acknowledgements are
+    /// reported
+    #[test]
+    fn test_nrf24_send() {
+        let (mut radio, chip) = mock_radio(Nrf24Config::default());
+        radio.listen_as_base_station(Team::Blue).unwrap();
+        radio.start_listening().unwrap();
+
+        let robot = ROBOT_RADIO_ADDRESSES[0][1];
+        chip.borrow_mut().acknowledge = true;
+        assert_eq!(radio.send(robot, &[1, 2, 3], true), Ok(()));
+        chip.borrow_mut().acknowledge = false;
+        assert_eq!(
+            radio.send(robot, &[4], true),
+            Err(LinkError::NotAcknowledged)
+        );
+        assert_eq!(radio.send(robot, &[5], false), Ok(()));
+        assert_eq!(
+            radio.send(robot, &[0; MAX_PAYLOAD_SIZE + 1], false),
+            Err(LinkError::PayloadTooLarge(MAX_PAYLOAD_SIZE + 1))
+        );
+        assert_eq!(
+            chip.borrow().sent,
+            [
+                (robot, [1, 2, 3].to_vec(), true),
+                (robot, [4].to_vec(), true),
+                (robot, [5].to_vec(), false),
+            ]
+        );
+
+        // the radio goes back to listening after sending
+        assert!(chip
+            .borrow_mut()
+            .deliver(base_station_address(Team::Blue), &[7]));
+        let mut buffer = [0u8; MAX_PAYLOAD_SIZE];
+        assert_eq!(radio.receive(&mut buffer, 0).unwrap().unwrap().len, 1);
+
+        // without dynamic payloads every packet is padded to the payload size
+        let (mut radio, chip) = mock_radio(Nrf24Config {
+            dynamic_payloads: false,
+            payload_size: 4,
+            ..Default::default()
+        });
+        radio.send(robot, &[9], false).unwrap();
+        assert_eq!(chip.borrow().sent[0].1, [9, 0, 0, 0]);
+        // and longer packets are rejected instead of cut off
+        assert_eq!(
+            radio.send(robot, &[1, 2, 3, 4, 5], false),
+            Err(LinkError::PayloadTooLarge(5))
+        );
+        assert_eq!(chip.borrow().sent.len(), 1);
+
+        // acknowledgements cannot be requested without auto acknowledgement
+        let (mut radio, chip) = mock_radio(Nrf24Config {
+            auto_ack: false,
+            ..Default::default()
+        });
+        assert_eq!(
+            radio.send(robot, &[1], true),
+            Err(LinkError::Radio(Nrf24Error::AutoAckDisabled))
+        );
+        assert!(chip.borrow().sent.is_empty());
+    }
+}
@@ -0,0 +1,204 @@
+//!
+//! A PID controller with a windup ring buffer, an alpha filtered derivative and
+//! anti-windup when the output is saturated.
+//!
+//! This is a port of `Pid` from `src/pid.cpp` with identical semantics.  The only difference
+//! is that the windup buffer has a fixed capacity (`WINDUP_CAPACITY`) instead of living in a
+//! `std::vector`, so it can be used on `no_std` targets without an allocator.
+//!
+
+/// A PID controller whose integral can sum over at most `WINDUP_CAPACITY` past errors
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pid<const WINDUP_CAPACITY: usize> {
+    /// Proportional gain
+    pub kp: f32,
+    /// Integral gain
+    pub ki: f32,
+    /// Derivative gain
+    pub kd: f32,
+    /// Weight of the previous derivative in the alpha filtered derivative (0 disables the
+    /// filter)
+    pub deriv_alpha: f32,
+    /// The number of past errors summed into the integral (0 sums every error)
+    windup: usize,
+    /// The index of the oldest error in the windup buffer
+    windup_loc: usize,
+    /// The sum of the errors in the windup buffer
+    err_sum: f32,
+    /// The error from the last call to `run`
+    last_error: f32,
+    /// The (filtered) derivative from the last call to `run`
+    last_deriv: f32,
+    /// True if the output is saturated (in which case the integral stops accumulating)
+    saturated: bool,
+    /// Ring buffer of the last `windup` errors
+    old_err: [f32; WINDUP_CAPACITY],
+}
+
+impl<const WINDUP_CAPACITY: usize> Pid<WINDUP_CAPACITY> {
+    /// Create a new PID controller
+    pub fn new(kp: f32, ki: f32, kd: f32, windup: usize, deriv_alpha: f32) -> Self {
+        let mut pid = Self {
+            kp,
+            ki,
+            kd,
+            deriv_alpha,
+            windup: 0,
+            windup_loc: 0,
+            err_sum: 0.0,
+            last_error: 0.0,
+            last_deriv: 0.0,
+            saturated: false,
+            old_err: [0.0; WINDUP_CAPACITY],
+        };
+        pid.set_windup(windup);
+        pid
+    }
+
+    /// Run the controller for a given error, returning the control output.
+    ///
+    /// Note: a NaN error returns 0 without updating any internal state
+    pub fn run(&mut self, err: f32) -> f32 {
+        if err.is_nan() {
+            return 0.0;
+        }
+
+        // integral
+        let integral_err = if !self.saturated { err } else { 0.0 };
+
+        self.err_sum += integral_err;
+
+        if self.windup > 0 {
+            self.err_sum -= self.old_err[self.windup_loc];
+            self.old_err[self.windup_loc] = integral_err;
+
+            self.windup_loc = (self.windup_loc + 1) % self.windup;
+        }
+
+        // derivative (with alpha filter)
+        let new_deriv = err - self.last_error;
+        let derivative = self.deriv_alpha * self.last_deriv + (1.0 - self.deriv_alpha) * new_deriv;
+
+        // update our state variables
+        self.last_error = err;
+        self.last_deriv = derivative;
+        if self.windup > 0 {
+            (err * self.kp) + (self.err_sum * self.ki) / self.windup as f32 + (derivative * self.kd)
+        } else {
+            (err * self.kp) + (self.err_sum * self.ki) + (derivative * self.kd)
+        }
+    }
+
+    /// The number of past errors summed into the integral
+    pub fn windup(&self) -> usize {
+        self.windup
+    }
+
+    /// Set the number of past errors summed into the integral (0 sums every error).
+    ///
+    /// Note: windups larger than `WINDUP_CAPACITY` are clamped to `WINDUP_CAPACITY`
+    pub fn set_windup(&mut self, windup: usize) {
+        let windup = windup.min(WINDUP_CAPACITY);
+        if windup != self.windup {
+            self.windup = windup;
+
+            if windup > 0 {
+                self.err_sum = 0.0;
+                self.windup_loc %= windup;
+                self.old_err.fill(0.0);
+            }
+        }
+    }
+
+    /// Clear any windup
+    pub fn clear_windup(&mut self) {
+        self.err_sum = 0.0;
+        self.old_err.fill(0.0);
+    }
+
+    /// Reset all internal state except for the last error
+    pub fn reset(&mut self) {
+        self.windup = 0;
+        self.windup_loc = 0;
+        self.last_deriv = 0.0;
+        self.saturated = false;
+        self.err_sum = 0.0;
+        self.old_err.fill(0.0);
+    }
+
+    /// Mark whether the output is saturated.  While saturated the integral stops accumulating
+    /// to prevent windup.
+    pub fn set_saturated(&mut self, saturated: bool) {
+        self.saturated = saturated;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Outputs of the C++ `Pid` for the sequence of operations in
+    /// `test_pid_matches_cpp_recording` (recorded with `test/pid_recording.cpp`).
+    const CPP_RECORDING: [u32; 21] = [
+        0x3fcae147, 0x3f516872, 0xbeae83e4, 0x404ee54b, 0x40b38dea, 0xbfb62281, 0x3e60a2f5,
+        0x3fa7f1a2, 0x40c379ce, 0x40c25cdc, 0xc050be0b, 0x00000000, 0x4000ca67, 0xbf3ebb33,
+        0x3e2817da, 0x405f479e, 0x4075ccc8, 0xc0a8cccd, 0x3fd80000, 0x3fdcf5c3, 0x3fda9ba6,
+    ];
+
+    /// Test that the port produces bit-for-bit the same outputs as the C++ implementation
+    #[test]
+    fn test_pid_matches_cpp_recording() {
+        let mut pid = Pid::<8>::new(1.5, 0.2, 0.05, 4, 0.3);
+        let mut outputs = [0.0f32; CPP_RECORDING.len()];
+        let mut outputs_iter = outputs.iter_mut();
+        let mut record = |pid: &mut Pid<8>, err: f32| {
+            *outputs_iter.next().unwrap() = pid.run(err);
+        };
+
+        for err in [1.0, 0.5, -0.25, 2.0, 3.5, -1.0, 0.0, 0.75] {
+            record(&mut pid, err);
+        }
+
+        pid.set_saturated(true);
+        record(&mut pid, 4.0);
+        record(&mut pid, 4.0);
+        pid.set_saturated(false);
+        record(&mut pid, -2.0);
+
+        record(&mut pid, f32::NAN);
+
+        pid.set_windup(3);
+        record(&mut pid, 1.25);
+        record(&mut pid, -0.5);
+
+        pid.clear_windup();
+        record(&mut pid, 0.1);
+
+        pid.set_windup(0);
+        record(&mut pid, 2.0);
+        record(&mut pid, 2.0);
+
+        pid.reset();
+        record(&mut pid, -3.0);
+        pid.set_windup(2);
+        record(&mut pid, 1.0);
+        record(&mut pid, 1.0);
+        record(&mut pid, 1.0);
+
+        for (output, expected) in outputs.iter().zip(CPP_RECORDING) {
+            assert_eq!(output.to_bits(), expected);
+        }
+    }
+
+    /// Test that windups larger than the buffer capacity are clamped
+    #[test]
+    fn test_pid_windup_is_clamped_to_capacity() {
+        let mut pid = Pid::<4>::new(0.0, 1.0, 0.0, 10, 0.0);
+        assert_eq!(pid.windup(), 4);
+
+        for _ in 0..10 {
+            pid.run(2.0);
+        }
+        assert_eq!(pid.run(2.0), 2.0);
+    }
+}
@@ -2,6 +2,11 @@
 //! The addresses of radios on the nRF24L01+ Network
 //! 
 
+use crate::{RtpError, Team, ROBOTS_PER_TEAM};
+
+/// The 5 Byte address of a radio
+pub type RadioAddress = [u8; 5];
+
 /// The different possible base stations.
 /// 
 /// BASE_STATION_ADDRESSES[0] = Blue Team
@@ -33,3 +38,60 @@ pub const ROBOT_RADIO_ADDRESSES: [[[u8; 5]; 6]; 2] = [
         [0xD5, 0xD5, 0xD5, 0xD5, 0xD6],
     ]
 ];
+
+/// The address every robot of a team listens on for `BroadcastFrame`s
+///
+/// Note: the broadcast addresses share their upper 4 Bytes with the team's
+/// `ROBOT_RADIO_ADDRESSES` so they can be assigned to one of the nRF24L01+'s
+/// secondary data pipes (2-5), which only differ from pipe 1 in the least significant Byte.
+///
+/// TEAM_BROADCAST_ADDRESSES[0] = Blue Team
+/// TEAM_BROADCAST_ADDRESSES[1] = Yellow Team
+pub const TEAM_BROADCAST_ADDRESSES: [[u8; 5]; 2] = [
+    [0xC3, 0xC3, 0xC3, 0xC3, 0xC0],
+    [0xD5, 0xD5, 0xD5, 0xD5, 0xD0],
+];
+
+/// The address every robot of a team listens on for `HaltMessage`s and `GameStateMessage`s
+/// (in an envelope, so they can be told apart)
+///
+/// Note: the halt addresses are the team's `BASE_STATION_ADDRESSES` with the least significant
+/// Byte inverted.  They do not share the upper 4 Bytes of the `ROBOT_RADIO_ADDRESSES` so robots
+/// listen for them on the nRF24L01+'s data pipe 0, which has a full 5 Byte address of its own and
+/// is only borrowed for the acknowledgements of the packets the robot sends to its base station.
+///
+/// TEAM_HALT_ADDRESSES[0] = Blue Team
+/// TEAM_HALT_ADDRESSES[1] = Yellow Team
+pub const TEAM_HALT_ADDRESSES: [[u8; 5]; 2] = [
+    derive_halt_address(BASE_STATION_ADDRESSES[0]),
+    derive_halt_address(BASE_STATION_ADDRESSES[1]),
+];
+
+const fn derive_halt_address(base_station: RadioAddress) -> RadioAddress {
+    let mut address = base_station;
+    address[4] = !address[4];
+    address
+}
+
+/// The address of a team's base station
+pub fn base_station_address(team: Team) -> RadioAddress {
+    BASE_STATION_ADDRESSES[team as usize]
+}
+
+/// The address of a robot's radio
+pub fn robot_address(team: Team, robot_id: u8) -> Result<RadioAddress, RtpError> {
+    if robot_id as usize >= ROBOTS_PER_TEAM {
+        return Err(RtpError::InvalidRobotId(robot_id));
+    }
+    Ok(ROBOT_RADIO_ADDRESSES[team as usize][robot_id as usize])
+}
+
+/// The address every robot of a team listens on for `BroadcastFrame`s
+pub fn broadcast_address(team: Team) -> RadioAddress {
+    TEAM_BROADCAST_ADDRESSES[team as usize]
+}
+
+/// The address every robot of a team listens on for `HaltMessage`s and `GameStateMessage`s
+pub fn halt_address(team: Team) -> RadioAddress {
+    TEAM_HALT_ADDRESSES[team as usize]
+}
@@ -2,12 +2,12 @@
 //! Messages related to radio benchmarking.
 //! 
 
-use ncomm_utils::packing::{Packable, PackingError};
+use crate::Packable;
 
-/// The size of a Radio Receive Benchmark Message
-pub const RADIO_RECEIVE_BENCHMARK_SIZE: usize = 8;
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Packable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[packable(size = RADIO_RECEIVE_BENCHMARK_SIZE)]
 /// Message sent from the robot back to the base station
 /// when benchmarking the radio's receiving capabilities
 pub struct RadioReceiveBenchmarkMessage {
@@ -18,38 +18,10 @@ pub struct RadioReceiveBenchmarkMessage {
     pub received_packets: u32,
 }
 
-impl Packable for RadioReceiveBenchmarkMessage {
-    fn len() -> usize {
-        RADIO_RECEIVE_BENCHMARK_SIZE
-    }
-
-    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
-        if buffer.len() < RADIO_RECEIVE_BENCHMARK_SIZE {
-            return Err(PackingError::InvalidBufferSize);
-        }
-
-        buffer[0..4].copy_from_slice(&self.receive_time_ms.to_le_bytes());
-        buffer[4..8].copy_from_slice(&self.received_packets.to_le_bytes());
-
-        Ok(())
-    }
-
-    fn unpack(data: &[u8]) -> Result<Self, PackingError> {
-        if data.len() < RADIO_RECEIVE_BENCHMARK_SIZE {
-            return Err(PackingError::InvalidBufferSize);
-        }
-
-        Ok(Self {
-            receive_time_ms: u32::from_le_bytes(data[0..4].try_into().unwrap()),
-            received_packets: u32::from_le_bytes(data[4..8].try_into().unwrap()),
-        })
-    }
-}
-
-/// The size of a radio send benchmark message
-pub const RADIO_SEND_BENCHMARK_SIZE: usize = 8;
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Packable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[packable(size = RADIO_SEND_BENCHMARK_SIZE)]
 /// Message sent from the robot back to the base station when a radio send benchmark
 /// test is complete
 pub struct RadioSendBenchmarkMessage {
@@ -60,38 +32,12 @@ pub struct RadioSendBenchmarkMessage {
     pub sent_packets: u32,
 }
 
-impl Packable for RadioSendBenchmarkMessage {
-    fn len() -> usize {
-        RADIO_SEND_BENCHMARK_SIZE
-    }
-
-    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
-        if buffer.len() < RADIO_SEND_BENCHMARK_SIZE {
-            return Err(PackingError::InvalidBufferSize);
-        }
-
-        buffer[0..4].copy_from_slice(&self.acknowledged_packets.to_le_bytes());
-        buffer[4..8].copy_from_slice(&self.sent_packets.to_le_bytes());
-
-        Ok(())
-    }
-
-    fn unpack(data: &[u8]) -> Result<Self, PackingError> {
-        if data.len() < RADIO_SEND_BENCHMARK_SIZE {
-            return Err(PackingError::InvalidBufferSize);
-        }
-
-        Ok(Self {
-            acknowledged_packets: u32::from_le_bytes(data[0..4].try_into().unwrap()),
-            sent_packets: u32::from_le_bytes(data[4..8].try_into().unwrap()),
-        })
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use ncomm_utils::packing::Packable;
+
     /// Test that radio receive benchmark messages can be packed and unpacked
     #[test]
     fn test_radio_receive_benchmark_message_pack_and_unpack() {
@@ -0,0 +1,387 @@
+//!
+//! In-memory radio channels for testing protocol logic without radios.
+//!
+//! A `Channel` connects any number of `Endpoint`s, each of which is a `RadioLink` listening on
+//! its own addresses.  Time on a channel is virtual: it only moves forward while an endpoint
+//! waits in `receive` or when `Channel::advance` is called, so a test behaves the same way every
+//! time it runs.
+//!
+//! `Channel::loopback` delivers every packet immediately and intact.  `Channel::new` injects the
+//! loss, latency, jitter (which reorders packets) and bit flips of a `ChannelConfig`, drawn from
+//! a seeded random number generator.
+//!
+
+use std::cell::RefCell;
+use std::convert::Infallible;
+use std::rc::Rc;
+use std::vec::Vec;
+
+use crate::radio_link::{check_payload, LinkError, RadioAddress, RadioLink, Received};
+
+/// The impairments of a `Channel`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChannelConfig {
+    /// The probability that a transmission is lost (0 to 1)
+    pub loss: f32,
+    /// The time between sending and receiving a packet (us)
+    pub latency: u64,
+    /// A random delay of up to `jitter` is added to the latency of every packet (us).  Packets
+    /// sent less than `jitter` apart may arrive out of order
+    pub jitter: u64,
+    /// The probability that each bit of a packet is flipped (0 to 1)
+    pub bit_error_rate: f32,
+    /// The number of times a packet sent with `ack` is retransmitted before giving up
+    pub retries: u8,
+    /// The seed of the random number generator
+    pub seed: u64,
+}
+
+impl Default for ChannelConfig {
+    /// A perfect channel that retries like the nRF24L01+ does by default
+    fn default() -> Self {
+        Self {
+            loss: 0.0,
+            latency: 0,
+            jitter: 0,
+            bit_error_rate: 0.0,
+            retries: 3,
+            seed: 1,
+        }
+    }
+}
+
+/// Counters of what happened on a `Channel`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChannelStats {
+    /// The number of transmissions (including retransmissions)
+    pub transmissions: u32,
+    /// The number of transmissions that were lost
+    pub lost: u32,
+    /// The number of transmissions that had at least one bit flipped
+    pub corrupted: u32,
+    /// The number of packets received by an endpoint
+    pub delivered: u32,
+}
+
+/// A xorshift random number generator so channels are deterministic and need no dependencies
+#[derive(Clone, Copy, Debug)]
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        // xorshift never leaves the all zero state
+        Self(seed.max(1))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Returns true with the given probability
+    fn chance(&mut self, probability: f32) -> bool {
+        probability > 0.0 && ((self.next_u64() >> 40) as f32 / (1u64 << 24) as f32) < probability
+    }
+
+    /// A random number from 0 to `max` (inclusive)
+    fn up_to(&mut self, max: u64) -> u64 {
+        match max.checked_add(1) {
+            Some(range) => self.next_u64() % range,
+            None => self.next_u64(),
+        }
+    }
+}
+
+/// A packet that has been sent but not received
+#[derive(Clone, Debug)]
+struct InFlight {
+    arrival: u64,
+    /// Breaks ties between packets arriving at the same time
+    order: u64,
+    endpoint: usize,
+    address: RadioAddress,
+    data: Vec<u8>,
+}
+
+/// The state shared by every endpoint of a channel
+#[derive(Debug)]
+struct Medium {
+    config: ChannelConfig,
+    rng: XorShift,
+    now: u64,
+    stats: ChannelStats,
+    /// The addresses each endpoint listens on
+    listeners: Vec<Vec<RadioAddress>>,
+    in_flight: Vec<InFlight>,
+    sent: u64,
+}
+
+/// A simulated radio channel shared by a set of `Endpoint`s
+#[derive(Clone, Debug)]
+pub struct Channel {
+    medium: Rc<RefCell<Medium>>,
+}
+
+impl Channel {
+    /// Create a channel with the given impairments
+    pub fn new(config: ChannelConfig) -> Self {
+        Self {
+            medium: Rc::new(RefCell::new(Medium {
+                config,
+                rng: XorShift::new(config.seed),
+                now: 0,
+                stats: ChannelStats::default(),
+                listeners: Vec::new(),
+                in_flight: Vec::new(),
+                sent: 0,
+            })),
+        }
+    }
+
+    /// Create a channel that delivers every packet immediately and intact
+    pub fn loopback() -> Self {
+        Self::new(ChannelConfig::default())
+    }
+
+    /// Add a radio to the channel listening on `addresses`
+    pub fn endpoint(&self, addresses: &[RadioAddress]) -> Endpoint {
+        let mut medium = self.medium.borrow_mut();
+        medium.listeners.push(addresses.to_vec());
+        Endpoint {
+            medium: self.medium.clone(),
+            index: medium.listeners.len() - 1,
+        }
+    }
+
+    /// The current time on the channel (us)
+    pub fn now(&self) -> u64 {
+        self.medium.borrow().now
+    }
+
+    /// Move the time on the channel forward (us)
+    pub fn advance(&self, duration: u64) {
+        let mut medium = self.medium.borrow_mut();
+        medium.now = medium.now.saturating_add(duration);
+    }
+
+    /// What has happened on the channel so far
+    pub fn stats(&self) -> ChannelStats {
+        self.medium.borrow().stats
+    }
+}
+
+/// A radio on a `Channel`
+#[derive(Clone, Debug)]
+pub struct Endpoint {
+    medium: Rc<RefCell<Medium>>,
+    index: usize,
+}
+
+impl Endpoint {
+    /// Start listening on another address
+    pub fn listen(&mut self, address: RadioAddress) {
+        self.medium.borrow_mut().listeners[self.index].push(address);
+    }
+}
+
+impl RadioLink for Endpoint {
+    type Error = Infallible;
+
+    fn send(
+        &mut self,
+        address: RadioAddress,
+        data: &[u8],
+        ack: bool,
+    ) -> Result<(), LinkError<Infallible>> {
+        check_payload(data)?;
+        let medium = &mut *self.medium.borrow_mut();
+        let receivers: Vec<usize> = (0..medium.listeners.len())
+            .filter(|index| *index != self.index && medium.listeners[*index].contains(&address))
+            .collect();
+
+        let attempts = if ack {
+            medium.config.retries as u32 + 1
+        } else {
+            1
+        };
+        for _ in 0..attempts {
+            medium.stats.transmissions += 1;
+            if medium.rng.chance(medium.config.loss) {
+                medium.stats.lost += 1;
+                continue;
+            }
+            if receivers.is_empty() {
+                continue;
+            }
+
+            let mut packet = data.to_vec();
+            let mut corrupted = false;
+            for byte in packet.iter_mut() {
+                for bit in 0..8 {
+                    if medium.rng.chance(medium.config.bit_error_rate) {
+                        *byte ^= 1 << bit;
+                        corrupted = true;
+                    }
+                }
+            }
+            if corrupted {
+                medium.stats.corrupted += 1;
+            }
+
+            let arrival =
+                medium.now + medium.config.latency + medium.rng.up_to(medium.config.jitter);
+            for endpoint in receivers {
+                medium.in_flight.push(InFlight {
+                    arrival,
+                    order: medium.sent,
+                    endpoint,
+                    address,
+                    data: packet.clone(),
+                });
+                medium.sent += 1;
+            }
+            return Ok(());
+        }
+
+        if ack {
+            return Err(LinkError::NotAcknowledged);
+        }
+        Ok(())
+    }
+
+    fn receive(
+        &mut self,
+        buffer: &mut [u8],
+        timeout: u64,
+    ) -> Result<Option<Received>, LinkError<Infallible>> {
+        let medium = &mut *self.medium.borrow_mut();
+        let deadline = medium.now.saturating_add(timeout);
+        let next = medium
+            .in_flight
+            .iter()
+            .enumerate()
+            .filter(|(_, packet)| packet.endpoint == self.index && packet.arrival <= deadline)
+            .min_by_key(|(_, packet)| (packet.arrival, packet.order))
+            .map(|(index, _)| index);
+
+        let Some(index) = next else {
+            medium.now = deadline;
+            return Ok(None);
+        };
+        let packet = medium.in_flight.remove(index);
+        medium.now = medium.now.max(packet.arrival);
+        if buffer.len() < packet.data.len() {
+            return Err(LinkError::BufferTooShort {
+                expected: packet.data.len(),
+                actual: buffer.len(),
+            });
+        }
+
+        buffer[..packet.data.len()].copy_from_slice(&packet.data);
+        medium.stats.delivered += 1;
+        Ok(Some(Received {
+            address: packet.address,
+            len: packet.data.len(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::radio_addresses::{base_station_address, robot_address};
+    use crate::radio_link::MAX_PAYLOAD_SIZE;
+    use crate::Team;
+
+    /// Test that a loopback channel delivers packets to the endpoint listening on their address
+    /// and that receiving times out on the virtual clock
+    #[test]
+    fn test_loopback_channel() {
+        let channel = Channel::loopback();
+        let base_station = base_station_address(Team::Blue);
+        let robot = robot_address(Team::Blue, 2).unwrap();
+        let mut base = channel.endpoint(&[base_station]);
+        let mut robot_radio = channel.endpoint(&[robot]);
+
+        let mut buffer = [0u8; MAX_PAYLOAD_SIZE];
+        base.send(robot, &[1, 2, 3], true).unwrap();
+        assert_eq!(
+            robot_radio.receive(&mut buffer, 0),
+            Ok(Some(Received {
+                address: robot,
+                len: 3
+            }))
+        );
+        assert_eq!(buffer[..3], [1, 2, 3]);
+
+        assert_eq!(base.receive(&mut buffer, 1_000), Ok(None));
+        assert_eq!(channel.now(), 1_000);
+
+        let nobody = robot_address(Team::Yellow, 2).unwrap();
+        assert_eq!(
+            base.send(nobody, &[1], true),
+            Err(LinkError::NotAcknowledged)
+        );
+        assert_eq!(base.send(nobody, &[1], false), Ok(()));
+        assert_eq!(
+            base.send(robot, &[0; MAX_PAYLOAD_SIZE + 1], false),
+            Err(LinkError::PayloadTooLarge(MAX_PAYLOAD_SIZE + 1))
+        );
+
+        robot_radio.send(base_station, &[4, 5], false).unwrap();
+        assert_eq!(
+            base.receive(&mut buffer[..1], 0),
+            Err(LinkError::BufferTooShort {
+                expected: 2,
+                actual: 1
+            })
+        );
+    }
+
+    /// Test that a lossy channel loses, delays, reorders and corrupts packets the same way for
+    /// the same seed
+    #[test]
+    fn test_lossy_channel_is_deterministic() {
+        let config = ChannelConfig {
+            loss: 0.2,
+            latency: 500,
+            jitter: 2_000,
+            bit_error_rate: 0.001,
+            retries: 0,
+            seed: 0x5EED,
+        };
+        let run = || {
+            let channel = Channel::new(config);
+            let robot = robot_address(Team::Yellow, 0).unwrap();
+            let mut base = channel.endpoint(&[]);
+            let mut robot_radio = channel.endpoint(&[robot]);
+
+            for sequence in 0..200u8 {
+                base.send(robot, &[sequence; 8], false).unwrap();
+                channel.advance(100);
+            }
+            let sent_until = channel.now();
+
+            let mut received = Vec::new();
+            let mut buffer = [0u8; MAX_PAYLOAD_SIZE];
+            while let Some(packet) = robot_radio.receive(&mut buffer, 10_000).unwrap() {
+                received.push((channel.now(), buffer[..packet.len].to_vec()));
+            }
+            assert!(received[0].0 >= 500);
+            assert!(received.last().unwrap().0 <= sent_until + 500 + 2_000);
+            (received, channel.stats())
+        };
+
+        let (received, stats) = run();
+        assert_eq!(run(), (received.clone(), stats));
+        assert_eq!(stats.transmissions, 200);
+        assert_eq!(stats.delivered as usize, received.len());
+        assert_eq!(stats.lost + stats.delivered, 200);
+        assert!(stats.lost > 20 && stats.lost < 60);
+        assert!(stats.corrupted > 0);
+        assert!(received.windows(2).any(|pair| pair[0].1[0] > pair[1].1[0]));
+    }
+}
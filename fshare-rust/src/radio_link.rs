@@ -0,0 +1,101 @@
+//!
+//! An abstraction over the radios that carry rtp messages.
+//!
+//! A `RadioLink` sends packets to the 5 Byte addresses in `radio_addresses` and receives the
+//! packets sent to the addresses it listens on.  The firmware implements it on top of the
+//! nRF24L01+ and tests can use the in-memory `radio_channel::Channel` (with the `std` feature)
+//! instead.
+//!
+
+use core::fmt;
+
+pub use crate::radio_addresses::RadioAddress;
+
+/// The largest packet a radio can send (in Bytes)
+pub const MAX_PAYLOAD_SIZE: usize = 32;
+
+/// A packet received by a `RadioLink`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Received {
+    /// The address the packet was sent to
+    pub address: RadioAddress,
+    /// The number of Bytes received
+    pub len: usize,
+}
+
+/// An error from sending or receiving a packet
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkError<E> {
+    /// The packet is larger than the link can send (`MAX_PAYLOAD_SIZE`, or less if the link has
+    /// a fixed payload size)
+    PayloadTooLarge(usize),
+    /// The buffer is too short for the received packet
+    BufferTooShort {
+        /// The number of Bytes needed
+        expected: usize,
+        /// The number of Bytes available
+        actual: usize,
+    },
+    /// The packet was not acknowledged by any radio
+    NotAcknowledged,
+    /// The radio itself failed
+    Radio(E),
+}
+
+impl<E: fmt::Debug> fmt::Display for LinkError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PayloadTooLarge(size) => write!(
+                f,
+                "payload of {} bytes is too large for the link (at most {} bytes)",
+                size, MAX_PAYLOAD_SIZE
+            ),
+            Self::BufferTooShort { expected, actual } => write!(
+                f,
+                "buffer too short: expected at least {} bytes but found {}",
+                expected, actual
+            ),
+            Self::NotAcknowledged => write!(f, "packet was not acknowledged"),
+            Self::Radio(error) => write!(f, "radio error: {:?}", error),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: fmt::Debug> std::error::Error for LinkError<E> {}
+
+/// A radio that sends and receives packets of at most `MAX_PAYLOAD_SIZE` Bytes
+pub trait RadioLink {
+    /// The error reported by the radio itself
+    type Error;
+
+    /// Send a packet to the radio listening on `address`.
+    ///
+    /// With `ack` the packet is retransmitted until it is acknowledged and
+    /// `LinkError::NotAcknowledged` is returned if it never is.  Without `ack` the packet is
+    /// sent once and it is not known whether it arrived.
+    fn send(
+        &mut self,
+        address: RadioAddress,
+        data: &[u8],
+        ack: bool,
+    ) -> Result<(), LinkError<Self::Error>>;
+
+    /// Receive a packet sent to one of the addresses this radio listens on, waiting at most
+    /// `timeout` microseconds.
+    ///
+    /// Returns `None` if no packet arrived before the timeout
+    fn receive(
+        &mut self,
+        buffer: &mut [u8],
+        timeout: u64,
+    ) -> Result<Option<Received>, LinkError<Self::Error>>;
+}
+
+/// Check that a packet fits into a single radio payload (for implementations of `RadioLink`)
+pub fn check_payload<E>(data: &[u8]) -> Result<(), LinkError<E>> {
+    if data.len() > MAX_PAYLOAD_SIZE {
+        return Err(LinkError::PayloadTooLarge(data.len()));
+    }
+    Ok(())
+}
@@ -0,0 +1,640 @@
+//!
+//! A timestamped, append-only log of the raw frames that crossed the radio so a match can be
+//! inspected (or replayed) after the fact.
+//!
+//! A log starts with an 8 Byte header followed by one record per frame:
+//! +---------+---------+---------+---------+---------+---------+---------+---------+
+//! | magic ("RTPL", 4 Bytes)               | format  | proto   | unused (2 Bytes)  |
+//! +---------+---------+---------+---------+---------+---------+---------+---------+
+//!
+//! Each record has a 12 Byte header followed by the payload exactly as it was sent or received:
+//! +---------+---------+---------+---------+---------+---------+---------+---------+
+//! | timestamp (microseconds since the log was started, u64 little endian)          |
+//! +---------+---------+---------+---------+---------+---------+---------+---------+
+//! | dir     | team    | robot id (0x3F: none)                                     |
+//! +---------+---------+---------+---------+---------+---------+---------+---------+
+//! | frame format (see `FrameFormat`)                                              |
+//! +---------+---------+---------+---------+---------+---------+---------+---------+
+//! | payload length                                                                |
+//! +---------+---------+---------+---------+---------+---------+---------+---------+
+//! | unused                                                                        |
+//! +---------+---------+---------+---------+---------+---------+---------+---------+
+//! | payload (payload length Bytes)                                                |
+//! +---------+---------+---------+---------+---------+---------+---------+---------+
+//!
+//! Every record is written with a single call to `write_all`, so a log that was cut short (e.g.
+//! the base station crashed) only loses its final record, which the reader reports as
+//! `RadioLogError::Truncated`.
+//!
+
+use core::fmt;
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+use std::vec::Vec;
+
+use crate::broadcast::for_each_command;
+use crate::envelope::{MessageKind, RtpMessage, PROTOCOL_VERSION};
+use crate::{ControlMessage, RtpError, Team};
+
+/// The magic Bytes at the start of every radio log
+pub const RADIO_LOG_MAGIC: [u8; 4] = *b"RTPL";
+
+/// The version of the radio log format written by `RadioLogWriter`
+pub const RADIO_LOG_VERSION: u8 = 1;
+
+/// The size of the header at the start of a radio log in Bytes
+pub const RADIO_LOG_HEADER_SIZE: usize = 8;
+
+/// The size of the header in front of every record in Bytes
+pub const RECORD_HEADER_SIZE: usize = 12;
+
+/// The value of the robot id bits for frames that are not for a single robot
+const NO_ROBOT_ID: u8 = 0x3F;
+
+/// The direction a frame was travelling in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Sent from the base station to the robots
+    ToRobot = 0,
+    /// Sent from a robot to the base station
+    FromRobot = 1,
+}
+
+/// How the payload of a record is encoded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameFormat {
+    /// A message without an envelope header (e.g. a `ControlMessage` sent on a robot's pipe)
+    Bare(MessageKind),
+    /// A message inside an envelope (see `RtpMessage`)
+    Envelope,
+    /// A `BroadcastFrame` of any size
+    Broadcast,
+}
+
+impl FrameFormat {
+    /// The byte the format is logged as
+    pub fn to_byte(self) -> u8 {
+        match self {
+            Self::Bare(kind) => kind as u8,
+            Self::Envelope => 0x10,
+            Self::Broadcast => 0x11,
+        }
+    }
+
+    /// Parse the byte a format is logged as
+    pub fn from_byte(byte: u8) -> Result<Self, RtpError> {
+        match byte {
+            0x10 => Ok(Self::Envelope),
+            0x11 => Ok(Self::Broadcast),
+            kind => Ok(Self::Bare(kind.try_into()?)),
+        }
+    }
+
+    /// Decode a payload in this format into the message(s) it carries
+    pub fn decode(self, payload: &[u8]) -> Result<Frame, RtpError> {
+        match self {
+            Self::Bare(kind) => RtpMessage::decode_payload(kind, payload).map(Frame::Message),
+            Self::Envelope => RtpMessage::decode(payload).map(Frame::Message),
+            Self::Broadcast => {
+                let mut commands = Vec::new();
+                for_each_command(payload, |command| commands.push(command))?;
+                Ok(Frame::Broadcast(commands))
+            }
+        }
+    }
+}
+
+/// A frame decoded from the payload of a record
+#[derive(Clone, Debug, PartialEq)]
+pub enum Frame {
+    /// A single message (bare or inside an envelope)
+    Message(RtpMessage),
+    /// The commands of a broadcast frame (in slot order)
+    Broadcast(Vec<ControlMessage>),
+}
+
+/// A single frame in a radio log
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogRecord {
+    /// The time since the log was started
+    pub timestamp: Duration,
+    /// The direction the frame was travelling in
+    pub direction: Direction,
+    /// The team the frame was sent to or from
+    pub team: Team,
+    /// The robot the frame was sent to or from (`None` for broadcast frames)
+    pub robot_id: Option<u8>,
+    /// How the payload is encoded
+    pub format: FrameFormat,
+    /// The raw bytes of the frame
+    pub payload: Vec<u8>,
+}
+
+impl LogRecord {
+    /// Decode the payload of the record into the message(s) it carries
+    pub fn decode(&self) -> Result<Frame, RtpError> {
+        self.format.decode(&self.payload)
+    }
+}
+
+/// An error from reading a radio log
+#[derive(Debug)]
+pub enum RadioLogError {
+    /// Reading from the underlying reader failed
+    Io(io::Error),
+    /// The data does not start with `RADIO_LOG_MAGIC`
+    NotARadioLog,
+    /// The log was written with a newer version of the radio log format
+    UnsupportedVersion(u8),
+    /// A record header contains an invalid value
+    InvalidRecord(RtpError),
+    /// The log ends in the middle of a record
+    Truncated,
+    /// The log was written with another rtp protocol version, so its frames cannot be replayed
+    ProtocolVersionMismatch(u8),
+    /// The replay speed is not a finite positive number
+    InvalidSpeed(f64),
+}
+
+impl fmt::Display for RadioLogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read radio log: {}", err),
+            Self::NotARadioLog => write!(f, "not a radio log"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported radio log version {}", version)
+            }
+            Self::InvalidRecord(err) => write!(f, "invalid record: {}", err),
+            Self::Truncated => write!(f, "radio log ends in the middle of a record"),
+            Self::ProtocolVersionMismatch(version) => write!(
+                f,
+                "radio log was written with protocol version {} but this is version {}",
+                version, PROTOCOL_VERSION
+            ),
+            Self::InvalidSpeed(speed) => write!(
+                f,
+                "replay speed must be a finite positive number but was {}",
+                speed
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RadioLogError {}
+
+impl From<io::Error> for RadioLogError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// Appends frames to a radio log
+pub struct RadioLogWriter<W: Write> {
+    inner: W,
+    start: Instant,
+}
+
+impl RadioLogWriter<BufWriter<File>> {
+    /// Create a new radio log file (replacing any existing file)
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> RadioLogWriter<W> {
+    /// Start a new radio log, writing its header.  Timestamps are measured from now
+    pub fn new(mut inner: W) -> io::Result<Self> {
+        let mut header = [0u8; RADIO_LOG_HEADER_SIZE];
+        header[..4].copy_from_slice(&RADIO_LOG_MAGIC);
+        header[4] = RADIO_LOG_VERSION;
+        header[5] = PROTOCOL_VERSION;
+        inner.write_all(&header)?;
+        Ok(Self {
+            inner,
+            start: Instant::now(),
+        })
+    }
+
+    /// Log a frame that was just sent or received
+    pub fn record(
+        &mut self,
+        direction: Direction,
+        team: Team,
+        robot_id: Option<u8>,
+        format: FrameFormat,
+        payload: &[u8],
+    ) -> io::Result<()> {
+        self.write_record(&LogRecord {
+            timestamp: self.start.elapsed(),
+            direction,
+            team,
+            robot_id,
+            format,
+            payload: payload.to_vec(),
+        })
+    }
+
+    /// Append a record to the log
+    pub fn write_record(&mut self, record: &LogRecord) -> io::Result<()> {
+        let invalid = |reason| io::Error::new(io::ErrorKind::InvalidInput, reason);
+        let payload_len = u8::try_from(record.payload.len())
+            .map_err(|_| invalid("payloads are at most 255 Bytes"))?;
+        let robot_id = match record.robot_id {
+            Some(robot_id) if robot_id >= NO_ROBOT_ID => {
+                return Err(invalid("robot ids must be smaller than 0x3F"))
+            }
+            Some(robot_id) => robot_id,
+            None => NO_ROBOT_ID,
+        };
+        let timestamp = u64::try_from(record.timestamp.as_micros()).unwrap_or(u64::MAX);
+
+        let mut buffer = Vec::with_capacity(RECORD_HEADER_SIZE + record.payload.len());
+        buffer.extend_from_slice(&timestamp.to_le_bytes());
+        buffer.push((record.direction as u8) << 7 | (record.team as u8) << 6 | robot_id);
+        buffer.push(record.format.to_byte());
+        buffer.push(payload_len);
+        buffer.push(0);
+        buffer.extend_from_slice(&record.payload);
+        self.inner.write_all(&buffer)
+    }
+
+    /// Flush any buffered records to the underlying writer
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Get the underlying writer back
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// Reads the records of a radio log one at a time
+pub struct RadioLogReader<R: Read> {
+    inner: R,
+    protocol_version: u8,
+}
+
+impl RadioLogReader<BufReader<File>> {
+    /// Open a radio log file
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, RadioLogError> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> RadioLogReader<R> {
+    /// Start reading a radio log, checking its header
+    pub fn new(mut inner: R) -> Result<Self, RadioLogError> {
+        let mut header = [0u8; RADIO_LOG_HEADER_SIZE];
+        match read_exact_or_eof(&mut inner, &mut header)? {
+            Fill::Full => (),
+            Fill::Empty | Fill::Partial => return Err(RadioLogError::NotARadioLog),
+        }
+        if header[..4] != RADIO_LOG_MAGIC {
+            return Err(RadioLogError::NotARadioLog);
+        }
+        if header[4] > RADIO_LOG_VERSION {
+            return Err(RadioLogError::UnsupportedVersion(header[4]));
+        }
+
+        Ok(Self {
+            inner,
+            protocol_version: header[5],
+        })
+    }
+
+    /// The rtp protocol version of the software that wrote the log.
+    ///
+    /// Note: Envelopes from other protocol versions fail to decode with
+    /// `RtpError::VersionMismatch`
+    pub fn protocol_version(&self) -> u8 {
+        self.protocol_version
+    }
+
+    /// Read the next record, returning `None` at the end of the log
+    pub fn read_record(&mut self) -> Result<Option<LogRecord>, RadioLogError> {
+        let mut header = [0u8; RECORD_HEADER_SIZE];
+        match read_exact_or_eof(&mut self.inner, &mut header)? {
+            Fill::Full => (),
+            Fill::Empty => return Ok(None),
+            Fill::Partial => return Err(RadioLogError::Truncated),
+        }
+
+        let mut timestamp = [0u8; 8];
+        timestamp.copy_from_slice(&header[..8]);
+        let direction = if header[8] & (0b1 << 7) == 0 {
+            Direction::ToRobot
+        } else {
+            Direction::FromRobot
+        };
+        let team = if header[8] & (0b1 << 6) == 0 {
+            Team::Blue
+        } else {
+            Team::Yellow
+        };
+        let robot_id = match header[8] & 0b0011_1111 {
+            NO_ROBOT_ID => None,
+            robot_id => Some(robot_id),
+        };
+        let format = FrameFormat::from_byte(header[9]).map_err(RadioLogError::InvalidRecord)?;
+
+        let mut payload = std::vec![0u8; header[10] as usize];
+        if !payload.is_empty() {
+            match read_exact_or_eof(&mut self.inner, &mut payload)? {
+                Fill::Full => (),
+                Fill::Empty | Fill::Partial => return Err(RadioLogError::Truncated),
+            }
+        }
+
+        Ok(Some(LogRecord {
+            timestamp: Duration::from_micros(u64::from_le_bytes(timestamp)),
+            direction,
+            team,
+            robot_id,
+            format,
+            payload,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for RadioLogReader<R> {
+    type Item = Result<LogRecord, RadioLogError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+/// Re-emits the records of a radio log with their original timing
+pub struct Replayer<R: Read> {
+    reader: RadioLogReader<R>,
+    speed: f64,
+}
+
+impl<R: Read> Replayer<R> {
+    /// Replay a radio log in real time
+    pub fn new(reader: RadioLogReader<R>) -> Self {
+        Self { reader, speed: 1.0 }
+    }
+
+    /// Replay the log `speed` times faster than it was recorded (e.g. 0.5 for half speed)
+    ///
+    /// Returns `RadioLogError::InvalidSpeed` if `speed` is not a finite positive number
+    pub fn speed(mut self, speed: f64) -> Result<Self, RadioLogError> {
+        if !(speed.is_finite() && speed > 0.0) {
+            return Err(RadioLogError::InvalidSpeed(speed));
+        }
+        self.speed = speed;
+        Ok(self)
+    }
+
+    /// Replay the log, calling `emit` with each record once the same amount of time has passed
+    /// since the first record as when it was recorded
+    pub fn replay(self, emit: impl FnMut(&LogRecord)) -> Result<(), RadioLogError> {
+        let start = Instant::now();
+        self.replay_with(
+            |offset| {
+                let elapsed = start.elapsed();
+                if offset > elapsed {
+                    thread::sleep(offset - elapsed);
+                }
+            },
+            emit,
+        )
+    }
+
+    /// Replay the log using `wait_until` to wait until a given offset from the start of the
+    /// replay (already scaled by the speed) before each record is passed to `emit`.
+    ///
+    /// Note: bare and broadcast frames do not carry a protocol version, so logs written with
+    /// another protocol version are rejected with `RadioLogError::ProtocolVersionMismatch`
+    /// before anything is replayed
+    pub fn replay_with(
+        self,
+        mut wait_until: impl FnMut(Duration),
+        mut emit: impl FnMut(&LogRecord),
+    ) -> Result<(), RadioLogError> {
+        if self.reader.protocol_version != PROTOCOL_VERSION {
+            return Err(RadioLogError::ProtocolVersionMismatch(
+                self.reader.protocol_version,
+            ));
+        }
+
+        let mut first = None;
+        for record in self.reader {
+            let record = record?;
+            let first = *first.get_or_insert(record.timestamp);
+            let offset = record.timestamp.saturating_sub(first).as_secs_f64() / self.speed;
+            // very slow replays wait for longer than a Duration can hold
+            wait_until(Duration::try_from_secs_f64(offset).unwrap_or(Duration::MAX));
+            emit(&record);
+        }
+        Ok(())
+    }
+}
+
+/// How much of a buffer `read_exact_or_eof` filled
+enum Fill {
+    Full,
+    Empty,
+    Partial,
+}
+
+/// Fill a buffer, distinguishing between the reader ending before and part way through it
+fn read_exact_or_eof(reader: &mut impl Read, buffer: &mut [u8]) -> io::Result<Fill> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) if filled == 0 => return Ok(Fill::Empty),
+            Ok(0) => return Ok(Fill::Partial),
+            Ok(read) => filled += read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(Fill::Full)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+
+    use ncomm_utils::packing::Packable;
+
+    use crate::broadcast::BroadcastFrame;
+    use crate::envelope::MAX_ENVELOPE_SIZE;
+    use crate::{ControlMessageBuilder, RobotStatusMessageBuilder, CONTROL_MESSAGE_SIZE};
+
+    /// A log with one record of every frame format
+    fn records() -> Vec<LogRecord> {
+        let command = ControlMessageBuilder::new()
+            .team(Team::Yellow)
+            .robot_id(3)
+            .body_x(1.5)
+            .sequence(7)
+            .build();
+        let mut bare = [0u8; CONTROL_MESSAGE_SIZE];
+        command.pack(&mut bare).unwrap();
+
+        let status = RobotStatusMessageBuilder::new()
+            .team(Team::Yellow)
+            .robot_id(3)
+            .sequence(7)
+            .build();
+        let mut envelope = [0u8; MAX_ENVELOPE_SIZE];
+        let written = RtpMessage::from(status).encode(&mut envelope).unwrap();
+
+        let mut broadcast = [0u8; BroadcastFrame::<2>::SIZE];
+        BroadcastFrame::new(Team::Yellow, 7, [command, command])
+            .pack(&mut broadcast)
+            .unwrap();
+
+        std::vec![
+            LogRecord {
+                timestamp: Duration::from_millis(10),
+                direction: Direction::ToRobot,
+                team: Team::Yellow,
+                robot_id: Some(3),
+                format: FrameFormat::Bare(MessageKind::Control),
+                payload: bare.to_vec(),
+            },
+            LogRecord {
+                timestamp: Duration::from_millis(12),
+                direction: Direction::FromRobot,
+                team: Team::Yellow,
+                robot_id: Some(3),
+                format: FrameFormat::Envelope,
+                payload: envelope[..written].to_vec(),
+            },
+            LogRecord {
+                timestamp: Duration::from_millis(26),
+                direction: Direction::ToRobot,
+                team: Team::Yellow,
+                robot_id: None,
+                format: FrameFormat::Broadcast,
+                payload: broadcast.to_vec(),
+            },
+        ]
+    }
+
+    /// Write a log containing `records`
+    fn write_log(records: &[LogRecord]) -> Vec<u8> {
+        let mut writer = RadioLogWriter::new(Vec::new()).unwrap();
+        for record in records {
+            writer.write_record(record).unwrap();
+        }
+        writer.into_inner()
+    }
+
+    /// Test that records can be written, read back and decoded into their messages
+    #[test]
+    fn test_radio_log_write_and_read() {
+        let records = records();
+        let log = write_log(&records);
+
+        let mut reader = RadioLogReader::new(Cursor::new(&log)).unwrap();
+        assert_eq!(reader.protocol_version(), PROTOCOL_VERSION);
+        let read: Vec<LogRecord> = reader.by_ref().map(Result::unwrap).collect();
+        assert_eq!(read, records);
+
+        let command = ControlMessageBuilder::new()
+            .team(Team::Yellow)
+            .robot_id(3)
+            .body_x(1.5)
+            .sequence(7)
+            .build();
+        assert_eq!(read[0].decode(), Ok(Frame::Message(command.into())));
+        assert!(matches!(
+            read[1].decode(),
+            Ok(Frame::Message(RtpMessage::RobotStatus(status))) if status.sequence == 7
+        ));
+        assert_eq!(
+            read[2].decode(),
+            Ok(Frame::Broadcast(std::vec![command, command]))
+        );
+    }
+
+    /// Test that invalid and truncated logs are reported
+    #[test]
+    fn test_radio_log_errors() {
+        assert!(matches!(
+            RadioLogReader::new(Cursor::new(b"not a log")),
+            Err(RadioLogError::NotARadioLog)
+        ));
+
+        let log = write_log(&records());
+        let mut reader = RadioLogReader::new(Cursor::new(&log[..log.len() - 1])).unwrap();
+        assert!(reader.read_record().unwrap().is_some());
+        assert!(reader.read_record().unwrap().is_some());
+        assert!(matches!(
+            reader.read_record(),
+            Err(RadioLogError::Truncated)
+        ));
+
+        let mut writer = RadioLogWriter::new(Vec::new()).unwrap();
+        let mut record = records().remove(0);
+        record.payload = std::vec![0; 256];
+        assert!(writer.write_record(&record).is_err());
+    }
+
+    /// Test that the replayer waits for the original (scaled) time between records
+    #[test]
+    fn test_replayer_timing() {
+        let log = write_log(&records());
+
+        let mut waits = Vec::new();
+        let mut emitted = Vec::new();
+        Replayer::new(RadioLogReader::new(Cursor::new(&log)).unwrap())
+            .speed(2.0)
+            .unwrap()
+            .replay_with(
+                |offset| waits.push(offset),
+                |record| emitted.push(record.clone()),
+            )
+            .unwrap();
+
+        assert_eq!(
+            waits,
+            [
+                Duration::ZERO,
+                Duration::from_millis(1),
+                Duration::from_millis(8)
+            ]
+        );
+        assert_eq!(emitted, records());
+    }
+
+    /// Test that a replay speed that is not a finite positive number is rejected up front
+    #[test]
+    fn test_replayer_rejects_invalid_speed() {
+        let log = write_log(&records());
+        for speed in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                Replayer::new(RadioLogReader::new(Cursor::new(&log)).unwrap()).speed(speed),
+                Err(RadioLogError::InvalidSpeed(_))
+            ));
+        }
+    }
+
+    /// Test that logs written with another protocol version are not replayed
+    #[test]
+    fn test_replayer_rejects_other_protocol_versions() {
+        let mut log = write_log(&records());
+        log[5] = PROTOCOL_VERSION + 1;
+
+        let mut emitted = 0;
+        let result = Replayer::new(RadioLogReader::new(Cursor::new(&log)).unwrap())
+            .replay_with(|_| (), |_| emitted += 1);
+        assert!(matches!(
+            result,
+            Err(RadioLogError::ProtocolVersionMismatch(version)) if version == PROTOCOL_VERSION + 1
+        ));
+        assert_eq!(emitted, 0);
+    }
+}
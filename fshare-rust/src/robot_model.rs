@@ -0,0 +1,163 @@
+//!
+//! Omni-wheel kinematics for the robots.
+//!
+//! This is a port of `RobotModel` from `include/rc-fshare/robot_model.hpp`.  Instead of a
+//! singleton, a `RobotModel` is computed from a `RobotGeometry` so robots with different wheel
+//! layouts can share the same implementation.
+//!
+
+use nalgebra::base::*;
+use nalgebra::ComplexField;
+
+/// Convert rad/s to duty cycle.
+///
+/// Chosen empirically on a no load robot by averaging the ratio between commanded speed and
+/// output speed.
+pub const DUTY_CYCLE_TO_SPEED: f32 = 125.0;
+
+/// Convert a wheel speed (rad/s) into a duty cycle
+pub const SPEED_TO_DUTY_CYCLE: f32 = 1.0 / DUTY_CYCLE_TO_SPEED;
+
+/// The physical layout of a robot's wheels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RobotGeometry {
+    /// Radius of the omni-wheels (m)
+    pub wheel_radius: f32,
+    /// Distance from the center of the robot to the center of the front wheels (m)
+    pub front_wheel_dist: f32,
+    /// Distance from the center of the robot to the center of the rear wheels (m)
+    pub rear_wheel_dist: f32,
+    /// Angle between the +x axis and the front right wheel axle (degrees)
+    pub front_angle: f32,
+    /// Angle between the -x axis and the rear left wheel axle (degrees)
+    pub back_angle: f32,
+}
+
+impl Default for RobotGeometry {
+    /// The geometry of the current robots
+    fn default() -> Self {
+        Self {
+            wheel_radius: 0.02786,
+            front_wheel_dist: 0.078089,
+            rear_wheel_dist: 0.077874,
+            front_angle: 30.0,
+            back_angle: 45.0,
+        }
+    }
+}
+
+/// Model parameters for a robot.  Used by the controls system.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RobotModel {
+    /// Radius of omni-wheel (m)
+    pub wheel_radius: f32,
+    /// Distance from center of robot to center of wheel (m)
+    pub wheel_dist: f32,
+    /// Wheel angles (rad) measured between +x axis and wheel axle (M1, M2, M3, M4)
+    pub wheel_angles: [f32; 4],
+    /// wheel_speeds = bot_to_wheel * v_bot
+    pub bot_to_wheel: Matrix4x3<f32>,
+    /// v_bot = wheel_to_bot * wheel_speeds (least squares inverse of bot_to_wheel)
+    pub wheel_to_bot: Matrix3x4<f32>,
+}
+
+impl RobotModel {
+    /// Compute the model for a given geometry.
+    ///
+    /// Returns `None` if the wheel layout cannot reproduce every body velocity
+    pub fn new(geometry: RobotGeometry) -> Option<Self> {
+        let wheel_angles = [
+            (180.0 - geometry.front_angle).to_radians(), // M1
+            (180.0 + geometry.back_angle).to_radians(),  // M2
+            (360.0 - geometry.back_angle).to_radians(),  // M3
+            (0.0 + geometry.front_angle).to_radians(),   // M4
+        ];
+        let wheel_dist = (geometry.front_wheel_dist + geometry.rear_wheel_dist) / 2.0;
+
+        Self::from_wheels(geometry.wheel_radius, wheel_dist, wheel_angles)
+    }
+
+    /// Compute the model from the wheel radius, the distance of every wheel from the center of
+    /// the robot and the angle of each wheel axle.
+    ///
+    /// Returns `None` if the wheel layout cannot reproduce every body velocity
+    pub fn from_wheels(wheel_radius: f32, wheel_dist: f32, wheel_angles: [f32; 4]) -> Option<Self> {
+        // See this paper for more info on how this matrix is derived:
+        // http://people.idsia.ch/~foerster/2006/1/omnidrive_kiart_preprint.pdf
+        let row = |angle: f32| {
+            RowVector3::new(-ComplexField::sin(angle), ComplexField::cos(angle), wheel_dist)
+        };
+        let bot_to_wheel = Matrix4x3::from_rows(&[
+            row(wheel_angles[0]),
+            row(wheel_angles[1]),
+            row(wheel_angles[2]),
+            row(wheel_angles[3]),
+        ]);
+        // Invert because our wheels spin opposite to paper
+        let bot_to_wheel = bot_to_wheel * -1.0 / wheel_radius;
+
+        let wheel_to_bot =
+            (bot_to_wheel.transpose() * bot_to_wheel).try_inverse()? * bot_to_wheel.transpose();
+
+        Some(Self {
+            wheel_radius,
+            wheel_dist,
+            wheel_angles,
+            bot_to_wheel,
+            wheel_to_bot,
+        })
+    }
+
+    /// Convert a body velocity (x m/s, y m/s, w rad/s) into wheel speeds (rad/s)
+    pub fn bot_to_wheel_speeds(&self, velocity: Vector3<f32>) -> Vector4<f32> {
+        self.bot_to_wheel * velocity
+    }
+
+    /// Convert wheel speeds (rad/s) into a body velocity (x m/s, y m/s, w rad/s)
+    pub fn wheel_speeds_to_bot(&self, wheel_speeds: Vector4<f32>) -> Vector3<f32> {
+        self.wheel_to_bot * wheel_speeds
+    }
+
+    /// Convert a body velocity into the duty cycle of each wheel
+    pub fn bot_to_duty_cycles(&self, velocity: Vector3<f32>) -> Vector4<f32> {
+        self.bot_to_wheel_speeds(velocity) * SPEED_TO_DUTY_CYCLE
+    }
+}
+
+impl Default for RobotModel {
+    /// The model of the current robots
+    fn default() -> Self {
+        Self::new(RobotGeometry::default()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that pure rotation spins every wheel at the same speed and that wheel_to_bot
+    /// inverts bot_to_wheel
+    #[test]
+    fn test_robot_model_kinematics() {
+        let model = RobotModel::default();
+        assert!((model.wheel_dist - 0.0779815).abs() < 1e-6);
+
+        let wheel_speeds = model.bot_to_wheel_speeds(Vector3::new(0.0, 0.0, 1.0));
+        for speed in wheel_speeds.iter() {
+            assert!((speed + model.wheel_dist / model.wheel_radius).abs() < 1e-4);
+        }
+
+        let identity = model.wheel_to_bot * model.bot_to_wheel;
+        assert!((identity - Matrix3::identity()).abs().max() < 1e-5);
+
+        let velocity = Vector3::new(1.2, -0.4, 3.0);
+        let round_trip = model.wheel_speeds_to_bot(model.bot_to_wheel_speeds(velocity));
+        assert!((round_trip - velocity).abs().max() < 1e-4);
+    }
+
+    /// Test that a degenerate wheel layout is rejected
+    #[test]
+    fn test_robot_model_degenerate_geometry() {
+        assert!(RobotModel::from_wheels(0.03, 0.08, [0.0; 4]).is_none());
+    }
+}
@@ -0,0 +1,376 @@
+//!
+//! Base station bookkeeping of every robot's liveness and health.
+//!
+//! The `RobotRegistry` records the latest `RobotStatusMessage` of each robot along with when it
+//! was received and derives whether the robot is connected, stale or lost from configurable
+//! timeouts.  Changes (a robot connecting or going quiet, its kicker becoming unhealthy, its FPGA
+//! failing, its battery running low or it latching or releasing a halt) are reported as
+//! `RegistryEvent`s.
+//!
+//! Like the `SequenceTracker`, timestamps are plain `u64`s from any monotonic clock (e.g.
+//! microseconds since startup) and the timeouts are in the same unit.
+//!
+
+use crate::{RobotStatusMessage, Team, ROBOTS_PER_TEAM};
+
+/// The timeouts and thresholds of a `RobotRegistry`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RegistryConfig {
+    /// A robot that has not been heard from for longer than this is stale
+    pub stale_after: u64,
+    /// A robot that has not been heard from for longer than this is lost
+    pub lost_after: u64,
+    /// The battery voltage (V) below which the battery is low
+    pub low_battery: f32,
+}
+
+impl Default for RegistryConfig {
+    /// Timeouts in microseconds and the low voltage of a 6 cell battery
+    fn default() -> Self {
+        Self {
+            stale_after: 250_000,
+            lost_after: 1_000_000,
+            low_battery: 21.0,
+        }
+    }
+}
+
+/// Whether a robot is being heard from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Liveness {
+    /// The robot has never been heard from
+    #[default]
+    Unknown,
+    /// The robot was heard from within `RegistryConfig::stale_after`
+    Connected,
+    /// The robot was heard from within `RegistryConfig::lost_after`
+    Stale,
+    /// The robot has not been heard from for longer than `RegistryConfig::lost_after`
+    Lost,
+}
+
+/// A change in a robot's liveness or health
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegistryEventKind {
+    /// The robot was heard from for the first time or after being stale or lost
+    Connected,
+    /// The robot has not been heard from for longer than `RegistryConfig::stale_after`
+    Stale,
+    /// The robot has not been heard from for longer than `RegistryConfig::lost_after`
+    Lost,
+    /// The robot reported that its kicker is not healthy
+    KickerUnhealthy,
+    /// The robot reported that its FPGA failed
+    FpgaFailed,
+    /// The robot reported a battery voltage below `RegistryConfig::low_battery`
+    BatteryLow,
+    /// The robot reported that it latched a `HaltMessage`
+    Halted,
+    /// The robot reported that it is no longer halted
+    Resumed,
+}
+
+/// A change of a single robot
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegistryEvent {
+    /// The team of the robot
+    pub team: Team,
+    /// The id of the robot
+    pub robot_id: u8,
+    /// What changed
+    pub kind: RegistryEventKind,
+}
+
+/// What is known about a single robot
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RobotEntry {
+    /// Whether the robot is being heard from
+    pub liveness: Liveness,
+    /// When the robot was last heard from
+    pub last_seen: Option<u64>,
+    /// The last status the robot sent
+    pub status: Option<RobotStatusMessage>,
+    /// The number of statuses received from the robot
+    pub packets: u32,
+}
+
+impl RobotEntry {
+    const fn new() -> Self {
+        Self {
+            liveness: Liveness::Unknown,
+            last_seen: None,
+            status: None,
+            packets: 0,
+        }
+    }
+
+    /// The liveness of the robot at `now`
+    fn liveness_at(&self, now: u64, config: &RegistryConfig) -> Liveness {
+        match self.last_seen {
+            None => Liveness::Unknown,
+            Some(last_seen) if now.saturating_sub(last_seen) > config.lost_after => Liveness::Lost,
+            Some(last_seen) if now.saturating_sub(last_seen) > config.stale_after => {
+                Liveness::Stale
+            }
+            Some(_) => Liveness::Connected,
+        }
+    }
+}
+
+/// Tracks the liveness and health of every robot on both teams
+#[derive(Clone, Debug)]
+pub struct RobotRegistry {
+    config: RegistryConfig,
+    robots: [[RobotEntry; ROBOTS_PER_TEAM]; 2],
+}
+
+impl RobotRegistry {
+    /// Create a registry in which no robot has been heard from
+    pub const fn new(config: RegistryConfig) -> Self {
+        Self {
+            config,
+            robots: [[RobotEntry::new(); ROBOTS_PER_TEAM]; 2],
+        }
+    }
+
+    /// The timeouts and thresholds of the registry
+    pub fn config(&self) -> &RegistryConfig {
+        &self.config
+    }
+
+    /// Record a status received at `now`, calling `on_event` for everything that changed.
+    ///
+    /// Note: statuses from robot ids that are out of range are ignored
+    pub fn record_status(
+        &mut self,
+        status: &RobotStatusMessage,
+        now: u64,
+        mut on_event: impl FnMut(RegistryEvent),
+    ) {
+        let config = self.config;
+        let Some(robot) = self.robot_mut(status.team, status.robot_id) else {
+            return;
+        };
+        let mut emit = |kind| {
+            on_event(RegistryEvent {
+                team: status.team,
+                robot_id: status.robot_id,
+                kind,
+            })
+        };
+
+        if robot.liveness != Liveness::Connected {
+            emit(RegistryEventKind::Connected);
+        }
+        // A robot that was never heard from is assumed to have been healthy
+        let previous = robot.status;
+        if !status.kick_healthy && previous.is_none_or(|previous| previous.kick_healthy) {
+            emit(RegistryEventKind::KickerUnhealthy);
+        }
+        if !status.fpga_status && previous.is_none_or(|previous| previous.fpga_status) {
+            emit(RegistryEventKind::FpgaFailed);
+        }
+        let low = |status: &RobotStatusMessage| status.get_battery_voltage() < config.low_battery;
+        if low(status) && !previous.is_some_and(|previous| low(&previous)) {
+            emit(RegistryEventKind::BatteryLow);
+        }
+        match (
+            status.halted,
+            previous.is_some_and(|previous| previous.halted),
+        ) {
+            (true, false) => emit(RegistryEventKind::Halted),
+            (false, true) => emit(RegistryEventKind::Resumed),
+            _ => (),
+        }
+
+        robot.liveness = Liveness::Connected;
+        robot.last_seen = Some(robot.last_seen.map_or(now, |last_seen| last_seen.max(now)));
+        robot.status = Some(*status);
+        robot.packets = robot.packets.saturating_add(1);
+    }
+
+    /// Update the liveness of every robot to `now`, calling `on_event` for every robot that
+    /// became stale or lost
+    pub fn update(&mut self, now: u64, mut on_event: impl FnMut(RegistryEvent)) {
+        let config = self.config;
+        for (team, robots) in [Team::Blue, Team::Yellow]
+            .into_iter()
+            .zip(self.robots.iter_mut())
+        {
+            for (robot_id, robot) in robots.iter_mut().enumerate() {
+                let liveness = robot.liveness_at(now, &config);
+                if liveness == robot.liveness {
+                    continue;
+                }
+                robot.liveness = liveness;
+                let kind = match liveness {
+                    Liveness::Stale => RegistryEventKind::Stale,
+                    Liveness::Lost => RegistryEventKind::Lost,
+                    Liveness::Unknown | Liveness::Connected => continue,
+                };
+                on_event(RegistryEvent {
+                    team,
+                    robot_id: robot_id as u8,
+                    kind,
+                });
+            }
+        }
+    }
+
+    /// Get what is known about a given robot
+    pub fn robot(&self, team: Team, robot_id: u8) -> Option<&RobotEntry> {
+        self.robots.get(team as usize)?.get(robot_id as usize)
+    }
+
+    /// The liveness of a given robot as of the last `record_status` or `update`
+    pub fn liveness(&self, team: Team, robot_id: u8) -> Liveness {
+        self.robot(team, robot_id)
+            .map_or(Liveness::Unknown, |robot| robot.liveness)
+    }
+
+    /// The ids of a team's robots that are connected
+    pub fn connected(&self, team: Team) -> impl Iterator<Item = u8> + '_ {
+        self.robots[team as usize]
+            .iter()
+            .enumerate()
+            .filter(|(_, robot)| robot.liveness == Liveness::Connected)
+            .map(|(robot_id, _)| robot_id as u8)
+    }
+
+    /// Forget everything about a given robot
+    pub fn reset(&mut self, team: Team, robot_id: u8) {
+        if let Some(robot) = self.robot_mut(team, robot_id) {
+            *robot = RobotEntry::new();
+        }
+    }
+
+    fn robot_mut(&mut self, team: Team, robot_id: u8) -> Option<&mut RobotEntry> {
+        self.robots
+            .get_mut(team as usize)?
+            .get_mut(robot_id as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::RobotStatusMessageBuilder;
+
+    /// A healthy status from a yellow robot with a full battery
+    fn status(robot_id: u8) -> RobotStatusMessageBuilder {
+        RobotStatusMessageBuilder::new()
+            .team(Team::Yellow)
+            .robot_id(robot_id)
+            .kick_healthy(true)
+            .fpga_status(true)
+            .battery_voltage(250)
+    }
+
+    /// Record a status, returning the kinds of the events it caused
+    fn record(
+        registry: &mut RobotRegistry,
+        status: RobotStatusMessage,
+        now: u64,
+    ) -> [Option<RegistryEventKind>; 4] {
+        let mut events = [None; 4];
+        let mut count = 0;
+        registry.record_status(&status, now, |event| {
+            assert_eq!((event.team, event.robot_id), (status.team, status.robot_id));
+            events[count] = Some(event.kind);
+            count += 1;
+        });
+        events
+    }
+
+    /// Test that robots become connected, stale and lost as time passes
+    #[test]
+    fn test_registry_liveness() {
+        let mut registry = RobotRegistry::new(RegistryConfig::default());
+        assert_eq!(registry.liveness(Team::Yellow, 2), Liveness::Unknown);
+
+        assert_eq!(
+            record(&mut registry, status(2).build(), 0),
+            [Some(RegistryEventKind::Connected), None, None, None]
+        );
+        assert_eq!(record(&mut registry, status(2).build(), 100_000), [None; 4]);
+        assert!(registry.connected(Team::Yellow).eq([2]));
+        assert_eq!(registry.robot(Team::Yellow, 2).unwrap().packets, 2);
+
+        let mut events = [None; 2];
+        let mut count = 0;
+        let mut update = |registry: &mut RobotRegistry, now| {
+            registry.update(now, |event| {
+                events[count] = Some((event.robot_id, event.kind));
+                count += 1;
+            })
+        };
+        update(&mut registry, 300_000);
+        assert_eq!(registry.liveness(Team::Yellow, 2), Liveness::Connected);
+        update(&mut registry, 400_000);
+        update(&mut registry, 1_200_000);
+        assert_eq!(
+            events,
+            [
+                Some((2, RegistryEventKind::Stale)),
+                Some((2, RegistryEventKind::Lost))
+            ]
+        );
+        assert_eq!(registry.liveness(Team::Yellow, 2), Liveness::Lost);
+        assert_eq!(registry.connected(Team::Yellow).next(), None);
+
+        assert_eq!(
+            record(&mut registry, status(2).build(), 1_300_000),
+            [Some(RegistryEventKind::Connected), None, None, None]
+        );
+
+        registry.record_status(&status(6).build(), 0, |_| panic!("robot 6 does not exist"));
+        registry.reset(Team::Yellow, 2);
+        assert_eq!(
+            registry.robot(Team::Yellow, 2),
+            Some(&RobotEntry::default())
+        );
+    }
+
+    /// Test that health and halt events are reported once when the state of a robot changes
+    #[test]
+    fn test_registry_health_events() {
+        let mut registry = RobotRegistry::new(RegistryConfig::default());
+        record(&mut registry, status(0).build(), 0);
+
+        let unhealthy = status(0)
+            .kick_healthy(false)
+            .fpga_status(false)
+            .battery_voltage(200)
+            .build();
+        assert_eq!(
+            record(&mut registry, unhealthy, 1),
+            [
+                Some(RegistryEventKind::KickerUnhealthy),
+                Some(RegistryEventKind::FpgaFailed),
+                Some(RegistryEventKind::BatteryLow),
+                None
+            ]
+        );
+        assert_eq!(record(&mut registry, unhealthy, 2), [None; 4]);
+
+        record(&mut registry, status(0).build(), 3);
+        assert_eq!(
+            record(&mut registry, status(0).kick_healthy(false).build(), 4),
+            [Some(RegistryEventKind::KickerUnhealthy), None, None, None]
+        );
+
+        assert_eq!(
+            record(&mut registry, status(0).halted(true).build(), 5),
+            [Some(RegistryEventKind::Halted), None, None, None]
+        );
+        assert_eq!(
+            record(&mut registry, status(0).halted(true).build(), 6),
+            [None; 4]
+        );
+        assert_eq!(
+            record(&mut registry, status(0).build(), 7),
+            [Some(RegistryEventKind::Resumed), None, None, None]
+        );
+    }
+}
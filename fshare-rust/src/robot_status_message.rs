@@ -1,38 +1,30 @@
 //!
 //! The Robot Status Message is sent from the robots to the base station and finally to the computer
 //!
+//! The packing code and layout table are generated from `schema/messages.toml`, edit the schema
+//! and run `cargo run -p rtp-codegen` to change the layout.
+//!
 
 #![allow(dead_code)]
 
-use ncomm_utils::packing::{Packable, PackingError};
 use crate::Team;
 
 /// battery_voltage is a direct reading from the micrcontroller's ADC
 /// and must be converted to an actual voltage, which means it should be
 /// multiplied by this scale factor (TODO: Debug the scale factor)
-#[allow(unused)]
 pub const BATTERY_SCALE_FACTOR: f32 = 0.09884;
 
 /// The size of a RobotStatusMessage in Bytes as a constant.
 /// Note: This is tested in the tests so it can be trusted
-pub const ROBOT_STATUS_SIZE: usize = 3;
+pub const ROBOT_STATUS_SIZE: usize = 4;
 
 /// The Robot Status Message is sent back from the robot's whenever they receive communication
 /// to let software know that they are doing good.
 ///
 /// The RobotStatusMessage has the following format:
-/// +---------+---------+---------+---------+---------+---------+---------+---------+
-/// |    7    |    6    |    5    |    4    |    3    |    2    |    1    |    0    |
-/// +---------+---------+---------+---------+---------+---------+---------+---------+
-/// | team    | robot_id                              | b_sense | k_status| k_health|
-/// +---------+---------+---------+---------+---------+---------+---------+---------+
-/// | battery_voltage                                                               |
-/// +---------+---------+---------+---------+---------+---------+---------+---------+
-/// | motor_errors                                    | fpga_s  | unused            |
-/// +---------+---------+---------+---------+---------+---------+---------+---------+
-///
-/// Size = 3 Bytes
+#[doc = include_str!("generated/robot_status_message_layout.txt")]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RobotStatusMessage {
     /// Team of the RObot (0: Blue) (1: Yellow)
     pub team: Team,
@@ -50,51 +42,45 @@ pub struct RobotStatusMessage {
     pub motor_errors: u8,
     /// Status of the FPGA
     pub fpga_status: bool,
+    /// True while the robot is latched in a team halt (see `HaltMessage`)
+    pub halted: bool,
+    /// The sequence number of the last `ControlMessage` the robot received
+    pub sequence: u8,
 }
 
-impl Packable for RobotStatusMessage {
-    fn len() -> usize {
-       ROBOT_STATUS_SIZE 
-    }
-
-    fn pack(self, buffer: &mut [u8]) -> Result<(), PackingError> {
-        if buffer.len() < ROBOT_STATUS_SIZE {
-            return Err(PackingError::InvalidBufferSize);
-        }
-
-        buffer[0] = (self.team as u8) << 7
-            | ((self.robot_id) & 0b1111) << 3
-            | (self.ball_sense_status as u8) << 2
-            | (self.kick_status as u8) << 1
-            | self.kick_healthy as u8;
-        buffer[1] = self.battery_voltage;
-        buffer[2] = (self.motor_errors & 0b11111) << 3 | (self.fpga_status as u8) << 2;
-        Ok(())
+impl RobotStatusMessage {
+    /// Get the battery voltage (in volts) from the robot status message
+    pub fn get_battery_voltage(&self) -> f32 {
+        self.battery_voltage as f32 * BATTERY_SCALE_FACTOR
     }
+}
 
-    fn unpack(data: &[u8]) -> Result<Self, PackingError> {
-        if data.len() < ROBOT_STATUS_SIZE {
-            return Err(PackingError::InvalidBufferSize);
-        }
-
-        Ok(Self {
-            team: if data[0] & (0b1 << 7) == 0 {
-                Team::Blue
-            } else {
-                Team::Yellow
-            },
-            robot_id: (data[0] & (0b1111 << 3)) >> 3,
-            ball_sense_status: data[0] & (0b1 << 2) != 0,
-            kick_status: data[0] & (0b1 << 1) != 0,
-            kick_healthy: data[0] & 0b1 != 0,
-            battery_voltage: data[1],
-            motor_errors: (data[2] & (0b11111 << 3)) >> 3,
-            fpga_status: data[2] & (0b1 << 2) != 0,
-        })
+/// Prints the battery voltage in volts and the motor errors as a bit mask
+#[cfg(feature = "defmt")]
+impl defmt::Format for RobotStatusMessage {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "RobotStatusMessage {{ team: {}, robot_id: {=u8}, ball_sense: {=bool}, \
+             kick_status: {=bool}, kick_healthy: {=bool}, battery: {=f32} V, \
+             motor_errors: {=u8:#b}, fpga_status: {=bool}, halted: {=bool}, \
+             sequence: {=u8} }}",
+            self.team,
+            self.robot_id,
+            self.ball_sense_status,
+            self.kick_status,
+            self.kick_healthy,
+            self.get_battery_voltage(),
+            self.motor_errors,
+            self.fpga_status,
+            self.halted,
+            self.sequence,
+        )
     }
 }
 
 /// Builder helper to create a robot status message
+#[derive(Default)]
 pub struct RobotStatusMessageBuilder {
     /// The team of the robot status message
     pub team: Option<Team>,
@@ -112,6 +98,10 @@ pub struct RobotStatusMessageBuilder {
     pub motor_errors: Option<u8>,
     /// The status of the fpga in the robot status message
     pub fpga_status: Option<bool>,
+    /// Whether or not the robot status message is halted
+    pub halted: Option<bool>,
+    /// The echoed sequence number of the robot status message
+    pub sequence: Option<u8>,
 }
 
 impl RobotStatusMessageBuilder {
@@ -127,6 +117,8 @@ impl RobotStatusMessageBuilder {
             battery_voltage: None,
             motor_errors: None,
             fpga_status: None,
+            halted: None,
+            sequence: None,
         }
     }
 
@@ -178,47 +170,39 @@ impl RobotStatusMessageBuilder {
         self
     }
 
+    /// Assign whether the robot is halted for the robot status message
+    pub fn halted(mut self, halted: bool) -> Self {
+        self.halted = Some(halted);
+        self
+    }
+
+    /// Assign the echoed sequence number for the robot status message
+    pub fn sequence(mut self, sequence: u8) -> Self {
+        self.sequence = Some(sequence);
+        self
+    }
+
     /// Build a new RobotStatusMessage from the assigned fields on the builder
     pub fn build(self) -> RobotStatusMessage {
-        let team = match self.team {
-            Some(team) => team,
-            None => Team::Blue,
-        };
+        let team = self.team.unwrap_or(Team::Blue);
 
-        let robot_id = match self.robot_id {
-            Some(robot_id) => robot_id,
-            None => 0,
-        };
+        let robot_id = self.robot_id.unwrap_or_default();
 
-        let ball_sense_status = match self.ball_sense_status {
-            Some(ball_sense_status) => ball_sense_status,
-            None => false,
-        };
+        let ball_sense_status = self.ball_sense_status.unwrap_or_default();
 
-        let kick_status = match self.kick_status {
-            Some(kick_status) => kick_status,
-            None => false,
-        };
+        let kick_status = self.kick_status.unwrap_or_default();
 
-        let kick_healthy = match self.kick_healthy {
-            Some(kick_healthy) => kick_healthy,
-            None => false,
-        };
+        let kick_healthy = self.kick_healthy.unwrap_or_default();
 
-        let battery_voltage = match self.battery_voltage {
-            Some(battery_voltage) => battery_voltage,
-            None => 0,
-        };
+        let battery_voltage = self.battery_voltage.unwrap_or_default();
 
-        let motor_errors = match self.motor_errors {
-            Some(motor_errors) => motor_errors,
-            None => 0,
-        };
+        let motor_errors = self.motor_errors.unwrap_or_default();
 
-        let fpga_status = match self.fpga_status {
-            Some(fpga_status) => fpga_status,
-            None => false,
-        };
+        let fpga_status = self.fpga_status.unwrap_or_default();
+
+        let halted = self.halted.unwrap_or_default();
+
+        let sequence = self.sequence.unwrap_or_default();
 
         RobotStatusMessage {
             team,
@@ -229,14 +213,19 @@ impl RobotStatusMessageBuilder {
             battery_voltage,
             motor_errors,
             fpga_status,
+            halted,
+            sequence,
         }
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::unusual_byte_groupings)]
 mod tests {
     use super::*;
 
+    use ncomm_utils::packing::Packable;
+
     /// Test that the RobotStatusMessageBuilder uses the correct default fields when
     /// they are not provided.
     #[test]
@@ -245,13 +234,15 @@ mod tests {
 
         let expected = RobotStatusMessage {
             team: Team::Blue,
-            robot_id: 0.into(),
+            robot_id: 0,
             ball_sense_status: false,
             kick_status: false,
             kick_healthy: false,
             battery_voltage: 0,
             motor_errors: 0,
             fpga_status: false,
+            halted: false,
+            sequence: 0,
         };
 
         assert_eq!(expected, robot_status);
@@ -270,17 +261,21 @@ mod tests {
             .battery_voltage(10)
             .motor_errors(2)
             .fpga_status(true)
+            .halted(true)
+            .sequence(17)
             .build();
 
         let expected: RobotStatusMessage = RobotStatusMessage {
             team: Team::Yellow,
-            robot_id: 1.into(),
+            robot_id: 1,
             ball_sense_status: true,
             kick_status: true,
             kick_healthy: true,
             battery_voltage: 10,
             motor_errors: 2,
             fpga_status: true,
+            halted: true,
+            sequence: 17,
         };
 
         assert_eq!(expected, robot_status);
@@ -296,21 +291,25 @@ mod tests {
     ///     battery_voltage: 10,
     ///     motor_errors: 0,
     ///     fpga_status: true,
+    ///     halted: true,
+    ///     sequence: 42,
     /// }
     ///
     /// is as follows:
-    ///     1_0001_1_1_0 | 00001010 | 00000_1_00
-    ///     ^   ^  ^ ^ ^       ^        ^   ^  ^
-    ///     |   |  | | |       |        |   |  |
-    /// team-   |  | | |       |        |   |  |
-    /// robot_id-  | | |       |        |   |  |
-    /// ball_sense-- | |       |        |   |  |
-    /// kick_status--- |       |        |   |  |
-    /// kick_healthy----       |        |   |  |
-    /// battery_voltage---------        |   |  |
-    /// motor_errors---------------------   |  |
-    /// fpga_status--------------------------  |
-    /// unused----------------------------------
+    ///     1_0001_1_1_0 | 00001010 | 00000_1_1_0 | 00101010
+    ///     ^   ^  ^ ^ ^       ^        ^   ^ ^ ^        ^
+    ///     |   |  | | |       |        |   | | |        |
+    /// team-   |  | | |       |        |   | | |        |
+    /// robot_id-  | | |       |        |   | | |        |
+    /// ball_sense-- | |       |        |   | | |        |
+    /// kick_status--- |       |        |   | | |        |
+    /// kick_healthy----       |        |   | | |        |
+    /// battery_voltage---------        |   | | |        |
+    /// motor_errors---------------------   | | |        |
+    /// fpga_status-------------------------- | |        |
+    /// halted--------------------------------- |        |
+    /// unused-----------------------------------        |
+    /// sequence------------------------------------------
     ///
     #[test]
     fn test_pack() {
@@ -322,6 +321,8 @@ mod tests {
             .battery_voltage(10)
             .motor_errors(0)
             .fpga_status(true)
+            .halted(true)
+            .sequence(42)
             .build();
 
         let mut packed_data = [0u8; ROBOT_STATUS_SIZE];
@@ -330,22 +331,25 @@ mod tests {
         assert_eq!(packed_data.len(), ROBOT_STATUS_SIZE);
         assert_eq!(packed_data[0], 0b1_0001_1_1_0);
         assert_eq!(packed_data[1], 0b00001010);
-        assert_eq!(packed_data[2], 0b00000_1_00);
+        assert_eq!(packed_data[2], 0b00000_1_1_0);
+        assert_eq!(packed_data[3], 0b00101010);
     }
 
     /// The Robot Status for the slice:
-    ///     1_0001_1_1_0 | 00001010 | 00000_1_00
-    ///     ^   ^  ^ ^ ^       ^        ^   ^  ^
-    ///     |   |  | | |       |        |   |  |
-    /// team-   |  | | |       |        |   |  |
-    /// robot_id-  | | |       |        |   |  |
-    /// ball_sense-- | |       |        |   |  |
-    /// kick_status--- |       |        |   |  |
-    /// kick_healthy----       |        |   |  |
-    /// battery_voltage---------        |   |  |
-    /// motor_errors---------------------   |  |
-    /// fpga_status--------------------------  |
-    /// unused----------------------------------
+    ///     1_0001_1_1_0 | 00001010 | 00000_1_1_0 | 00101010
+    ///     ^   ^  ^ ^ ^       ^        ^   ^ ^ ^        ^
+    ///     |   |  | | |       |        |   | | |        |
+    /// team-   |  | | |       |        |   | | |        |
+    /// robot_id-  | | |       |        |   | | |        |
+    /// ball_sense-- | |       |        |   | | |        |
+    /// kick_status--- |       |        |   | | |        |
+    /// kick_healthy----       |        |   | | |        |
+    /// battery_voltage---------        |   | | |        |
+    /// motor_errors---------------------   | | |        |
+    /// fpga_status-------------------------- | |        |
+    /// halted--------------------------------- |        |
+    /// unused-----------------------------------        |
+    /// sequence------------------------------------------
     ///
     /// is as follows:
     /// RobotStatusMessage {
@@ -356,10 +360,12 @@ mod tests {
     ///     kick_healthy: false,
     ///     battery_voltage: 10,
     ///     fpga_status: true,
+    ///     halted: true,
+    ///     sequence: 42,
     /// }
     #[test]
     fn test_unpack() {
-        let status_slice: [u8; 3] = [0b1_0001_1_1_0, 0b00001010, 0b00000_1_00];
+        let status_slice: [u8; 4] = [0b1_0001_1_1_0, 0b00001010, 0b00000_1_1_0, 0b00101010];
         let robot_status = RobotStatusMessage::unpack(&status_slice).unwrap();
 
         let expected = RobotStatusMessage {
@@ -371,8 +377,17 @@ mod tests {
             battery_voltage: 10,
             fpga_status: true,
             motor_errors: 0,
+            halted: true,
+            sequence: 42,
         };
 
         assert_eq!(expected, robot_status);
     }
+
+    /// Test that the battery voltage is scaled into volts
+    #[test]
+    fn test_get_battery_voltage() {
+        let robot_status = RobotStatusMessageBuilder::new().battery_voltage(170).build();
+        assert!((robot_status.get_battery_voltage() - 16.8028).abs() < 1e-4);
+    }
 }
@@ -0,0 +1,449 @@
+//!
+//! Base station bookkeeping for the rolling sequence numbers carried by `ControlMessage`s and
+//! echoed back in `RobotStatusMessage`s.
+//!
+//! The `SequenceTracker` stamps outgoing control messages, remembers when each one was sent and
+//! matches replies against them to measure per-robot round trip time, packet loss, duplicated
+//! replies, late replies and replies that arrived out of order.
+//!
+//! The `SequenceLatch` is used on the receiving side of messages that are repeated without
+//! acknowledgements (like `HaltMessage`s) to ignore repeats and late arrivals.
+//!
+//! Timestamps are plain `u64`s from any monotonic clock (e.g. microseconds since startup) and
+//! round trip times are reported in the same unit.
+//!
+
+use crate::{ControlMessage, RobotStatusMessage, Team, ROBOTS_PER_TEAM};
+
+/// The number of control messages per robot that can be awaiting a reply at the same time.
+/// Sending more than this evicts the oldest outstanding message, counting it as lost.
+pub const MAX_OUTSTANDING: usize = 16;
+
+/// Link statistics for a single robot
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LinkStats {
+    /// The number of control messages sent to the robot
+    pub sent: u32,
+    /// The number of replies matched against a sent control message
+    pub received: u32,
+    /// The number of control messages that were never answered
+    pub lost: u32,
+    /// The number of replies to a control message that was already answered (or never sent)
+    pub duplicated: u32,
+    /// The number of replies to a control message that had already been counted as lost
+    /// (because it expired or was evicted).  These are not removed from `lost`
+    pub late: u32,
+    /// The number of replies that arrived after a reply to a newer control message
+    pub reordered: u32,
+    /// The most recent round trip time
+    pub last_rtt: Option<u64>,
+    /// The smallest round trip time seen
+    pub min_rtt: Option<u64>,
+    /// The largest round trip time seen
+    pub max_rtt: Option<u64>,
+    /// The sum of every round trip time (used to compute the mean)
+    pub total_rtt: u64,
+}
+
+impl LinkStats {
+    /// The mean round trip time of every matched reply
+    pub fn mean_rtt(&self) -> Option<u64> {
+        if self.received == 0 {
+            return None;
+        }
+        Some(self.total_rtt / self.received as u64)
+    }
+
+    /// The fraction of resolved control messages (answered or lost) that were lost
+    pub fn loss_ratio(&self) -> f32 {
+        let resolved = self.received + self.lost;
+        if resolved == 0 {
+            return 0.0;
+        }
+        self.lost as f32 / resolved as f32
+    }
+}
+
+/// A control message awaiting a reply
+#[derive(Clone, Copy, Debug)]
+struct Outstanding {
+    sequence: u8,
+    sent_at: u64,
+}
+
+/// Sequence bookkeeping for a single robot
+#[derive(Clone, Copy, Debug)]
+struct RobotTracker {
+    next_sequence: u8,
+    last_received: Option<u8>,
+    outstanding: [Option<Outstanding>; MAX_OUTSTANDING],
+    /// A bit for every sequence number that was counted as lost and has not been answered since
+    lost: [u32; 8],
+    stats: LinkStats,
+}
+
+impl RobotTracker {
+    const fn new() -> Self {
+        Self {
+            next_sequence: 0,
+            last_received: None,
+            outstanding: [None; MAX_OUTSTANDING],
+            lost: [0; 8],
+            stats: LinkStats {
+                sent: 0,
+                received: 0,
+                lost: 0,
+                duplicated: 0,
+                late: 0,
+                reordered: 0,
+                last_rtt: None,
+                min_rtt: None,
+                max_rtt: None,
+                total_rtt: 0,
+            },
+        }
+    }
+
+    fn record_sent(&mut self, sequence: u8, now: u64) {
+        self.stats.sent += 1;
+
+        // Resending a sequence number that is still outstanding means the old one was lost.
+        // Replies to it can no longer be told apart from replies to the new one
+        for slot in self.outstanding.iter_mut() {
+            if matches!(slot, Some(entry) if entry.sequence == sequence) {
+                *slot = None;
+                self.stats.lost += 1;
+            }
+        }
+        self.set_lost(sequence, false);
+
+        let slot = match self.outstanding.iter().position(Option::is_none) {
+            Some(index) => index,
+            None => {
+                // Evict the oldest outstanding message
+                let index = self
+                    .outstanding
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, entry)| entry.map(|entry| entry.sent_at))
+                    .map(|(index, _)| index)
+                    .unwrap_or(0);
+                if let Some(entry) = self.outstanding[index] {
+                    self.set_lost(entry.sequence, true);
+                }
+                self.stats.lost += 1;
+                index
+            }
+        };
+        self.outstanding[slot] = Some(Outstanding {
+            sequence,
+            sent_at: now,
+        });
+    }
+
+    fn record_received(&mut self, sequence: u8, now: u64) -> Option<u64> {
+        let slot = self
+            .outstanding
+            .iter()
+            .position(|entry| matches!(entry, Some(entry) if entry.sequence == sequence));
+        let Some(slot) = slot else {
+            if self.is_lost(sequence) {
+                self.set_lost(sequence, false);
+                self.stats.late += 1;
+            } else {
+                self.stats.duplicated += 1;
+            }
+            return None;
+        };
+        let entry = self.outstanding[slot].take().unwrap();
+
+        if let Some(last_received) = self.last_received {
+            if is_before(sequence, last_received) {
+                self.stats.reordered += 1;
+            } else {
+                self.last_received = Some(sequence);
+            }
+        } else {
+            self.last_received = Some(sequence);
+        }
+
+        let rtt = now.saturating_sub(entry.sent_at);
+        self.stats.received += 1;
+        self.stats.last_rtt = Some(rtt);
+        self.stats.min_rtt = Some(self.stats.min_rtt.map_or(rtt, |min| min.min(rtt)));
+        self.stats.max_rtt = Some(self.stats.max_rtt.map_or(rtt, |max| max.max(rtt)));
+        self.stats.total_rtt += rtt;
+        Some(rtt)
+    }
+
+    fn expire(&mut self, now: u64, timeout: u64) {
+        for index in 0..MAX_OUTSTANDING {
+            if let Some(entry) = self.outstanding[index] {
+                if now.saturating_sub(entry.sent_at) > timeout {
+                    self.outstanding[index] = None;
+                    self.set_lost(entry.sequence, true);
+                    self.stats.lost += 1;
+                }
+            }
+        }
+    }
+
+    fn is_lost(&self, sequence: u8) -> bool {
+        self.lost[sequence as usize / 32] & (1 << (sequence % 32)) != 0
+    }
+
+    fn set_lost(&mut self, sequence: u8, lost: bool) {
+        let bit = 1 << (sequence % 32);
+        if lost {
+            self.lost[sequence as usize / 32] |= bit;
+        } else {
+            self.lost[sequence as usize / 32] &= !bit;
+        }
+    }
+}
+
+/// Is sequence number `a` older than sequence number `b` (accounting for wrap around)
+pub(crate) fn is_before(a: u8, b: u8) -> bool {
+    (a.wrapping_sub(b) as i8) < 0
+}
+
+/// The default time (in microseconds) after which a `SequenceLatch` accepts any sequence number
+/// again
+pub const DEFAULT_RESYNC_TIMEOUT: u64 = 1_000_000;
+
+/// Accepts only the messages whose sequence number is newer than the last one accepted, so
+/// repeats and late arrivals of a message that is sent without acknowledgements are ignored.
+///
+/// A sender that restarts starts counting from 0 again, so once no message has been accepted for
+/// `timeout` the next message is accepted whatever its sequence number.  After a restart the
+/// receiver ignores the new messages for at most `timeout`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SequenceLatch {
+    timeout: u64,
+    /// The sequence number of the last message accepted and when it was accepted
+    last: Option<(u8, u64)>,
+}
+
+impl SequenceLatch {
+    /// Create a latch that accepts the first message it sees and re-syncs after `timeout`
+    pub const fn new(timeout: u64) -> Self {
+        Self {
+            timeout,
+            last: None,
+        }
+    }
+
+    /// Should a message with a given sequence number received at `now` be accepted (recording it
+    /// as the last message accepted if it is)
+    pub fn accept(&mut self, sequence: u8, now: u64) -> bool {
+        if let Some((last, accepted_at)) = self.last {
+            if !is_before(last, sequence) && now.saturating_sub(accepted_at) <= self.timeout {
+                return false;
+            }
+        }
+        self.last = Some((sequence, now));
+        true
+    }
+
+    /// Forget the last message accepted so the next message is accepted whatever its sequence
+    /// number
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+/// Tracks outstanding sequence numbers for every robot on both teams
+#[derive(Clone, Debug)]
+pub struct SequenceTracker {
+    /// How long to wait for a reply before a control message is counted as lost
+    timeout: u64,
+    robots: [[RobotTracker; ROBOTS_PER_TEAM]; 2],
+}
+
+impl SequenceTracker {
+    /// Create a new tracker that counts control messages as lost if they are not answered
+    /// within `timeout`
+    pub const fn new(timeout: u64) -> Self {
+        Self {
+            timeout,
+            robots: [[RobotTracker::new(); ROBOTS_PER_TEAM]; 2],
+        }
+    }
+
+    /// Get the next sequence number to send to a given robot.
+    ///
+    /// Note: robot ids that are out of range always receive sequence number 0
+    pub fn next_sequence(&mut self, team: Team, robot_id: u8) -> u8 {
+        match self.robot_mut(team, robot_id) {
+            Some(robot) => {
+                let sequence = robot.next_sequence;
+                robot.next_sequence = sequence.wrapping_add(1);
+                sequence
+            }
+            None => 0,
+        }
+    }
+
+    /// Record that a control message was sent at `now`
+    pub fn record_sent(&mut self, message: &ControlMessage, now: u64) {
+        if let Some(robot) = self.robot_mut(message.team, message.robot_id) {
+            robot.record_sent(message.sequence, now);
+        }
+    }
+
+    /// Record that a robot status was received at `now`, returning the round trip time if it
+    /// answers an outstanding control message
+    pub fn record_received(&mut self, status: &RobotStatusMessage, now: u64) -> Option<u64> {
+        self.robot_mut(status.team, status.robot_id)?
+            .record_received(status.sequence, now)
+    }
+
+    /// Count every control message that has been awaiting a reply for longer than the timeout
+    /// as lost
+    pub fn expire(&mut self, now: u64) {
+        let timeout = self.timeout;
+        for robot in self.robots.iter_mut().flatten() {
+            robot.expire(now, timeout);
+        }
+    }
+
+    /// Get the link statistics for a given robot
+    pub fn stats(&self, team: Team, robot_id: u8) -> Option<&LinkStats> {
+        self.robots
+            .get(team as usize)?
+            .get(robot_id as usize)
+            .map(|robot| &robot.stats)
+    }
+
+    /// Clear the statistics and outstanding messages of a given robot
+    pub fn reset(&mut self, team: Team, robot_id: u8) {
+        if let Some(robot) = self.robot_mut(team, robot_id) {
+            *robot = RobotTracker::new();
+        }
+    }
+
+    fn robot_mut(&mut self, team: Team, robot_id: u8) -> Option<&mut RobotTracker> {
+        self.robots
+            .get_mut(team as usize)?
+            .get_mut(robot_id as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{ControlMessageBuilder, RobotStatusMessageBuilder};
+
+    fn send(tracker: &mut SequenceTracker, robot_id: u8, now: u64) -> u8 {
+        let sequence = tracker.next_sequence(Team::Blue, robot_id);
+        let message = ControlMessageBuilder::new()
+            .robot_id(robot_id)
+            .sequence(sequence)
+            .build();
+        tracker.record_sent(&message, now);
+        sequence
+    }
+
+    fn reply(tracker: &mut SequenceTracker, robot_id: u8, sequence: u8, now: u64) -> Option<u64> {
+        let status = RobotStatusMessageBuilder::new()
+            .robot_id(robot_id)
+            .sequence(sequence)
+            .build();
+        tracker.record_received(&status, now)
+    }
+
+    /// Test that round trip times, duplicates, reordering and loss are reported per robot
+    #[test]
+    fn test_sequence_tracker_statistics() {
+        let mut tracker = SequenceTracker::new(100);
+
+        let first = send(&mut tracker, 2, 0);
+        let second = send(&mut tracker, 2, 10);
+        let third = send(&mut tracker, 2, 20);
+        let _lost = send(&mut tracker, 2, 30);
+
+        assert_eq!(reply(&mut tracker, 2, second, 15), Some(5));
+        assert_eq!(reply(&mut tracker, 2, first, 25), Some(25));
+        assert_eq!(reply(&mut tracker, 2, first, 26), None);
+        assert_eq!(reply(&mut tracker, 2, third, 32), Some(12));
+
+        tracker.expire(200);
+
+        let stats = tracker.stats(Team::Blue, 2).unwrap();
+        assert_eq!(stats.sent, 4);
+        assert_eq!(stats.received, 3);
+        assert_eq!(stats.lost, 1);
+        assert_eq!(stats.duplicated, 1);
+        assert_eq!(stats.late, 0);
+        assert_eq!(stats.reordered, 1);
+        assert_eq!(stats.min_rtt, Some(5));
+        assert_eq!(stats.max_rtt, Some(25));
+        assert_eq!(stats.mean_rtt(), Some(14));
+        assert_eq!(stats.loss_ratio(), 0.25);
+
+        assert_eq!(tracker.stats(Team::Blue, 3).unwrap().sent, 0);
+        assert!(tracker.stats(Team::Blue, 6).is_none());
+    }
+
+    /// Test that sequence numbers wrap around and that wrapped replies are not counted as
+    /// reordered
+    #[test]
+    fn test_sequence_tracker_wraps() {
+        let mut tracker = SequenceTracker::new(100);
+
+        for now in 0..300 {
+            let sequence = send(&mut tracker, 0, now);
+            assert_eq!(sequence, now as u8);
+            assert_eq!(reply(&mut tracker, 0, sequence, now + 1), Some(1));
+        }
+
+        let stats = tracker.stats(Team::Blue, 0).unwrap();
+        assert_eq!(stats.received, 300);
+        assert_eq!(stats.reordered, 0);
+        assert_eq!(stats.lost, 0);
+    }
+
+    /// Test that replies to expired or evicted control messages are counted as late instead of
+    /// duplicated
+    #[test]
+    fn test_sequence_tracker_late_replies() {
+        let mut tracker = SequenceTracker::new(100);
+
+        let expired = send(&mut tracker, 1, 0);
+        tracker.expire(200);
+        assert_eq!(reply(&mut tracker, 1, expired, 210), None);
+        assert_eq!(reply(&mut tracker, 1, expired, 220), None);
+
+        let evicted = send(&mut tracker, 1, 300);
+        for now in 0..MAX_OUTSTANDING as u64 {
+            send(&mut tracker, 1, 301 + now);
+        }
+        assert_eq!(reply(&mut tracker, 1, evicted, 400), None);
+
+        let stats = tracker.stats(Team::Blue, 1).unwrap();
+        assert_eq!(stats.lost, 2);
+        assert_eq!(stats.late, 2);
+        assert_eq!(stats.duplicated, 1);
+        assert_eq!(stats.received, 0);
+    }
+
+    /// Test that the latch ignores repeated and late sequence numbers until it re-syncs
+    #[test]
+    fn test_sequence_latch() {
+        let mut latch = SequenceLatch::new(100);
+        assert!(latch.accept(100, 0));
+        assert!(!latch.accept(100, 10));
+        assert!(!latch.accept(50, 20));
+        assert!(latch.accept(101, 30));
+
+        // the sender restarted, its messages are ignored until the timeout
+        assert!(!latch.accept(0, 40));
+        assert!(!latch.accept(0, 130));
+        assert!(latch.accept(0, 131));
+        assert!(latch.accept(1, 132));
+
+        latch.reset();
+        assert!(latch.accept(0, 133));
+    }
+}
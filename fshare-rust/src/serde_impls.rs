@@ -0,0 +1,119 @@
+//!
+//! Helpers for the `serde` feature that are used with `#[serde(with = "...")]` on fields whose
+//! packed representation is not the one a person wants to read.
+//!
+
+/// Serialize a velocity packed with a `FixedPoint` encoding as a float in m/s (or rad/s).
+///
+/// Deserializing rounds to the nearest packed value (saturating at the limits of an `i16`) so a
+/// serialized message always deserializes back into the same message.
+mod velocity {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::control_message::{Overflow, Rounding};
+    use crate::fixed_point::FixedPoint;
+
+    pub fn serialize<S: Serializer>(
+        encoding: FixedPoint,
+        value: &i16,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f32(encoding.decode(*value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        encoding: FixedPoint,
+        deserializer: D,
+    ) -> Result<i16, D::Error> {
+        let value = f32::deserialize(deserializer)?;
+        Ok(encoding
+            .encode(value, Rounding::Nearest, Overflow::Saturate)
+            .unwrap_or_default())
+    }
+}
+
+/// Serialize a linear velocity (body_x, body_y) as a float in m/s.
+pub(crate) mod linear_velocity {
+    use serde::{Deserializer, Serializer};
+
+    use crate::fixed_point::VelocityEncoding;
+
+    pub fn serialize<S: Serializer>(value: &i16, serializer: S) -> Result<S::Ok, S::Error> {
+        super::velocity::serialize(VelocityEncoding::CURRENT.linear, value, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i16, D::Error> {
+        super::velocity::deserialize(VelocityEncoding::CURRENT.linear, deserializer)
+    }
+}
+
+/// Serialize an angular velocity (body_w) as a float in rad/s.
+pub(crate) mod angular_velocity {
+    use serde::{Deserializer, Serializer};
+
+    use crate::fixed_point::VelocityEncoding;
+
+    pub fn serialize<S: Serializer>(value: &i16, serializer: S) -> Result<S::Ok, S::Error> {
+        super::velocity::serialize(VelocityEncoding::CURRENT.angular, value, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i16, D::Error> {
+        super::velocity::deserialize(VelocityEncoding::CURRENT.angular, deserializer)
+    }
+}
+
+/// Serialize an array of any length as a sequence.
+///
+/// serde only implements `Serialize` and `Deserialize` for arrays of up to 32 elements and not
+/// for arrays with a const generic length.
+pub(crate) mod array {
+    use core::fmt;
+    use core::marker::PhantomData;
+
+    use serde::de::{Error, SeqAccess, Visitor};
+    use serde::ser::SerializeTuple;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer, T: Serialize, const N: usize>(
+        values: &[T; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(N)?;
+        for value in values {
+            tuple.serialize_element(value)?;
+        }
+        tuple.end()
+    }
+
+    pub fn deserialize<'de, D, T, const N: usize>(deserializer: D) -> Result<[T; N], D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de> + Copy,
+    {
+        struct ArrayVisitor<T, const N: usize>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de> + Copy, const N: usize> Visitor<'de> for ArrayVisitor<T, N> {
+            type Value = [T; N];
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "an array of {} elements", N)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut values = [None; N];
+                for (index, value) in values.iter_mut().enumerate() {
+                    *value = Some(
+                        seq.next_element()?
+                            .ok_or_else(|| A::Error::invalid_length(index, &self))?,
+                    );
+                }
+                if seq.next_element::<T>()?.is_some() {
+                    return Err(A::Error::invalid_length(N + 1, &self));
+                }
+                Ok(values.map(|value| value.unwrap()))
+            }
+        }
+
+        deserializer.deserialize_tuple(N, ArrayVisitor(PhantomData))
+    }
+}
@@ -0,0 +1,630 @@
+//!
+//! A simulated robot that answers `ControlMessage`s the way the firmware does so base station
+//! code can be tested without physical robots.
+//!
+//! The robot replies with the kind of message `MessageKind::reply_to` its `Mode`:
+//! - `Default`: a `RobotStatusMessage` echoing the sequence number of the control message
+//! - `ImuTest`: a stream of `ImuTestMessage`s (the first and last are flagged)
+//! - `ReceiveBenchmark` and `SendBenchmark`: the benchmark results since the mode was entered
+//! - `ProgramKickOnBreakbeam` and `ProgramKicker`: a `KickerProgramMessage` for the next page
+//! - `KickerTest`: a `KickerTestingMessage`
+//! - `FpgaTest`: a `ControlTestMessage`
+//!
+//! Between control messages the commanded body velocity is integrated into a pose on the field,
+//! the wheels spin at the speeds given by the `RobotModel` and the battery drains with the
+//! wheel speeds.  Timestamps are `u64` microseconds from any monotonic clock.
+//!
+//! A `HaltMessage` for the robot's team stops it (and stands the kicker down) until its team is
+//! resumed, the robot keeps replying while it is halted.  The commands are also limited by the
+//! last `GameStateMessage` for its team like the firmware does.
+//!
+
+use nalgebra::base::*;
+use nalgebra::ComplexField;
+
+use crate::control_message::{Mode, ShootMode, TriggerMode, CONTROL_MESSAGE_SIZE};
+use crate::control_test_message::ControlTestMessage;
+use crate::error::check_len;
+use crate::game_state::{GameStateLimiter, GameStateLimits, GameStateMessage};
+use crate::imu_test_message::ImuTestMessage;
+use crate::kicker_program_message::KickerProgramMessage;
+use crate::kicker_testing::KickerTestingMessage;
+use crate::radio_benchmarks::{RadioReceiveBenchmarkMessage, RadioSendBenchmarkMessage};
+use crate::robot_status_message::BATTERY_SCALE_FACTOR;
+use crate::sequence::DEFAULT_RESYNC_TIMEOUT;
+use crate::{
+    ControlMessage, HaltLatch, HaltMessage, RobotModel, RobotStatusMessage, RtpError, RtpMessage,
+    Team,
+};
+use ncomm_utils::packing::Packable;
+
+/// The largest reply a robot sends (in Bytes)
+const MAX_REPLY_SIZE: usize = crate::envelope::MAX_ENVELOPE_SIZE;
+
+/// The parameters of a simulated robot
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SimulatedRobotConfig {
+    /// The kinematics of the robot
+    pub model: RobotModel,
+    /// The voltage of a fully charged battery (V)
+    pub battery_full: f32,
+    /// The voltage of an empty battery (V).  The battery never drains below this
+    pub battery_empty: f32,
+    /// The voltage drained every second while the robot is idle (V/s)
+    pub idle_drain: f32,
+    /// The additional voltage drained every second for each rad/s of wheel speed (V/s)
+    pub drive_drain: f32,
+    /// The voltage of a fully charged kicker
+    pub kicker_voltage: u8,
+    /// The time it takes to charge (or stand down) the kicker (us)
+    pub kicker_charge_time: u64,
+    /// The time after which ball sense toggles (us), or `None` if ball sense only changes
+    /// when the ball is kicked or `SimulatedRobot::set_ball_sense` is called
+    pub ball_sense_period: Option<u64>,
+    /// The number of `ImuTestMessage`s sent in reply to each control message in `ImuTest` mode
+    pub imu_samples: u32,
+    /// The number of pages programmed into the kicker
+    pub kicker_pages: u32,
+    /// The limits placed on the robot by each game state
+    pub game_state_limits: GameStateLimits,
+}
+
+impl Default for SimulatedRobotConfig {
+    /// A robot with a 6 cell battery and the current wheel layout
+    fn default() -> Self {
+        Self {
+            model: RobotModel::default(),
+            battery_full: 25.2,
+            battery_empty: 19.8,
+            idle_drain: 0.001,
+            drive_drain: 0.0001,
+            kicker_voltage: 200,
+            kicker_charge_time: 2_000_000,
+            ball_sense_period: None,
+            imu_samples: 8,
+            kicker_pages: 16,
+            game_state_limits: GameStateLimits::default(),
+        }
+    }
+}
+
+/// A robot that replies to control messages without any hardware
+#[derive(Clone, Debug)]
+pub struct SimulatedRobot {
+    team: Team,
+    robot_id: u8,
+    config: SimulatedRobotConfig,
+    /// x (m), y (m) and heading (rad) on the field
+    pose: Vector3<f32>,
+    /// The commanded body velocity (x m/s, y m/s, w rad/s)
+    velocity: Vector3<f32>,
+    /// The change of the body velocity by the last control message (m/s^2, rad/s^2)
+    acceleration: Vector3<f32>,
+    battery: f32,
+    kicker_voltage: f32,
+    charging: bool,
+    ball_sense: bool,
+    ball_sense_changed: u64,
+    kicks: u32,
+    last_update: Option<u64>,
+    /// The time between the last two control messages (us)
+    delta: u64,
+    mode: Mode,
+    mode_entered: u64,
+    /// The number of control messages received since the mode was entered
+    mode_messages: u32,
+    kicker_page: u32,
+    halt: HaltLatch,
+    game_state: GameStateLimiter,
+}
+
+impl SimulatedRobot {
+    /// Create a robot at the origin of the field with a full battery and an empty kicker
+    pub fn new(team: Team, robot_id: u8, config: SimulatedRobotConfig) -> Self {
+        Self {
+            team,
+            robot_id,
+            config,
+            pose: Vector3::zeros(),
+            velocity: Vector3::zeros(),
+            acceleration: Vector3::zeros(),
+            battery: config.battery_full,
+            kicker_voltage: 0.0,
+            charging: false,
+            ball_sense: false,
+            ball_sense_changed: 0,
+            kicks: 0,
+            last_update: None,
+            delta: 0,
+            mode: Mode::Default,
+            mode_entered: 0,
+            mode_messages: 0,
+            kicker_page: 0,
+            halt: HaltLatch::new(team, DEFAULT_RESYNC_TIMEOUT),
+            game_state: GameStateLimiter::new(team, config.game_state_limits),
+        }
+    }
+
+    /// The team of the robot
+    pub fn team(&self) -> Team {
+        self.team
+    }
+
+    /// The id of the robot
+    pub fn robot_id(&self) -> u8 {
+        self.robot_id
+    }
+
+    /// The position (x m, y m) and heading (rad) of the robot on the field
+    pub fn pose(&self) -> Vector3<f32> {
+        self.pose
+    }
+
+    /// Move the robot to a position (x m, y m) and heading (rad) on the field
+    pub fn set_pose(&mut self, pose: Vector3<f32>) {
+        self.pose = pose;
+    }
+
+    /// The body velocity (x m/s, y m/s, w rad/s) the robot was last commanded
+    pub fn velocity(&self) -> Vector3<f32> {
+        self.velocity
+    }
+
+    /// The speed of each wheel (rad/s)
+    pub fn wheel_speeds(&self) -> Vector4<f32> {
+        self.config.model.bot_to_wheel_speeds(self.velocity)
+    }
+
+    /// The voltage of the battery (V)
+    pub fn battery_voltage(&self) -> f32 {
+        self.battery
+    }
+
+    /// The voltage of the kicker
+    pub fn kicker_voltage(&self) -> u8 {
+        (self.kicker_voltage + 0.5) as u8
+    }
+
+    /// Is the ball in the dribbler
+    pub fn ball_sense(&self) -> bool {
+        self.ball_sense
+    }
+
+    /// Put the ball into (or take it out of) the dribbler
+    pub fn set_ball_sense(&mut self, ball_sense: bool) {
+        self.ball_sense = ball_sense;
+    }
+
+    /// The number of times the robot has kicked
+    pub fn kicks(&self) -> u32 {
+        self.kicks
+    }
+
+    /// The mode the robot is in
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Is the robot latched in a halt
+    pub fn is_halted(&self) -> bool {
+        self.halt.is_halted()
+    }
+
+    /// Handle a `HaltMessage`, stopping the robot immediately when it is halted.
+    ///
+    /// Halt messages for other teams and repeated or late halt messages are ignored (see
+    /// `HaltLatch`)
+    pub fn handle_halt(&mut self, halt: &HaltMessage, now: u64) {
+        let mut latch = self.halt;
+        if !latch.receive(halt, now) {
+            return;
+        }
+        self.update(now);
+        self.halt = latch;
+        if self.is_halted() {
+            self.acceleration = Vector3::zeros();
+            self.velocity = Vector3::zeros();
+            self.charging = false;
+        }
+    }
+
+    /// Handle a `GameStateMessage`.
+    ///
+    /// Game state messages for other teams and repeated or late game state messages are ignored
+    pub fn handle_game_state(&mut self, message: &GameStateMessage, now: u64) {
+        self.game_state.receive(message, now);
+    }
+
+    /// Handle an enveloped message sent to the team's halt address (a `HaltMessage` or a
+    /// `GameStateMessage`), ignoring every other kind of message
+    pub fn receive_team(&mut self, data: &[u8], now: u64) -> Result<(), RtpError> {
+        match RtpMessage::decode(data)? {
+            RtpMessage::Halt(halt) => self.handle_halt(&halt, now),
+            RtpMessage::GameState(message) => self.handle_game_state(&message, now),
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Handle a packed `ControlMessage`, calling `reply` with each packed reply.
+    ///
+    /// Control messages for other robots are ignored
+    pub fn receive(
+        &mut self,
+        data: &[u8],
+        now: u64,
+        mut reply: impl FnMut(&[u8]),
+    ) -> Result<(), RtpError> {
+        check_len(data, CONTROL_MESSAGE_SIZE)?;
+        let control = ControlMessage::unpack(data)?;
+
+        let mut buffer = [0u8; MAX_REPLY_SIZE];
+        self.handle(&control, now, |message| {
+            // the buffer fits the largest message so packing cannot fail
+            let written = message.encode_payload(&mut buffer).unwrap();
+            reply(&buffer[..written]);
+        });
+        Ok(())
+    }
+
+    /// Handle a `ControlMessage`, calling `reply` with each reply.
+    ///
+    /// Control messages for other robots are ignored
+    pub fn handle(
+        &mut self,
+        control: &ControlMessage,
+        now: u64,
+        mut reply: impl FnMut(RtpMessage),
+    ) {
+        if control.team != self.team || control.robot_id != self.robot_id {
+            return;
+        }
+        let control = &self.game_state.limit(*control);
+
+        let dt = self.update(now);
+        if control.mode != self.mode {
+            self.mode = control.mode;
+            self.mode_entered = now;
+            self.mode_messages = 0;
+            self.kicker_page = 0;
+        }
+        self.mode_messages = self.mode_messages.saturating_add(1);
+
+        let velocity = if self.is_halted() {
+            Vector3::zeros()
+        } else {
+            control.get_velocity()
+        };
+        self.acceleration = if dt > 0.0 {
+            (velocity - self.velocity) / dt
+        } else {
+            Vector3::zeros()
+        };
+        self.velocity = velocity;
+        self.charging = !self.is_halted() && control.trigger_mode != TriggerMode::StandDown;
+        if !self.is_halted() {
+            self.trigger_kicker(control, now);
+        }
+
+        match self.mode {
+            Mode::Default => reply(RtpMessage::RobotStatus(self.status(control.sequence))),
+            Mode::ImuTest => {
+                for sample in 0..self.config.imu_samples {
+                    reply(RtpMessage::ImuTest(ImuTestMessage {
+                        first_message: sample == 0,
+                        last_message: sample + 1 == self.config.imu_samples,
+                        gyro_z: self.velocity.z,
+                        accel_x: self.acceleration.x,
+                        accel_y: self.acceleration.y,
+                    }));
+                }
+            }
+            Mode::ReceiveBenchmark => reply(RtpMessage::RadioReceiveBenchmark(
+                RadioReceiveBenchmarkMessage {
+                    receive_time_ms: (now.saturating_sub(self.mode_entered) / 1000) as u32,
+                    received_packets: self.mode_messages,
+                },
+            )),
+            Mode::SendBenchmark => {
+                // every reply of the simulated robot is acknowledged
+                reply(RtpMessage::RadioSendBenchmark(RadioSendBenchmarkMessage {
+                    acknowledged_packets: self.mode_messages,
+                    sent_packets: self.mode_messages,
+                }))
+            }
+            Mode::ProgramKickOnBreakbeam | Mode::ProgramKicker => {
+                let last_page = self.config.kicker_pages.saturating_sub(1);
+                reply(RtpMessage::KickerProgram(KickerProgramMessage {
+                    kick_on_breakbeam: self.mode == Mode::ProgramKickOnBreakbeam,
+                    finished: self.kicker_page >= last_page,
+                    page: self.kicker_page,
+                }));
+                self.kicker_page = (self.kicker_page + 1).min(last_page);
+            }
+            Mode::KickerTest => reply(RtpMessage::KickerTesting(KickerTestingMessage {
+                healthy: true,
+                ball_sense: self.ball_sense,
+                kicking: control.shoot_mode == ShootMode::Kick,
+                kick_on_ball_sense: control.trigger_mode == TriggerMode::OnBreakBeam,
+                kick_immediately: control.trigger_mode == TriggerMode::Immediate,
+                voltage: self.kicker_voltage(),
+            })),
+            Mode::FpgaTest => {
+                let wheel_speeds = self.wheel_speeds();
+                reply(RtpMessage::ControlTest(ControlTestMessage {
+                    gyro_z: self.velocity.z,
+                    accel_x: self.acceleration.x,
+                    accel_y: self.acceleration.y,
+                    motor_encoders: [
+                        wheel_speeds[0],
+                        wheel_speeds[1],
+                        wheel_speeds[2],
+                        wheel_speeds[3],
+                    ],
+                    delta: self.delta.min(u16::MAX as u64) as u16,
+                }))
+            }
+        }
+    }
+
+    /// The status the robot reports in `Default` mode
+    fn status(&self, sequence: u8) -> RobotStatusMessage {
+        RobotStatusMessage {
+            team: self.team,
+            robot_id: self.robot_id,
+            ball_sense_status: self.ball_sense,
+            kick_status: self.kicker_charged(),
+            kick_healthy: true,
+            battery_voltage: (self.battery / BATTERY_SCALE_FACTOR + 0.5) as u8,
+            motor_errors: 0,
+            fpga_status: true,
+            halted: self.is_halted(),
+            sequence,
+        }
+    }
+
+    /// Is the kicker charged enough to kick
+    fn kicker_charged(&self) -> bool {
+        self.kicker_voltage() >= self.config.kicker_voltage
+    }
+
+    /// Kick the ball if the control message asks for a kick and the kicker is charged
+    fn trigger_kicker(&mut self, control: &ControlMessage, now: u64) {
+        let triggered = match control.trigger_mode {
+            TriggerMode::StandDown => false,
+            TriggerMode::Immediate => true,
+            TriggerMode::OnBreakBeam => self.ball_sense,
+        };
+        if triggered && control.kick_strength > 0 && self.kicker_charged() {
+            self.kicks += 1;
+            self.kicker_voltage = 0.0;
+            self.ball_sense = false;
+            self.ball_sense_changed = now;
+        }
+    }
+
+    /// Advance the simulation to `now`, returning the elapsed time (s)
+    fn update(&mut self, now: u64) -> f32 {
+        let Some(last_update) = self.last_update.replace(now) else {
+            self.ball_sense_changed = now;
+            return 0.0;
+        };
+        self.delta = now.saturating_sub(last_update);
+        let dt = self.delta as f32 / 1_000_000.0;
+
+        // integrate the body velocity (rotated by the mean heading over the step) into the pose
+        let heading = self.pose.z + self.velocity.z * dt / 2.0;
+        let (sin, cos) = (ComplexField::sin(heading), ComplexField::cos(heading));
+        self.pose.x += (self.velocity.x * cos - self.velocity.y * sin) * dt;
+        self.pose.y += (self.velocity.x * sin + self.velocity.y * cos) * dt;
+        self.pose.z += self.velocity.z * dt;
+
+        let wheel_speed: f32 = self.wheel_speeds().iter().map(|speed| speed.abs()).sum();
+        let drain = (self.config.idle_drain + self.config.drive_drain * wheel_speed) * dt;
+        self.battery = (self.battery - drain).max(self.config.battery_empty);
+
+        let full = self.config.kicker_voltage as f32;
+        let charge = full * self.delta as f32 / self.config.kicker_charge_time.max(1) as f32;
+        self.kicker_voltage = if self.charging {
+            (self.kicker_voltage + charge).min(full)
+        } else {
+            (self.kicker_voltage - charge).max(0.0)
+        };
+
+        if let Some(period) = self.config.ball_sense_period.filter(|period| *period > 0) {
+            let toggles = now.saturating_sub(self.ball_sense_changed) / period;
+            if toggles % 2 == 1 {
+                self.ball_sense = !self.ball_sense;
+            }
+            self.ball_sense_changed += toggles * period;
+        }
+
+        dt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{ControlMessageBuilder, GameState};
+
+    /// A control message for robot 1 on the blue team
+    fn control(mode: Mode, sequence: u8) -> ControlMessageBuilder {
+        ControlMessageBuilder::new()
+            .team(Team::Blue)
+            .robot_id(1)
+            .mode(mode)
+            .sequence(sequence)
+    }
+
+    /// Collect the replies to a control message
+    fn replies(
+        robot: &mut SimulatedRobot,
+        control: ControlMessage,
+        now: u64,
+    ) -> [Option<RtpMessage>; 8] {
+        let mut replies = [None; 8];
+        let mut count = 0;
+        robot.handle(&control, now, |reply| {
+            replies[count] = Some(reply);
+            count += 1;
+        });
+        replies
+    }
+
+    /// Test that the robot echoes the sequence number, integrates its velocity, drains its
+    /// battery, stops while halted and slows down during a stop, and that packed control
+    /// messages for other robots are ignored
+    #[test]
+    fn test_simulated_robot_status() {
+        let mut robot = SimulatedRobot::new(Team::Blue, 1, SimulatedRobotConfig::default());
+
+        let command = control(Mode::Default, 7).body_x(1.0).build();
+        let [Some(RtpMessage::RobotStatus(status)), None, ..] = replies(&mut robot, command, 0)
+        else {
+            panic!("expected a single robot status message");
+        };
+        assert_eq!(status.sequence, 7);
+        assert_eq!((status.team, status.robot_id), (Team::Blue, 1));
+        assert!((status.get_battery_voltage() - 25.2).abs() < BATTERY_SCALE_FACTOR);
+
+        let mut data = [0u8; CONTROL_MESSAGE_SIZE];
+        control(Mode::Default, 8)
+            .body_x(1.0)
+            .build()
+            .pack(&mut data)
+            .unwrap();
+        let mut reply = None;
+        robot
+            .receive(&data, 1_000_000, |bytes| {
+                reply = Some(RobotStatusMessage::unpack(bytes).unwrap())
+            })
+            .unwrap();
+        assert_eq!(reply.unwrap().sequence, 8);
+        assert!((robot.pose().x - 1.0).abs() < 1e-3);
+        assert!(robot.pose().y.abs() < 1e-3);
+        assert!(robot.battery_voltage() < 25.2);
+
+        robot.handle_halt(&HaltMessage::halt(Team::Yellow, 0), 1_000_000);
+        assert!(!robot.is_halted());
+        robot.handle_halt(&HaltMessage::halt(Team::Blue, 0), 1_000_000);
+        let [Some(RtpMessage::RobotStatus(halted)), ..] = replies(&mut robot, command, 1_500_000)
+        else {
+            panic!("expected a robot status message");
+        };
+        assert!(halted.halted);
+        assert_eq!(robot.velocity(), Vector3::zeros());
+        assert!((robot.pose().x - 1.0).abs() < 1e-3);
+        robot.handle_halt(&HaltMessage::resume(Team::Blue, 1), 1_500_000);
+        let [Some(RtpMessage::RobotStatus(resumed)), ..] = replies(&mut robot, command, 1_500_000)
+        else {
+            panic!("expected a robot status message");
+        };
+        assert!(!resumed.halted);
+        assert_eq!(robot.velocity().x, 1.0);
+
+        let mut envelope = [0u8; crate::envelope::MAX_ENVELOPE_SIZE];
+        let written = RtpMessage::from(GameStateMessage::new(Team::Blue, GameState::Stop, 0))
+            .encode(&mut envelope)
+            .unwrap();
+        robot.receive_team(&envelope[..written], 1_500_000).unwrap();
+        replies(
+            &mut robot,
+            control(Mode::Default, 9).body_x(3.0).build(),
+            1_500_000,
+        );
+        assert_eq!(robot.velocity().x, 1.5);
+
+        let mut other = control(Mode::Default, 9).build();
+        other.robot_id = 2;
+        other.pack(&mut data).unwrap();
+        robot
+            .receive(&data, 2_000_000, |_| panic!("robot 1 replied to robot 2"))
+            .unwrap();
+        assert_eq!(
+            robot.receive(&data[..4], 2_000_000, |_| ()),
+            Err(RtpError::BufferTooShort {
+                expected: CONTROL_MESSAGE_SIZE,
+                actual: 4
+            })
+        );
+    }
+
+    /// Test that the test modes reply with their messages
+    #[test]
+    fn test_simulated_robot_test_modes() {
+        let config = SimulatedRobotConfig {
+            imu_samples: 3,
+            kicker_pages: 2,
+            ..Default::default()
+        };
+        let mut robot = SimulatedRobot::new(Team::Blue, 1, config);
+
+        let imu = replies(&mut robot, control(Mode::ImuTest, 0).body_w(2.0).build(), 0);
+        let flags: [(bool, bool); 3] = core::array::from_fn(|index| match imu[index] {
+            Some(RtpMessage::ImuTest(message)) => {
+                assert_eq!(message.gyro_z, 2.0);
+                (message.first_message, message.last_message)
+            }
+            reply => panic!("expected an imu test message but found {:?}", reply),
+        });
+        assert_eq!(flags, [(true, false), (false, false), (false, true)]);
+        assert_eq!(imu[3], None);
+
+        replies(&mut robot, control(Mode::ReceiveBenchmark, 0).build(), 0);
+        let benchmark = replies(
+            &mut robot,
+            control(Mode::ReceiveBenchmark, 1).build(),
+            5_000,
+        );
+        assert_eq!(
+            benchmark[0],
+            Some(RtpMessage::RadioReceiveBenchmark(
+                RadioReceiveBenchmarkMessage {
+                    receive_time_ms: 5,
+                    received_packets: 2,
+                }
+            ))
+        );
+
+        let pages: [_; 3] = core::array::from_fn(|_| {
+            match replies(&mut robot, control(Mode::ProgramKicker, 0).build(), 10_000)[0] {
+                Some(RtpMessage::KickerProgram(message)) => (message.page, message.finished),
+                reply => panic!("expected a kicker program message but found {:?}", reply),
+            }
+        });
+        assert_eq!(pages, [(0, false), (1, true), (1, true)]);
+    }
+
+    /// Test that the robot kicks on break beam once its kicker is charged, losing the ball
+    #[test]
+    fn test_simulated_robot_kick() {
+        let mut robot = SimulatedRobot::new(Team::Blue, 1, SimulatedRobotConfig::default());
+        robot.set_ball_sense(true);
+
+        let kick = |sequence| {
+            control(Mode::KickerTest, sequence)
+                .trigger_mode(TriggerMode::OnBreakBeam)
+                .kick_strength(255)
+                .build()
+        };
+        replies(&mut robot, kick(0), 0);
+        let [Some(RtpMessage::KickerTesting(charging)), ..] =
+            replies(&mut robot, kick(1), 1_000_000)
+        else {
+            panic!("expected a kicker testing message");
+        };
+        assert!(charging.ball_sense && charging.kick_on_ball_sense);
+        assert_eq!(charging.voltage, 100);
+        assert_eq!(robot.kicks(), 0);
+
+        let [Some(RtpMessage::KickerTesting(kicked)), ..] = replies(&mut robot, kick(2), 2_000_000)
+        else {
+            panic!("expected a kicker testing message");
+        };
+        assert!(!kicked.ball_sense);
+        assert_eq!(kicked.voltage, 0);
+        assert_eq!(robot.kicks(), 1);
+    }
+}
@@ -0,0 +1,279 @@
+//!
+//! Checks every message against the golden vectors in `testdata/rtp_vectors.txt`.  The same
+//! vectors are checked against `rtp.hpp` by `test/rtp_vectors_test.cpp`.
+//!
+
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use ncomm_utils::packing::Packable;
+
+use robojackets_robocup_rtp::control_message::{Mode, ShootMode, TriggerMode};
+use robojackets_robocup_rtp::control_test_message::ControlTestMessage;
+use robojackets_robocup_rtp::imu_test_message::ImuTestMessage;
+use robojackets_robocup_rtp::kicker_program_message::KickerProgramMessage;
+use robojackets_robocup_rtp::kicker_testing::KickerTestingMessage;
+use robojackets_robocup_rtp::radio_benchmarks::{
+    RadioReceiveBenchmarkMessage, RadioSendBenchmarkMessage,
+};
+use robojackets_robocup_rtp::{
+    ControlMessage, GameState, GameStateMessage, HaltMessage, RobotStatusMessage, Team,
+};
+
+const VECTORS: &str = include_str!("../testdata/rtp_vectors.txt");
+
+/// Every message that must have at least one vector
+const MESSAGES: [&str; 10] = [
+    "ControlMessage",
+    "RobotStatusMessage",
+    "HaltMessage",
+    "GameStateMessage",
+    "ImuTestMessage",
+    "KickerProgramMessage",
+    "KickerTestingMessage",
+    "RadioReceiveBenchmarkMessage",
+    "RadioSendBenchmarkMessage",
+    "ControlTestMessage",
+];
+
+/// The fields of a vector that have not been used yet
+struct Fields<'a> {
+    line: usize,
+    fields: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Fields<'a> {
+    /// Remove a field from the vector and parse its value
+    fn take<T: FromStr>(&mut self, name: &str) -> T {
+        let index = self
+            .fields
+            .iter()
+            .position(|(field, _)| *field == name)
+            .unwrap_or_else(|| panic!("line {}: missing field {}", self.line, name));
+        let (_, value) = self.fields.remove(index);
+        value
+            .parse()
+            .unwrap_or_else(|_| panic!("line {}: invalid value {} for {}", self.line, value, name))
+    }
+
+    /// Remove a boolean field (written as 0 or 1) from the vector
+    fn take_bool(&mut self, name: &str) -> bool {
+        match self.take::<u8>(name) {
+            0 => false,
+            1 => true,
+            value => panic!("line {}: invalid bool {} for {}", self.line, value, name),
+        }
+    }
+
+    /// Remove an enum field (written as its raw value) from the vector
+    fn take_enum<T: TryFrom<u8, Error: Debug>>(&mut self, name: &str) -> T {
+        T::try_from(self.take::<u8>(name)).unwrap()
+    }
+
+    /// Remove an array field (written as comma separated elements) from the vector
+    fn take_array<T: FromStr, const N: usize>(&mut self, name: &str) -> [T; N] {
+        let value: String = self.take(name);
+        let elements: Vec<T> = value
+            .split(',')
+            .map(|element| {
+                element.parse().unwrap_or_else(|_| {
+                    panic!(
+                        "line {}: invalid element {} for {}",
+                        self.line, element, name
+                    )
+                })
+            })
+            .collect();
+        elements
+            .try_into()
+            .unwrap_or_else(|_| panic!("line {}: {} must have {} elements", self.line, name, N))
+    }
+
+    /// Check that every field of the vector was used
+    fn finish(self) {
+        assert!(
+            self.fields.is_empty(),
+            "line {}: unknown fields {:?}",
+            self.line,
+            self.fields
+        );
+    }
+}
+
+/// Check that a message packs into the expected bytes and unpacks back into the message
+fn check<M: Packable + Copy + PartialEq + Debug>(line: usize, message: M, expected: &[u8]) {
+    assert_eq!(M::len(), expected.len(), "line {}: wrong size", line);
+    let mut buffer = vec![0xA5; M::len()];
+    message.pack(&mut buffer).unwrap();
+    assert_eq!(
+        hex(&buffer),
+        hex(expected),
+        "line {}: {:?} packed incorrectly",
+        line,
+        message
+    );
+    assert_eq!(M::unpack(expected).unwrap(), message, "line {}", line);
+}
+
+/// Format bytes the way they are written in the vectors
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Test that every message packs into and unpacks from its golden vectors
+#[test]
+fn test_golden_vectors() {
+    let mut covered = BTreeSet::new();
+    for (index, text) in VECTORS.lines().enumerate() {
+        let line = index + 1;
+        let text = text.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+
+        let (description, bytes) = text
+            .split_once("=>")
+            .unwrap_or_else(|| panic!("line {}: missing =>", line));
+        let bytes: Vec<u8> = bytes
+            .split_whitespace()
+            .map(|byte| u8::from_str_radix(byte, 16).unwrap())
+            .collect();
+        let mut words = description.split_whitespace();
+        let name = words.next().unwrap();
+        let mut fields = Fields {
+            line,
+            fields: words
+                .map(|word| {
+                    word.split_once('=')
+                        .unwrap_or_else(|| panic!("line {}: invalid field {}", line, word))
+                })
+                .collect(),
+        };
+
+        match name {
+            "ControlMessage" => {
+                let message = ControlMessage {
+                    team: fields.take_enum::<Team>("team"),
+                    robot_id: fields.take("robot_id"),
+                    shoot_mode: fields.take_enum::<ShootMode>("shoot_mode"),
+                    trigger_mode: fields.take_enum::<TriggerMode>("trigger_mode"),
+                    body_x: fields.take("body_x"),
+                    body_y: fields.take("body_y"),
+                    body_w: fields.take("body_w"),
+                    dribbler_speed: fields.take("dribbler_speed"),
+                    kick_strength: fields.take("kick_strength"),
+                    role: fields.take("role"),
+                    mode: fields.take_enum::<Mode>("mode"),
+                    sequence: fields.take("sequence"),
+                };
+                check(line, message, &bytes);
+                assert_eq!(ControlMessage::unpack_strict(&bytes), Ok(message));
+            }
+            "RobotStatusMessage" => {
+                let message = RobotStatusMessage {
+                    team: fields.take_enum::<Team>("team"),
+                    robot_id: fields.take("robot_id"),
+                    ball_sense_status: fields.take_bool("ball_sense_status"),
+                    kick_status: fields.take_bool("kick_status"),
+                    kick_healthy: fields.take_bool("kick_healthy"),
+                    battery_voltage: fields.take("battery_voltage"),
+                    motor_errors: fields.take("motor_errors"),
+                    fpga_status: fields.take_bool("fpga_status"),
+                    halted: fields.take_bool("halted"),
+                    sequence: fields.take("sequence"),
+                };
+                check(line, message, &bytes);
+                assert_eq!(RobotStatusMessage::unpack_strict(&bytes), Ok(message));
+            }
+            "HaltMessage" => {
+                let message = HaltMessage {
+                    team: fields.take_enum::<Team>("team"),
+                    halt: fields.take_bool("halt"),
+                    sequence: fields.take("sequence"),
+                };
+                check(line, message, &bytes);
+                assert_eq!(HaltMessage::unpack_strict(&bytes), Ok(message));
+            }
+            "GameStateMessage" => {
+                let message = GameStateMessage {
+                    team: fields.take_enum::<Team>("team"),
+                    state: fields.take_enum::<GameState>("state"),
+                    sequence: fields.take("sequence"),
+                };
+                check(line, message, &bytes);
+                assert_eq!(GameStateMessage::unpack_strict(&bytes), Ok(message));
+            }
+            "ImuTestMessage" => check(
+                line,
+                ImuTestMessage {
+                    first_message: fields.take_bool("first_message"),
+                    last_message: fields.take_bool("last_message"),
+                    gyro_z: fields.take("gyro_z"),
+                    accel_x: fields.take("accel_x"),
+                    accel_y: fields.take("accel_y"),
+                },
+                &bytes,
+            ),
+            "KickerProgramMessage" => check(
+                line,
+                KickerProgramMessage {
+                    kick_on_breakbeam: fields.take_bool("kick_on_breakbeam"),
+                    finished: fields.take_bool("finished"),
+                    page: fields.take("page"),
+                },
+                &bytes,
+            ),
+            "KickerTestingMessage" => check(
+                line,
+                KickerTestingMessage {
+                    healthy: fields.take_bool("healthy"),
+                    ball_sense: fields.take_bool("ball_sense"),
+                    kicking: fields.take_bool("kicking"),
+                    kick_on_ball_sense: fields.take_bool("kick_on_ball_sense"),
+                    kick_immediately: fields.take_bool("kick_immediately"),
+                    voltage: fields.take("voltage"),
+                },
+                &bytes,
+            ),
+            "RadioReceiveBenchmarkMessage" => check(
+                line,
+                RadioReceiveBenchmarkMessage {
+                    receive_time_ms: fields.take("receive_time_ms"),
+                    received_packets: fields.take("received_packets"),
+                },
+                &bytes,
+            ),
+            "RadioSendBenchmarkMessage" => check(
+                line,
+                RadioSendBenchmarkMessage {
+                    acknowledged_packets: fields.take("acknowledged_packets"),
+                    sent_packets: fields.take("sent_packets"),
+                },
+                &bytes,
+            ),
+            "ControlTestMessage" => check(
+                line,
+                ControlTestMessage {
+                    gyro_z: fields.take("gyro_z"),
+                    accel_x: fields.take("accel_x"),
+                    accel_y: fields.take("accel_y"),
+                    motor_encoders: fields.take_array("motor_encoders"),
+                    delta: fields.take("delta"),
+                },
+                &bytes,
+            ),
+            _ => panic!("line {}: unknown message {}", line, name),
+        }
+        fields.finish();
+        covered.insert(name);
+    }
+
+    for message in MESSAGES {
+        assert!(covered.contains(message), "no vectors for {}", message);
+    }
+}
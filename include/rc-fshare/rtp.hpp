@@ -1,3 +1,6 @@
+// @generated by rtp-codegen from fshare-rust/schema/messages.toml.
+// Do not edit by hand, run `cargo run -p rtp-codegen` from fshare-rust instead.
+
 #pragma once
 
 #include <cstdint>
@@ -6,6 +9,31 @@
 
 namespace rtp {
 
+/**
+* The team the robots are on
+*/
+enum Team {
+    TEAM_BLUE = 0,
+    TEAM_YELLOW = 1,
+};
+
+/**
+* The trigger mode of the kicker
+*/
+enum TriggerMode {
+    TRIGGER_MODE_STAND_DOWN = 0,
+    TRIGGER_MODE_IMMEDIATE = 1,
+    TRIGGER_MODE_ON_BREAK_BEAM = 2,
+};
+
+/**
+* How the robot should kick the ball
+*/
+enum ShootMode {
+    SHOOT_MODE_KICK = 0,
+    SHOOT_MODE_CHIP = 1,
+};
+
 /**
 * Special enum to dictate the special mode the robot should be in.
 *
@@ -23,56 +51,117 @@ enum ControlMode {
     PROGRAM_KICKER = 5,
     KICKER_TEST = 6,
     FPGA_TEST = 7,
-}
+};
+
+/**
+* The state of the game called by the referee, which limits what the robots may do
+*/
+enum GameState {
+    GAME_STATE_HALT = 0,
+    GAME_STATE_STOP = 1,
+    GAME_STATE_RUNNING = 2,
+    GAME_STATE_BALL_PLACEMENT = 3,
+};
 
+/**
+* Sent from the base station to the robots to tell them what to do
+*/
 struct ControlMessage {
-    static constexpr float VELOCITY_SCALE_FACTOR = 1000.0f;
+    static constexpr float LINEAR_VELOCITY_SCALE_FACTOR = 4000.0f;
+    static constexpr float ANGULAR_VELOCITY_SCALE_FACTOR = 2000.0f;
+    [[deprecated("use LINEAR_VELOCITY_SCALE_FACTOR or ANGULAR_VELOCITY_SCALE_FACTOR")]]
+    static constexpr float VELOCITY_SCALE_FACTOR = 4000.0f;
 
-    // TODO: Determine the mapping for this
-    unsigned trigger_mode: 2;
+    // 0 -> Stand Down; 1 -> Immediate; 2 -> On Break Beam
+    unsigned trigger_mode : 2;
     // 0 -> Kick; 1 -> Chip
-    unsigned shoot_mode: 1;
+    unsigned shoot_mode : 1;
     // Id of the robot
-    unsigned robot_id: 4;
+    unsigned robot_id : 4;
     // Team of the robot (0: Blue) (1: Yellow)
-    unsigned team: 1;
-    // x velocity (body frame)
+    unsigned team : 1;
+    // x velocity (body frame) multiplied by LINEAR_VELOCITY_SCALE_FACTOR
     int16_t body_x;
-    // y velocity (body frame)
+    // y velocity (body frame) multiplied by LINEAR_VELOCITY_SCALE_FACTOR
     int16_t body_y;
-    // w velocity (body frame)
+    // w velocity (body frame) multiplied by ANGULAR_VELOCITY_SCALE_FACTOR
     int16_t body_w;
-    // speed of the dribbler
+    // Speed of the dribbler
     int8_t dribbler_speed;
-    // strenght of the kicker
+    // Strength of the kicker
     uint8_t kick_strength;
-    // Unused bytes
-    unsigned unused : 6;
+    // Special mode of the robot (see ControlMode)
+    unsigned mode : 6;
     // Robot role
     unsigned role : 2;
+    // Rolling sequence number echoed back in the RobotStatusMessage
+    uint8_t sequence;
 } __attribute__((packed));
 
+static_assert(sizeof(ControlMessage) == 11, "ControlMessage does not match the packed layout");
+
+/**
+* Sent from the robots back to the base station whenever they receive a ControlMessage
+*/
 struct RobotStatusMessage {
     static constexpr float BATTERY_SCALE_FACTOR = 0.09884f;
 
     // True if the kicker is healthy
-    unsigned kick_healthy: 1;
+    unsigned kick_healthy : 1;
     // True if is kicking
-    unsigned kick_status: 1;
+    unsigned kick_status : 1;
     // True if the robot has ball sense
-    unsigned ball_sense_status: 1;
+    unsigned ball_sense_status : 1;
     // Id of the robot
-    unsigned robot_id: 4;
+    unsigned robot_id : 4;
     // Team of the robot (0: Blue) (1: Yellow)
-    unsigned team: 1;
-    // Battery Voltage
+    unsigned team : 1;
+    // Battery voltage (multiply by BATTERY_SCALE_FACTOR for volts)
     uint8_t battery_voltage;
-    // Unused data
-    unsigned unused: 2;
+    // Unused bits
+    unsigned unused : 1;
+    // True while the robot is latched in a team halt
+    unsigned halted : 1;
     // FPGA is working
-    unsigned fpga_status: 1;
+    unsigned fpga_status : 1;
     // Error per motor
-    unsigned motor_errors: 5;
+    unsigned motor_errors : 5;
+    // Sequence number of the last ControlMessage received
+    uint8_t sequence;
 } __attribute__((packed));
 
+static_assert(sizeof(RobotStatusMessage) == 4, "RobotStatusMessage does not match the packed layout");
+
+/**
+* Sent from the base station to every robot of a team to halt them or let them resume
+*/
+struct HaltMessage {
+    // Unused bits
+    unsigned unused : 6;
+    // True to halt the robots, false to let them resume
+    unsigned halt : 1;
+    // Team of the robots (0: Blue) (1: Yellow)
+    unsigned team : 1;
+    // Rolling sequence number of the halt message
+    uint8_t sequence;
+} __attribute__((packed));
+
+static_assert(sizeof(HaltMessage) == 2, "HaltMessage does not match the packed layout");
+
+/**
+* Sent from the base station to every robot of a team whenever the referee changes the game state
+*/
+struct GameStateMessage {
+    // Unused bits
+    unsigned unused : 5;
+    // 0 -> Halt; 1 -> Stop; 2 -> Running; 3 -> Ball Placement
+    unsigned state : 2;
+    // Team of the robots (0: Blue) (1: Yellow)
+    unsigned team : 1;
+    // Rolling sequence number of the game state message
+    uint8_t sequence;
+} __attribute__((packed));
+
+static_assert(sizeof(GameStateMessage) == 2, "GameStateMessage does not match the packed layout");
+
 }  // namespace rtp
@@ -0,0 +1,280 @@
+// @generated by rtp-codegen from fshare-rust/schema/messages.toml.
+// Do not edit by hand, run `cargo run -p rtp-codegen` from fshare-rust instead.
+//
+// C ABI of the Rust rtp packers (fshare-rust/src/ffi.rs).  Link against the
+// robojackets-robocup-rtp-ffi static library (see RC_FSHARE_RUST_FFI in CMakeLists.txt).
+
+#pragma once
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// The operation succeeded
+#define RTP_OK 0
+// A pointer argument was null
+#define RTP_ERROR_NULL_POINTER (-1)
+// The buffer is too short for the message
+#define RTP_ERROR_BUFFER_TOO_SHORT (-2)
+// A field contains a value that does not correspond to any variant of its enum
+#define RTP_ERROR_INVALID_DISCRIMINANT (-3)
+// Any other error
+#define RTP_ERROR_OTHER (-4)
+
+// The size of a packed ControlMessage in Bytes
+#define RTP_CONTROL_MESSAGE_SIZE 11
+
+/**
+* Sent from the base station to the robots to tell them what to do
+*
+* Enums are passed as their raw value (see rtp.hpp).
+*/
+typedef struct RtpControlMessage {
+    // Team of the robot (0: Blue) (1: Yellow)
+    uint8_t team;
+    // Id of the robot
+    uint8_t robot_id;
+    // 0 -> Kick; 1 -> Chip
+    uint8_t shoot_mode;
+    // 0 -> Stand Down; 1 -> Immediate; 2 -> On Break Beam
+    uint8_t trigger_mode;
+    // x velocity (body frame) multiplied by LINEAR_VELOCITY_SCALE_FACTOR
+    int16_t body_x;
+    // y velocity (body frame) multiplied by LINEAR_VELOCITY_SCALE_FACTOR
+    int16_t body_y;
+    // w velocity (body frame) multiplied by ANGULAR_VELOCITY_SCALE_FACTOR
+    int16_t body_w;
+    // Speed of the dribbler
+    int8_t dribbler_speed;
+    // Strength of the kicker
+    uint8_t kick_strength;
+    // Robot role
+    uint8_t role;
+    // Special mode of the robot (see ControlMode)
+    uint8_t mode;
+    // Rolling sequence number echoed back in the RobotStatusMessage
+    uint8_t sequence;
+} RtpControlMessage;
+
+// Pack a ControlMessage into buffer, returning RTP_OK or a negative error code
+int32_t rtp_control_message_pack(const RtpControlMessage *message, uint8_t *buffer, size_t buffer_len);
+// Unpack a ControlMessage from data, returning RTP_OK or a negative error code
+int32_t rtp_control_message_unpack(const uint8_t *data, size_t data_len, RtpControlMessage *message);
+
+// The size of a packed RobotStatusMessage in Bytes
+#define RTP_ROBOT_STATUS_MESSAGE_SIZE 4
+
+/**
+* Sent from the robots back to the base station whenever they receive a ControlMessage
+*
+* Enums are passed as their raw value (see rtp.hpp).
+*/
+typedef struct RtpRobotStatusMessage {
+    // Team of the robot (0: Blue) (1: Yellow)
+    uint8_t team;
+    // Id of the robot
+    uint8_t robot_id;
+    // True if the robot has ball sense
+    bool ball_sense_status;
+    // True if is kicking
+    bool kick_status;
+    // True if the kicker is healthy
+    bool kick_healthy;
+    // Battery voltage (multiply by BATTERY_SCALE_FACTOR for volts)
+    uint8_t battery_voltage;
+    // Error per motor
+    uint8_t motor_errors;
+    // FPGA is working
+    bool fpga_status;
+    // True while the robot is latched in a team halt
+    bool halted;
+    // Sequence number of the last ControlMessage received
+    uint8_t sequence;
+} RtpRobotStatusMessage;
+
+// Pack a RobotStatusMessage into buffer, returning RTP_OK or a negative error code
+int32_t rtp_robot_status_message_pack(const RtpRobotStatusMessage *message, uint8_t *buffer, size_t buffer_len);
+// Unpack a RobotStatusMessage from data, returning RTP_OK or a negative error code
+int32_t rtp_robot_status_message_unpack(const uint8_t *data, size_t data_len, RtpRobotStatusMessage *message);
+
+// The size of a packed HaltMessage in Bytes
+#define RTP_HALT_MESSAGE_SIZE 2
+
+/**
+* Sent from the base station to every robot of a team to halt them or let them resume
+*
+* Enums are passed as their raw value (see rtp.hpp).
+*/
+typedef struct RtpHaltMessage {
+    // Team of the robots (0: Blue) (1: Yellow)
+    uint8_t team;
+    // True to halt the robots, false to let them resume
+    bool halt;
+    // Rolling sequence number of the halt message
+    uint8_t sequence;
+} RtpHaltMessage;
+
+// Pack a HaltMessage into buffer, returning RTP_OK or a negative error code
+int32_t rtp_halt_message_pack(const RtpHaltMessage *message, uint8_t *buffer, size_t buffer_len);
+// Unpack a HaltMessage from data, returning RTP_OK or a negative error code
+int32_t rtp_halt_message_unpack(const uint8_t *data, size_t data_len, RtpHaltMessage *message);
+
+// The size of a packed GameStateMessage in Bytes
+#define RTP_GAME_STATE_MESSAGE_SIZE 2
+
+/**
+* Sent from the base station to every robot of a team whenever the referee changes the game state
+*
+* Enums are passed as their raw value (see rtp.hpp).
+*/
+typedef struct RtpGameStateMessage {
+    // Team of the robots (0: Blue) (1: Yellow)
+    uint8_t team;
+    // 0 -> Halt; 1 -> Stop; 2 -> Running; 3 -> Ball Placement
+    uint8_t state;
+    // Rolling sequence number of the game state message
+    uint8_t sequence;
+} RtpGameStateMessage;
+
+// Pack a GameStateMessage into buffer, returning RTP_OK or a negative error code
+int32_t rtp_game_state_message_pack(const RtpGameStateMessage *message, uint8_t *buffer, size_t buffer_len);
+// Unpack a GameStateMessage from data, returning RTP_OK or a negative error code
+int32_t rtp_game_state_message_unpack(const uint8_t *data, size_t data_len, RtpGameStateMessage *message);
+
+// The size of a packed ImuTestMessage in Bytes
+#define RTP_IMU_TEST_MESSAGE_SIZE 13
+
+/**
+* Sent back from the robot while it is testing its IMU
+*/
+typedef struct RtpImuTestMessage {
+    // Is this the first IMU test message
+    bool first_message;
+    // Is this the last IMU test message
+    bool last_message;
+    // The z-gyro value obtained from the IMU
+    float gyro_z;
+    // The x-accelerometer value obtained from the IMU
+    float accel_x;
+    // The y-accelerometer value obtained from the IMU
+    float accel_y;
+} RtpImuTestMessage;
+
+// Pack a ImuTestMessage into buffer, returning RTP_OK or a negative error code
+int32_t rtp_imu_test_message_pack(const RtpImuTestMessage *message, uint8_t *buffer, size_t buffer_len);
+// Unpack a ImuTestMessage from data, returning RTP_OK or a negative error code
+int32_t rtp_imu_test_message_unpack(const uint8_t *data, size_t data_len, RtpImuTestMessage *message);
+
+// The size of a packed KickerProgramMessage in Bytes
+#define RTP_KICKER_PROGRAM_MESSAGE_SIZE 5
+
+/**
+* Sent from the robot when the kicker is being programmed
+*/
+typedef struct RtpKickerProgramMessage {
+    // Is the kicker being programmed with kick on breakbeam
+    bool kick_on_breakbeam;
+    // Is the kicker finished programming
+    bool finished;
+    // The current page being programmed
+    uint32_t page;
+} RtpKickerProgramMessage;
+
+// Pack a KickerProgramMessage into buffer, returning RTP_OK or a negative error code
+int32_t rtp_kicker_program_message_pack(const RtpKickerProgramMessage *message, uint8_t *buffer, size_t buffer_len);
+// Unpack a KickerProgramMessage from data, returning RTP_OK or a negative error code
+int32_t rtp_kicker_program_message_unpack(const uint8_t *data, size_t data_len, RtpKickerProgramMessage *message);
+
+// The size of a packed KickerTestingMessage in Bytes
+#define RTP_KICKER_TESTING_MESSAGE_SIZE 2
+
+/**
+* Sent from the robot while testing the kicker
+*/
+typedef struct RtpKickerTestingMessage {
+    // Is the kicker healthy
+    bool healthy;
+    // Does the kicker have ball sense
+    bool ball_sense;
+    // Is the kicker kicking (otherwise it is chipping)
+    bool kicking;
+    // Should the kicker activate on ball sense
+    bool kick_on_ball_sense;
+    // Should the kicker be kicking immediately
+    bool kick_immediately;
+    // The current voltage of the kicker
+    uint8_t voltage;
+} RtpKickerTestingMessage;
+
+// Pack a KickerTestingMessage into buffer, returning RTP_OK or a negative error code
+int32_t rtp_kicker_testing_message_pack(const RtpKickerTestingMessage *message, uint8_t *buffer, size_t buffer_len);
+// Unpack a KickerTestingMessage from data, returning RTP_OK or a negative error code
+int32_t rtp_kicker_testing_message_unpack(const uint8_t *data, size_t data_len, RtpKickerTestingMessage *message);
+
+// The size of a packed RadioReceiveBenchmarkMessage in Bytes
+#define RTP_RADIO_RECEIVE_BENCHMARK_MESSAGE_SIZE 8
+
+/**
+* Sent from the robot when benchmarking the radio's receiving capabilities
+*/
+typedef struct RtpRadioReceiveBenchmarkMessage {
+    // The amount of time the radio was receiving for (ms)
+    uint32_t receive_time_ms;
+    // The total number of packets received during the time interval
+    uint32_t received_packets;
+} RtpRadioReceiveBenchmarkMessage;
+
+// Pack a RadioReceiveBenchmarkMessage into buffer, returning RTP_OK or a negative error code
+int32_t rtp_radio_receive_benchmark_message_pack(const RtpRadioReceiveBenchmarkMessage *message, uint8_t *buffer, size_t buffer_len);
+// Unpack a RadioReceiveBenchmarkMessage from data, returning RTP_OK or a negative error code
+int32_t rtp_radio_receive_benchmark_message_unpack(const uint8_t *data, size_t data_len, RtpRadioReceiveBenchmarkMessage *message);
+
+// The size of a packed RadioSendBenchmarkMessage in Bytes
+#define RTP_RADIO_SEND_BENCHMARK_MESSAGE_SIZE 8
+
+/**
+* Sent from the robot when a radio send benchmark is complete
+*/
+typedef struct RtpRadioSendBenchmarkMessage {
+    // The number of packets successfully sent (and acknowledged) according to the robot
+    uint32_t acknowledged_packets;
+    // The total number of packets sent by the robot
+    uint32_t sent_packets;
+} RtpRadioSendBenchmarkMessage;
+
+// Pack a RadioSendBenchmarkMessage into buffer, returning RTP_OK or a negative error code
+int32_t rtp_radio_send_benchmark_message_pack(const RtpRadioSendBenchmarkMessage *message, uint8_t *buffer, size_t buffer_len);
+// Unpack a RadioSendBenchmarkMessage from data, returning RTP_OK or a negative error code
+int32_t rtp_radio_send_benchmark_message_unpack(const uint8_t *data, size_t data_len, RtpRadioSendBenchmarkMessage *message);
+
+// The size of a packed ControlTestMessage in Bytes
+#define RTP_CONTROL_TEST_MESSAGE_SIZE 30
+
+/**
+* Sent from the robot with the sensor measurements used for the current control step
+*/
+typedef struct RtpControlTestMessage {
+    // The z-gyro value obtained from the IMU
+    float gyro_z;
+    // The x-accelerometer value obtained from the IMU
+    float accel_x;
+    // The y-accelerometer value obtained from the IMU
+    float accel_y;
+    // The encoder velocities obtained from the FPGA
+    float motor_encoders[4];
+    // The time from the last control test message to this message (us, saturating at `u16::MAX`)
+    uint16_t delta;
+} RtpControlTestMessage;
+
+// Pack a ControlTestMessage into buffer, returning RTP_OK or a negative error code
+int32_t rtp_control_test_message_pack(const RtpControlTestMessage *message, uint8_t *buffer, size_t buffer_len);
+// Unpack a ControlTestMessage from data, returning RTP_OK or a negative error code
+int32_t rtp_control_test_message_unpack(const uint8_t *data, size_t data_len, RtpControlTestMessage *message);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif
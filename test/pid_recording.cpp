@@ -0,0 +1,53 @@
+#include "rc-fshare/pid.hpp"
+
+#include <cmath>
+#include <cstdint>
+#include <cstdio>
+#include <cstring>
+
+// Records the outputs of Pid for a fixed sequence of operations so the Rust
+// port (fshare-rust/src/pid.rs) can be checked against this implementation.
+//
+// Each output is printed as the bit pattern of the returned float.
+
+static void record(Pid& pid, float err) {
+    float out = pid.run(err);
+    uint32_t bits;
+    std::memcpy(&bits, &out, sizeof(bits));
+    std::printf("0x%08x,\n", bits);
+}
+
+int main() {
+    Pid pid(1.5f, 0.2f, 0.05f, 4, 0.3f);
+
+    const float errors[] = {1.0f, 0.5f, -0.25f, 2.0f, 3.5f, -1.0f, 0.0f, 0.75f};
+    for (float err : errors) record(pid, err);
+
+    pid.set_saturated(true);
+    record(pid, 4.0f);
+    record(pid, 4.0f);
+    pid.set_saturated(false);
+    record(pid, -2.0f);
+
+    record(pid, NAN);
+
+    pid.setWindup(3);
+    record(pid, 1.25f);
+    record(pid, -0.5f);
+
+    pid.clearWindup();
+    record(pid, 0.1f);
+
+    pid.setWindup(0);
+    record(pid, 2.0f);
+    record(pid, 2.0f);
+
+    pid.reset();
+    record(pid, -3.0f);
+    pid.setWindup(2);
+    record(pid, 1.0f);
+    record(pid, 1.0f);
+    record(pid, 1.0f);
+
+    return 0;
+}
@@ -0,0 +1,89 @@
+#include "rc-fshare/rtp.hpp"
+#include "rc-fshare/rtp_ffi.h"
+
+#include <cstring>
+#include <iostream>
+
+// Checks that the Rust packers (through rtp_ffi.h) produce the same bytes as the
+// rtp.hpp bitfield structs
+int main() {
+    int failures = 0;
+    auto check = [&failures](bool ok, const char* what) {
+        if (!ok) {
+            std::cerr << "FAILED: " << what << std::endl;
+            failures++;
+        }
+    };
+
+    RtpControlMessage control = {};
+    control.team = 1;
+    control.robot_id = 3;
+    control.shoot_mode = rtp::SHOOT_MODE_CHIP;
+    control.trigger_mode = rtp::TRIGGER_MODE_ON_BREAK_BEAM;
+    control.body_x = 1500;
+    control.body_y = -250;
+    control.body_w = 3141;
+    control.dribbler_speed = -7;
+    control.kick_strength = 200;
+    control.role = 2;
+    control.mode = rtp::KICKER_TEST;
+    control.sequence = 42;
+
+    uint8_t buffer[RTP_CONTROL_MESSAGE_SIZE];
+    check(rtp_control_message_pack(&control, buffer, sizeof(buffer)) == RTP_OK,
+          "pack ControlMessage");
+
+    rtp::ControlMessage bitfields;
+    std::memcpy(&bitfields, buffer, sizeof(bitfields));
+    check(bitfields.team == control.team && bitfields.robot_id == control.robot_id &&
+              bitfields.shoot_mode == control.shoot_mode &&
+              bitfields.trigger_mode == control.trigger_mode &&
+              bitfields.body_x == control.body_x && bitfields.body_y == control.body_y &&
+              bitfields.body_w == control.body_w &&
+              bitfields.dribbler_speed == control.dribbler_speed &&
+              bitfields.kick_strength == control.kick_strength &&
+              bitfields.role == control.role && bitfields.mode == control.mode &&
+              bitfields.sequence == control.sequence,
+          "ControlMessage matches rtp.hpp");
+
+    RtpControlMessage unpacked = {};
+    check(rtp_control_message_unpack(buffer, sizeof(buffer), &unpacked) == RTP_OK,
+          "unpack ControlMessage");
+    check(std::memcmp(&unpacked, &control, sizeof(control)) == 0,
+          "ControlMessage round trip");
+
+    rtp::RobotStatusMessage status = {};
+    status.team = 0;
+    status.robot_id = 5;
+    status.ball_sense_status = 1;
+    status.kick_status = 0;
+    status.kick_healthy = 1;
+    status.battery_voltage = 170;
+    status.motor_errors = 0b10101;
+    status.fpga_status = 1;
+    status.sequence = 42;
+
+    RtpRobotStatusMessage status_unpacked = {};
+    check(rtp_robot_status_message_unpack(reinterpret_cast<const uint8_t*>(&status),
+                                          sizeof(status), &status_unpacked) == RTP_OK,
+          "unpack RobotStatusMessage");
+    check(status_unpacked.robot_id == 5 && status_unpacked.ball_sense_status &&
+              !status_unpacked.kick_status && status_unpacked.kick_healthy &&
+              status_unpacked.battery_voltage == 170 &&
+              status_unpacked.motor_errors == 0b10101 && status_unpacked.fpga_status &&
+              status_unpacked.sequence == 42,
+          "RobotStatusMessage matches rtp.hpp");
+
+    check(rtp_control_message_pack(&control, buffer, sizeof(buffer) - 1) ==
+              RTP_ERROR_BUFFER_TOO_SHORT,
+          "short buffer is rejected");
+    control.mode = 63;
+    check(rtp_control_message_pack(&control, buffer, sizeof(buffer)) ==
+              RTP_ERROR_INVALID_DISCRIMINANT,
+          "invalid mode is rejected");
+
+    if (failures == 0) {
+        std::cout << "rtp ffi matches rtp.hpp" << std::endl;
+    }
+    return failures == 0 ? 0 : 1;
+}
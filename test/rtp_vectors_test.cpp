@@ -0,0 +1,368 @@
+#include "rc-fshare/rtp.hpp"
+#ifdef RC_FSHARE_RUST_FFI
+#include "rc-fshare/rtp_ffi.h"
+#endif
+
+#include <cstdlib>
+#include <cstring>
+#include <fstream>
+#include <functional>
+#include <iomanip>
+#include <iostream>
+#include <map>
+#include <sstream>
+#include <string>
+#include <vector>
+
+// Checks the messages against the golden vectors in fshare-rust/testdata/rtp_vectors.txt
+// (the same vectors are checked by fshare-rust/tests/vectors.rs).
+//
+// The rtp.hpp structs are always checked; every other message is only checked through the
+// Rust packers (rtp_ffi.h) when built with RC_FSHARE_RUST_FFI.
+
+struct Vector {
+    int line;
+    std::string message;
+    std::map<std::string, std::vector<double>> fields;
+    std::vector<uint8_t> bytes;
+};
+
+// A field of a message, accessed as a double so every field can be compared the same way
+template <typename T>
+struct Field {
+    const char* name;
+    size_t count;
+    double (*get)(const T&, size_t);
+    void (*set)(T&, size_t, double);
+};
+
+#define FIELD(T, field)                                                     \
+    Field<T> {                                                              \
+        #field, 1, [](const T& m, size_t) -> double { return m.field; },    \
+            [](T& m, size_t, double value) { m.field = value; }             \
+    }
+
+#define ARRAY_FIELD(T, field, n)                                             \
+    Field<T> {                                                               \
+        #field, n, [](const T& m, size_t i) -> double { return m.field[i]; }, \
+            [](T& m, size_t i, double value) { m.field[i] = value; }         \
+    }
+
+std::string hex(const std::vector<uint8_t>& bytes) {
+    std::ostringstream out;
+    for (size_t i = 0; i < bytes.size(); i++) {
+        out << (i ? " " : "") << std::hex << std::setw(2) << std::setfill('0')
+            << static_cast<int>(bytes[i]);
+    }
+    return out.str();
+}
+
+bool fail(const Vector& vector, const std::string& source, const std::string& what) {
+    std::cerr << "line " << vector.line << " (" << vector.message << ", " << source
+              << "): " << what << std::endl;
+    return false;
+}
+
+// Packs the fields of the vector with `pack` and unpacks its bytes with `unpack`
+template <typename T>
+bool check(const Vector& vector, const std::string& source, const std::vector<Field<T>>& fields,
+           std::function<int32_t(const T&, uint8_t*, size_t)> pack,
+           std::function<int32_t(const uint8_t*, size_t, T&)> unpack) {
+    if (vector.fields.size() != fields.size()) {
+        return fail(vector, source, "wrong number of fields");
+    }
+
+    T message;
+    std::memset(&message, 0, sizeof(message));
+    for (const Field<T>& field : fields) {
+        auto value = vector.fields.find(field.name);
+        if (value == vector.fields.end() || value->second.size() != field.count) {
+            return fail(vector, source, std::string("missing field ") + field.name);
+        }
+        for (size_t i = 0; i < field.count; i++) {
+            field.set(message, i, value->second[i]);
+        }
+    }
+
+    std::vector<uint8_t> packed(vector.bytes.size(), 0xA5);
+    if (pack(message, packed.data(), packed.size()) != 0) {
+        return fail(vector, source, "pack failed");
+    }
+    if (packed != vector.bytes) {
+        return fail(vector, source, "packed " + hex(packed) + " expected " + hex(vector.bytes));
+    }
+
+    T unpacked;
+    std::memset(&unpacked, 0, sizeof(unpacked));
+    if (unpack(vector.bytes.data(), vector.bytes.size(), unpacked) != 0) {
+        return fail(vector, source, "unpack failed");
+    }
+    for (const Field<T>& field : fields) {
+        for (size_t i = 0; i < field.count; i++) {
+            if (field.get(unpacked, i) != vector.fields.at(field.name)[i]) {
+                return fail(vector, source, std::string("unpacked wrong ") + field.name);
+            }
+        }
+    }
+    return true;
+}
+
+// Checks an rtp.hpp struct by copying it to and from the packed bytes
+template <typename T>
+std::function<bool(const Vector&)> bitfields(std::vector<Field<T>> fields) {
+    return [fields](const Vector& vector) {
+        return check<T>(
+            vector, "rtp.hpp", fields,
+            [](const T& message, uint8_t* buffer, size_t len) -> int32_t {
+                if (len != sizeof(T)) return -1;
+                std::memcpy(buffer, &message, sizeof(T));
+                return 0;
+            },
+            [](const uint8_t* data, size_t len, T& message) -> int32_t {
+                if (len != sizeof(T)) return -1;
+                std::memcpy(&message, data, sizeof(T));
+                return 0;
+            });
+    };
+}
+
+#ifdef RC_FSHARE_RUST_FFI
+// Checks a message through the Rust packers
+template <typename T>
+std::function<bool(const Vector&)> ffi(std::vector<Field<T>> fields,
+                                       int32_t (*pack)(const T*, uint8_t*, size_t),
+                                       int32_t (*unpack)(const uint8_t*, size_t, T*)) {
+    return [fields, pack, unpack](const Vector& vector) {
+        return check<T>(
+            vector, "rtp_ffi.h", fields,
+            [pack](const T& message, uint8_t* buffer, size_t len) {
+                return pack(&message, buffer, len);
+            },
+            [unpack](const uint8_t* data, size_t len, T& message) {
+                return unpack(data, len, &message);
+            });
+    };
+}
+#endif
+
+std::map<std::string, std::vector<std::function<bool(const Vector&)>>> checkers() {
+    std::map<std::string, std::vector<std::function<bool(const Vector&)>>> checkers;
+
+    using rtp::ControlMessage;
+    using rtp::GameStateMessage;
+    using rtp::HaltMessage;
+    using rtp::RobotStatusMessage;
+    checkers["ControlMessage"].push_back(bitfields<ControlMessage>({
+        FIELD(ControlMessage, team),
+        FIELD(ControlMessage, robot_id),
+        FIELD(ControlMessage, shoot_mode),
+        FIELD(ControlMessage, trigger_mode),
+        FIELD(ControlMessage, body_x),
+        FIELD(ControlMessage, body_y),
+        FIELD(ControlMessage, body_w),
+        FIELD(ControlMessage, dribbler_speed),
+        FIELD(ControlMessage, kick_strength),
+        FIELD(ControlMessage, role),
+        FIELD(ControlMessage, mode),
+        FIELD(ControlMessage, sequence),
+    }));
+    checkers["RobotStatusMessage"].push_back(bitfields<RobotStatusMessage>({
+        FIELD(RobotStatusMessage, team),
+        FIELD(RobotStatusMessage, robot_id),
+        FIELD(RobotStatusMessage, ball_sense_status),
+        FIELD(RobotStatusMessage, kick_status),
+        FIELD(RobotStatusMessage, kick_healthy),
+        FIELD(RobotStatusMessage, battery_voltage),
+        FIELD(RobotStatusMessage, motor_errors),
+        FIELD(RobotStatusMessage, fpga_status),
+        FIELD(RobotStatusMessage, halted),
+        FIELD(RobotStatusMessage, sequence),
+    }));
+    checkers["HaltMessage"].push_back(bitfields<HaltMessage>({
+        FIELD(HaltMessage, team),
+        FIELD(HaltMessage, halt),
+        FIELD(HaltMessage, sequence),
+    }));
+    checkers["GameStateMessage"].push_back(bitfields<GameStateMessage>({
+        FIELD(GameStateMessage, team),
+        FIELD(GameStateMessage, state),
+        FIELD(GameStateMessage, sequence),
+    }));
+
+#ifdef RC_FSHARE_RUST_FFI
+    checkers["ControlMessage"].push_back(ffi<RtpControlMessage>(
+        {
+            FIELD(RtpControlMessage, team),
+            FIELD(RtpControlMessage, robot_id),
+            FIELD(RtpControlMessage, shoot_mode),
+            FIELD(RtpControlMessage, trigger_mode),
+            FIELD(RtpControlMessage, body_x),
+            FIELD(RtpControlMessage, body_y),
+            FIELD(RtpControlMessage, body_w),
+            FIELD(RtpControlMessage, dribbler_speed),
+            FIELD(RtpControlMessage, kick_strength),
+            FIELD(RtpControlMessage, role),
+            FIELD(RtpControlMessage, mode),
+            FIELD(RtpControlMessage, sequence),
+        },
+        rtp_control_message_pack, rtp_control_message_unpack));
+    checkers["RobotStatusMessage"].push_back(ffi<RtpRobotStatusMessage>(
+        {
+            FIELD(RtpRobotStatusMessage, team),
+            FIELD(RtpRobotStatusMessage, robot_id),
+            FIELD(RtpRobotStatusMessage, ball_sense_status),
+            FIELD(RtpRobotStatusMessage, kick_status),
+            FIELD(RtpRobotStatusMessage, kick_healthy),
+            FIELD(RtpRobotStatusMessage, battery_voltage),
+            FIELD(RtpRobotStatusMessage, motor_errors),
+            FIELD(RtpRobotStatusMessage, fpga_status),
+            FIELD(RtpRobotStatusMessage, halted),
+            FIELD(RtpRobotStatusMessage, sequence),
+        },
+        rtp_robot_status_message_pack, rtp_robot_status_message_unpack));
+    checkers["HaltMessage"].push_back(ffi<RtpHaltMessage>(
+        {
+            FIELD(RtpHaltMessage, team),
+            FIELD(RtpHaltMessage, halt),
+            FIELD(RtpHaltMessage, sequence),
+        },
+        rtp_halt_message_pack, rtp_halt_message_unpack));
+    checkers["GameStateMessage"].push_back(ffi<RtpGameStateMessage>(
+        {
+            FIELD(RtpGameStateMessage, team),
+            FIELD(RtpGameStateMessage, state),
+            FIELD(RtpGameStateMessage, sequence),
+        },
+        rtp_game_state_message_pack, rtp_game_state_message_unpack));
+    checkers["ImuTestMessage"].push_back(ffi<RtpImuTestMessage>(
+        {
+            FIELD(RtpImuTestMessage, first_message),
+            FIELD(RtpImuTestMessage, last_message),
+            FIELD(RtpImuTestMessage, gyro_z),
+            FIELD(RtpImuTestMessage, accel_x),
+            FIELD(RtpImuTestMessage, accel_y),
+        },
+        rtp_imu_test_message_pack, rtp_imu_test_message_unpack));
+    checkers["KickerProgramMessage"].push_back(ffi<RtpKickerProgramMessage>(
+        {
+            FIELD(RtpKickerProgramMessage, kick_on_breakbeam),
+            FIELD(RtpKickerProgramMessage, finished),
+            FIELD(RtpKickerProgramMessage, page),
+        },
+        rtp_kicker_program_message_pack, rtp_kicker_program_message_unpack));
+    checkers["KickerTestingMessage"].push_back(ffi<RtpKickerTestingMessage>(
+        {
+            FIELD(RtpKickerTestingMessage, healthy),
+            FIELD(RtpKickerTestingMessage, ball_sense),
+            FIELD(RtpKickerTestingMessage, kicking),
+            FIELD(RtpKickerTestingMessage, kick_on_ball_sense),
+            FIELD(RtpKickerTestingMessage, kick_immediately),
+            FIELD(RtpKickerTestingMessage, voltage),
+        },
+        rtp_kicker_testing_message_pack, rtp_kicker_testing_message_unpack));
+    checkers["RadioReceiveBenchmarkMessage"].push_back(ffi<RtpRadioReceiveBenchmarkMessage>(
+        {
+            FIELD(RtpRadioReceiveBenchmarkMessage, receive_time_ms),
+            FIELD(RtpRadioReceiveBenchmarkMessage, received_packets),
+        },
+        rtp_radio_receive_benchmark_message_pack, rtp_radio_receive_benchmark_message_unpack));
+    checkers["RadioSendBenchmarkMessage"].push_back(ffi<RtpRadioSendBenchmarkMessage>(
+        {
+            FIELD(RtpRadioSendBenchmarkMessage, acknowledged_packets),
+            FIELD(RtpRadioSendBenchmarkMessage, sent_packets),
+        },
+        rtp_radio_send_benchmark_message_pack, rtp_radio_send_benchmark_message_unpack));
+    checkers["ControlTestMessage"].push_back(ffi<RtpControlTestMessage>(
+        {
+            FIELD(RtpControlTestMessage, gyro_z),
+            FIELD(RtpControlTestMessage, accel_x),
+            FIELD(RtpControlTestMessage, accel_y),
+            ARRAY_FIELD(RtpControlTestMessage, motor_encoders, 4),
+            FIELD(RtpControlTestMessage, delta),
+        },
+        rtp_control_test_message_pack, rtp_control_test_message_unpack));
+#endif
+
+    return checkers;
+}
+
+bool parse(const std::string& text, int line, Vector& vector) {
+    size_t arrow = text.find("=>");
+    if (arrow == std::string::npos) return false;
+
+    vector.line = line;
+    std::istringstream description(text.substr(0, arrow));
+    description >> vector.message;
+    std::string word;
+    while (description >> word) {
+        size_t equals = word.find('=');
+        if (equals == std::string::npos) return false;
+        std::vector<double>& values = vector.fields[word.substr(0, equals)];
+        std::istringstream elements(word.substr(equals + 1));
+        std::string element;
+        while (std::getline(elements, element, ',')) {
+            values.push_back(std::strtod(element.c_str(), nullptr));
+        }
+    }
+
+    std::istringstream bytes(text.substr(arrow + 2));
+    std::string byte;
+    while (bytes >> byte) {
+        vector.bytes.push_back(static_cast<uint8_t>(std::strtoul(byte.c_str(), nullptr, 16)));
+    }
+    return true;
+}
+
+int main(int argc, char** argv) {
+#ifdef RTP_VECTORS_PATH
+    const char* path = argc > 1 ? argv[1] : RTP_VECTORS_PATH;
+#else
+    if (argc < 2) {
+        std::cerr << "usage: " << argv[0] << " <rtp_vectors.txt>" << std::endl;
+        return 2;
+    }
+    const char* path = argv[1];
+#endif
+
+    std::ifstream file(path);
+    if (!file) {
+        std::cerr << "failed to open " << path << std::endl;
+        return 2;
+    }
+
+    auto all_checkers = checkers();
+    std::map<std::string, int> checked;
+    int failures = 0;
+    std::string text;
+    for (int line = 1; std::getline(file, text); line++) {
+        size_t start = text.find_first_not_of(" \t\r");
+        if (start == std::string::npos || text[start] == '#') continue;
+
+        Vector vector;
+        if (!parse(text, line, vector)) {
+            std::cerr << "line " << line << ": invalid vector" << std::endl;
+            failures++;
+            continue;
+        }
+        int& count = checked[vector.message];
+        for (const auto& checker : all_checkers[vector.message]) {
+            failures += checker(vector) ? 0 : 1;
+            count++;
+        }
+    }
+
+    for (const auto& message : checked) {
+        if (message.second == 0) {
+            std::cout << "skipped " << message.first
+                      << " (only available through rtp_ffi.h)" << std::endl;
+        } else {
+            std::cout << "checked " << message.second << " " << message.first << " vectors"
+                      << std::endl;
+        }
+    }
+    if (failures) {
+        std::cerr << failures << " vectors failed" << std::endl;
+        return 1;
+    }
+    return 0;
+}